@@ -0,0 +1,627 @@
+use futures_util::{Stream, StreamExt};
+use reqwest::multipart::{Form, Part};
+use reqwest::{Response, StatusCode, Url};
+use serde::de::DeserializeOwned;
+
+use crate::error::ClientError;
+use crate::events::EventStream;
+use crate::models::{
+    BoundingBox, Capabilities, DiagnosticsQuery, DiagnosticsResponse, EventsQuery, HealthResponse,
+    HistoryQuery, Job, MonitorSupervision, MonitoredRegion, OcrResult, OcrSettings,
+    RecordingSession, Region, RegionJobRequest, ScreenshotQuery, SetRegionRequest,
+    StartRecordingRequest, StatusResponse, TextChange,
+};
+use crate::DisplayInfo;
+
+/// Result of a client call
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+/// Encoded screenshot with the frame area it shows
+#[derive(Debug, Clone)]
+pub struct Screenshot {
+    /// Encoded image
+    pub data: Vec<u8>,
+
+    /// MIME type of the image, e.g. `image/png`
+    pub content_type: String,
+
+    /// Captured area of the display frame in physical pixels
+    pub capture_region: Option<BoundingBox>,
+
+    /// Physical pixels per logical pixel of the display
+    pub scale_factor: Option<f64>,
+}
+
+/// Async client of the Screen Text Reader HTTP API
+///
+/// Cloning is cheap, clones share the connection pool.
+#[derive(Debug, Clone)]
+pub struct Client {
+    /// HTTP client sending the requests
+    http: reqwest::Client,
+
+    /// URL of the server, API paths are appended to it
+    base_url: Url,
+}
+
+impl Client {
+    /// Create a client of the server at `base_url`, e.g. `http://127.0.0.1:8080`
+    pub fn new(base_url: &str) -> Result<Self> {
+        Self::with_http_client(base_url, reqwest::Client::new())
+    }
+
+    /// Create a client sending requests through a configured HTTP client
+    ///
+    /// Use this to set timeouts, proxies or default headers.
+    pub fn with_http_client(base_url: &str, http: reqwest::Client) -> Result<Self> {
+        let base_url = Url::parse(base_url)
+            .map_err(|e| ClientError::InvalidUrl(format!("{}: {}", base_url, e)))?;
+        if base_url.cannot_be_a_base() {
+            return Err(ClientError::InvalidUrl(base_url.to_string()));
+        }
+
+        Ok(Self { http, base_url })
+    }
+
+    /// Get information about all available screens
+    pub async fn screens(&self) -> Result<Vec<DisplayInfo>> {
+        self.get_json(&["api", "screens"]).await
+    }
+
+    /// Take a screenshot of a display
+    pub async fn screenshot(&self, query: &ScreenshotQuery) -> Result<Screenshot> {
+        self.get_screenshot(&["api", "screenshot"], query).await
+    }
+
+    /// Take a small preview screenshot, JPEG encoded unless a format is given
+    pub async fn thumbnail(&self, query: &ScreenshotQuery) -> Result<Screenshot> {
+        self.get_screenshot(&["api", "screenshot", "thumbnail"], query)
+            .await
+    }
+
+    /// Get the latest PNG screenshot of the monitored region, if there is one
+    pub async fn latest_screenshot(&self) -> Result<Option<Vec<u8>>> {
+        let response = self
+            .http
+            .get(self.endpoint(&["api", "latest-screenshot"]))
+            .send()
+            .await?;
+        let response = check(response).await?;
+
+        if response.status() == StatusCode::NO_CONTENT {
+            return Ok(None);
+        }
+        Ok(Some(response.bytes().await?.to_vec()))
+    }
+
+    /// Set the region to monitor, replacing the default region
+    pub async fn set_region(&self, region: &Region) -> Result<Region> {
+        let request = SetRegionRequest {
+            region: region.clone(),
+        };
+        let response = self
+            .http
+            .post(self.endpoint(&["api", "region"]))
+            .json(&request)
+            .send()
+            .await?;
+
+        json(response).await
+    }
+
+    /// List all monitored regions
+    pub async fn regions(&self) -> Result<Vec<MonitoredRegion>> {
+        self.get_json(&["api", "regions"]).await
+    }
+
+    /// Add a monitored region, replacing any region with the same id
+    pub async fn add_region(&self, region: &MonitoredRegion) -> Result<MonitoredRegion> {
+        let response = self
+            .http
+            .post(self.endpoint(&["api", "regions"]))
+            .json(region)
+            .send()
+            .await?;
+
+        json(response).await
+    }
+
+    /// Stop monitoring a region
+    pub async fn delete_region(&self, id: &str) -> Result<()> {
+        let response = self
+            .http
+            .delete(self.endpoint(&["api", "regions", id]))
+            .send()
+            .await?;
+        check(response).await?;
+
+        Ok(())
+    }
+
+    /// Get the latest text changes of a region, oldest first
+    ///
+    /// Pass `limit` to only get that many of the latest changes.
+    pub async fn region_history(&self, id: &str, limit: Option<usize>) -> Result<Vec<TextChange>> {
+        let response = self
+            .http
+            .get(self.endpoint(&["api", "regions", id, "history"]))
+            .query(&HistoryQuery { limit })
+            .send()
+            .await?;
+
+        json(response).await
+    }
+
+    /// Get the current monitoring status
+    pub async fn status(&self) -> Result<StatusResponse> {
+        self.get_json(&["api", "status"]).await
+    }
+
+    /// Start monitoring the selected regions
+    pub async fn start_monitoring(&self) -> Result<()> {
+        self.post_empty(&["api", "monitor", "start"]).await?;
+        Ok(())
+    }
+
+    /// Stop monitoring, which is not an error when it isn't running
+    pub async fn stop_monitoring(&self) -> Result<()> {
+        self.post_empty(&["api", "monitor", "stop"]).await?;
+        Ok(())
+    }
+
+    /// Re-initialize the monitor's OCR engine
+    ///
+    /// Returns the supervision status right after the request; poll
+    /// `diagnostics` to follow progress.
+    pub async fn reinit_monitor(&self) -> Result<MonitorSupervision> {
+        let response = self.post_empty(&["api", "monitor", "reinit"]).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Subscribe to text changes of all regions, or of one region
+    pub async fn text_events(&self, region: Option<&str>) -> Result<EventStream<TextChange>> {
+        let query = EventsQuery {
+            region: region.map(str::to_string),
+        };
+        let response = self
+            .http
+            .get(self.endpoint(&["api", "events"]))
+            .query(&query)
+            .send()
+            .await?;
+
+        Ok(EventStream::new(check(response).await?, "text"))
+    }
+
+    /// Run OCR on an encoded image (PNG, JPEG or TIFF)
+    pub async fn ocr_image(&self, image: Vec<u8>, settings: &OcrSettings) -> Result<OcrResult> {
+        let response = self
+            .http
+            .post(self.endpoint(&["api", "ocr"]))
+            .query(settings)
+            .body(image)
+            .send()
+            .await?;
+
+        json(response).await
+    }
+
+    /// Submit a background job capturing and reading a live screen region
+    pub async fn submit_region_job(&self, request: &RegionJobRequest) -> Result<Job> {
+        let response = self
+            .http
+            .post(self.endpoint(&["api", "jobs", "region"]))
+            .json(request)
+            .send()
+            .await?;
+
+        json(response).await
+    }
+
+    /// Submit a background job reading one or more encoded images
+    pub async fn submit_image_job(
+        &self,
+        images: Vec<Vec<u8>>,
+        settings: &OcrSettings,
+    ) -> Result<Job> {
+        let form = images
+            .into_iter()
+            .enumerate()
+            .fold(Form::new(), |form, (index, image)| {
+                form.part(
+                    "image",
+                    Part::bytes(image).file_name(format!("image-{}", index)),
+                )
+            });
+        let response = self
+            .http
+            .post(self.endpoint(&["api", "jobs", "images"]))
+            .query(settings)
+            .multipart(form)
+            .send()
+            .await?;
+
+        json(response).await
+    }
+
+    /// List all known jobs without their results
+    pub async fn jobs(&self) -> Result<Vec<Job>> {
+        self.get_json(&["api", "jobs"]).await
+    }
+
+    /// Get the status and results of a job
+    pub async fn job(&self, id: &str) -> Result<Job> {
+        self.get_json(&["api", "jobs", id]).await
+    }
+
+    /// Cancel a queued or running job
+    pub async fn cancel_job(&self, id: &str) -> Result<Job> {
+        let response = self
+            .http
+            .delete(self.endpoint(&["api", "jobs", id]))
+            .send()
+            .await?;
+
+        json(response).await
+    }
+
+    /// Subscribe to updates of a job, the stream ends once the job finished
+    pub async fn job_events(&self, id: &str) -> Result<EventStream<Job>> {
+        let response = self
+            .http
+            .get(self.endpoint(&["api", "jobs", id, "events"]))
+            .send()
+            .await?;
+
+        Ok(EventStream::new(check(response).await?, "job"))
+    }
+
+    /// Get the metrics in the Prometheus text format
+    pub async fn metrics(&self) -> Result<String> {
+        let response = self.http.get(self.endpoint(&["metrics"])).send().await?;
+        Ok(check(response).await?.text().await?)
+    }
+
+    /// Get liveness and readiness
+    ///
+    /// A server that is not ready answers 503 with the failing checks,
+    /// which is returned like a ready one.
+    pub async fn health(&self) -> Result<HealthResponse> {
+        let response = self
+            .http
+            .get(self.endpoint(&["api", "health"]))
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::SERVICE_UNAVAILABLE {
+            return Ok(response.json().await?);
+        }
+        json(response).await
+    }
+
+    /// Get the environment, component errors and optionally an OCR self-test
+    pub async fn diagnostics(&self, self_test: bool) -> Result<DiagnosticsResponse> {
+        let response = self
+            .http
+            .get(self.endpoint(&["api", "diagnostics"]))
+            .query(&DiagnosticsQuery { self_test })
+            .send()
+            .await?;
+
+        json(response).await
+    }
+
+    /// Get what this build of the server can do
+    pub async fn capabilities(&self) -> Result<Capabilities> {
+        self.get_json(&["api", "capabilities"]).await
+    }
+
+    /// Start recording the monitor's captures into a new session archive
+    pub async fn start_recording(
+        &self,
+        request: &StartRecordingRequest,
+    ) -> Result<RecordingSession> {
+        let response = self
+            .http
+            .post(self.endpoint(&["api", "recordings", "start"]))
+            .json(request)
+            .send()
+            .await?;
+
+        json(response).await
+    }
+
+    /// Stop the running recording and finish its archive
+    pub async fn stop_recording(&self) -> Result<RecordingSession> {
+        let response = self.post_empty(&["api", "recordings", "stop"]).await?;
+        Ok(response.json().await?)
+    }
+
+    /// List all recorded sessions, oldest first
+    pub async fn recordings(&self) -> Result<Vec<RecordingSession>> {
+        self.get_json(&["api", "recordings"]).await
+    }
+
+    /// Get the summary of a recorded session
+    pub async fn recording(&self, id: &str) -> Result<RecordingSession> {
+        self.get_json(&["api", "recordings", id]).await
+    }
+
+    /// Download the tar archive of a finished session in chunks
+    pub async fn recording_archive(&self, id: &str) -> Result<impl Stream<Item = Result<Vec<u8>>>> {
+        let response = self
+            .http
+            .get(self.endpoint(&["api", "recordings", id, "archive"]))
+            .send()
+            .await?;
+        let response = check(response).await?;
+
+        Ok(response.bytes_stream().map(|chunk| Ok(chunk?.to_vec())))
+    }
+
+    /// Delete a finished session and its archive
+    pub async fn delete_recording(&self, id: &str) -> Result<()> {
+        let response = self
+            .http
+            .delete(self.endpoint(&["api", "recordings", id]))
+            .send()
+            .await?;
+        check(response).await?;
+
+        Ok(())
+    }
+
+    /// Build the URL of an API path, escaping every segment
+    fn endpoint(&self, segments: &[&str]) -> Url {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .expect("Base URL was checked in the constructor")
+            .pop_if_empty()
+            .extend(segments);
+        url
+    }
+
+    /// Send a GET request and decode the JSON response
+    async fn get_json<T: DeserializeOwned>(&self, segments: &[&str]) -> Result<T> {
+        let response = self.http.get(self.endpoint(segments)).send().await?;
+        json(response).await
+    }
+
+    /// Send a POST request without a body
+    async fn post_empty(&self, segments: &[&str]) -> Result<Response> {
+        let response = self.http.post(self.endpoint(segments)).send().await?;
+        check(response).await
+    }
+
+    /// Request a screenshot and read the capture headers
+    async fn get_screenshot(
+        &self,
+        segments: &[&str],
+        query: &ScreenshotQuery,
+    ) -> Result<Screenshot> {
+        let response = self
+            .http
+            .get(self.endpoint(segments))
+            .query(query)
+            .send()
+            .await?;
+        let response = check(response).await?;
+
+        let header = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let content_type = header("Content-Type").unwrap_or_default();
+        let capture_region = header("X-Capture-Region").and_then(|value| parse_box(&value));
+        let scale_factor = header("X-Scale-Factor").and_then(|value| value.parse().ok());
+
+        Ok(Screenshot {
+            data: response.bytes().await?.to_vec(),
+            content_type,
+            capture_region,
+            scale_factor,
+        })
+    }
+}
+
+/// Fail with the server's error unless the response is a success
+async fn check(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body = response.bytes().await?;
+    Err(ClientError::from_response(status, &body))
+}
+
+/// Decode the JSON body of a successful response
+async fn json<T: DeserializeOwned>(response: Response) -> Result<T> {
+    Ok(check(response).await?.json().await?)
+}
+
+/// Parse an `x,y,width,height` rectangle
+fn parse_box(value: &str) -> Option<BoundingBox> {
+    let mut parts = value.split(',').map(|part| part.trim().parse::<i32>());
+    let bbox = BoundingBox {
+        x: parts.next()?.ok()?,
+        y: parts.next()?.ok()?,
+        width: parts.next()?.ok()?,
+        height: parts.next()?.ok()?,
+    };
+
+    parts.next().is_none().then_some(bbox)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MonitoredRegion;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    /// Answer one request on a local port with a canned response
+    ///
+    /// Returns the server URL and a handle resolving to the raw request.
+    async fn mock_server(status: &str, body: &str) -> (String, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 4096];
+
+            // Read the head, then as much body as it announces
+            loop {
+                let read = socket.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(head_end) = text.find("\r\n\r\n") {
+                    let length = text[..head_end]
+                        .lines()
+                        .find_map(|line| {
+                            let (name, value) = line.split_once(':')?;
+                            name.eq_ignore_ascii_case("content-length")
+                                .then(|| value.trim().parse::<usize>().ok())?
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= head_end + 4 + length || read == 0 {
+                        break;
+                    }
+                }
+            }
+
+            socket.write_all(response.as_bytes()).await.unwrap();
+            socket.shutdown().await.unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        (url, server)
+    }
+
+    /// Client of a mock server, bypassing any proxy configured in the environment
+    fn local_client(url: &str) -> Client {
+        let http = reqwest::Client::builder().no_proxy().build().unwrap();
+        Client::with_http_client(url, http).unwrap()
+    }
+
+    #[test]
+    fn test_endpoint_escapes_segments() {
+        let client = Client::new("http://127.0.0.1:8080/").unwrap();
+        assert_eq!(
+            client
+                .endpoint(&["api", "regions", "a b/c", "history"])
+                .as_str(),
+            "http://127.0.0.1:8080/api/regions/a%20b%2Fc/history"
+        );
+
+        let client = Client::new("http://proxy.local/reader").unwrap();
+        assert_eq!(
+            client.endpoint(&["api", "status"]).as_str(),
+            "http://proxy.local/reader/api/status"
+        );
+
+        assert!(Client::new("not a url").is_err());
+    }
+
+    #[test]
+    fn test_parse_capture_region() {
+        assert_eq!(
+            parse_box("10,20,300,40"),
+            Some(BoundingBox {
+                x: 10,
+                y: 20,
+                width: 300,
+                height: 40
+            })
+        );
+        assert_eq!(parse_box("10,20,300"), None);
+        assert_eq!(parse_box("10,20,300,40,5"), None);
+    }
+
+    #[tokio::test]
+    async fn test_add_region_round_trip() {
+        let region = MonitoredRegion::new("price", Region::new(10, 20, 300, 40));
+        let (url, server) = mock_server("200 OK", &serde_json::to_string(&region).unwrap()).await;
+
+        let added = local_client(&url).add_region(&region).await.unwrap();
+        assert_eq!(added.id, "price");
+        assert_eq!(added.region.width, 300);
+
+        let request = server.await.unwrap();
+        assert!(
+            request.starts_with("POST /api/regions HTTP/1.1\r\n"),
+            "{}",
+            request
+        );
+        let (_, body) = request.split_once("\r\n\r\n").unwrap();
+        let sent: MonitoredRegion = serde_json::from_str(body).unwrap();
+        assert_eq!(sent.id, "price");
+    }
+
+    #[tokio::test]
+    async fn test_error_responses_are_mapped() {
+        let (url, server) = mock_server(
+            "404 Not Found",
+            r#"{"error":{"code":"not_found","message":"Job 'job-9' not found"}}"#,
+        )
+        .await;
+
+        let error = local_client(&url).job("job-9").await.unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::NOT_FOUND));
+        assert_eq!(error.code(), Some("not_found"));
+        assert!(error.to_string().contains("Job 'job-9' not found"));
+        assert!(server
+            .await
+            .unwrap()
+            .starts_with("GET /api/jobs/job-9 HTTP/1.1\r\n"));
+
+        let (url, _server) = mock_server("429 Too Many Requests", "slow down").await;
+        let error = local_client(&url).jobs().await.unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::TOO_MANY_REQUESTS));
+        assert_eq!(error.code(), Some("http_error"));
+    }
+
+    #[tokio::test]
+    async fn test_text_events_report_lag() {
+        let change = |text: &str| {
+            serde_json::json!({
+                "region_id": "price",
+                "timestamp": "2024-01-01T00:00:00Z",
+                "text": text,
+                "diff": {"lines": [], "added": 0, "removed": 0, "changed": 0},
+            })
+        };
+        let body = format!(
+            "event: text\ndata: {}\n\nevent: lagged\ndata: {{\"skipped\":2}}\n\nevent: text\ndata: {}\n\n",
+            change("12.50"),
+            change("13.75")
+        );
+        let (url, server) = mock_server("200 OK", &body).await;
+
+        let mut events = local_client(&url).text_events(None).await.unwrap();
+        assert_eq!(events.next().await.unwrap().unwrap().text, "12.50");
+        assert!(matches!(
+            events.next().await.unwrap(),
+            Err(ClientError::Lagged { skipped: 2 })
+        ));
+        assert_eq!(events.next().await.unwrap().unwrap().text, "13.75");
+        assert!(events.next().await.is_none());
+
+        assert!(server
+            .await
+            .unwrap()
+            .starts_with("GET /api/events HTTP/1.1\r\n"));
+    }
+}
@@ -0,0 +1,100 @@
+use reqwest::StatusCode;
+use thiserror::Error;
+
+use crate::models::ErrorResponse;
+
+/// Error returned by client calls
+#[derive(Debug, Error)]
+pub enum ClientError {
+    /// The server URL can't be used as a base for API paths
+    #[error("Invalid server URL: {0}")]
+    InvalidUrl(String),
+
+    /// The request could not be sent or its response not read
+    #[error("Request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// The server rejected the request or failed to handle it
+    #[error("Server answered {status} ({code}): {message}")]
+    Api {
+        /// HTTP status of the response
+        status: StatusCode,
+
+        /// Stable machine readable error code, e.g. `not_found`
+        code: String,
+
+        /// Human readable description
+        message: String,
+    },
+
+    /// An event of a stream could not be decoded
+    #[error("Invalid event: {0}")]
+    InvalidEvent(#[from] serde_json::Error),
+
+    /// The server dropped events because the client didn't keep up
+    ///
+    /// The stream goes on with later events; resync from the history to
+    /// catch up on the missed ones.
+    #[error("Missed {skipped} event(s)")]
+    Lagged {
+        /// Number of events the server dropped
+        skipped: u64,
+    },
+}
+
+impl ClientError {
+    /// Build the error of a failed response from its status and body
+    ///
+    /// Bodies without the API's error envelope, e.g. from a proxy in
+    /// between, are reported with the code `http_error`.
+    pub(crate) fn from_response(status: StatusCode, body: &[u8]) -> Self {
+        match serde_json::from_slice::<ErrorResponse>(body) {
+            Ok(response) => ClientError::Api {
+                status,
+                code: response.error.code,
+                message: response.error.message,
+            },
+            Err(_) => ClientError::Api {
+                status,
+                code: "http_error".to_string(),
+                message: String::from_utf8_lossy(body).trim().to_string(),
+            },
+        }
+    }
+
+    /// Error code reported by the server, if it answered with an error
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            ClientError::Api { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+
+    /// HTTP status of the failed response, if there was one
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            ClientError::Api { status, .. } => Some(*status),
+            ClientError::Http(error) => error.status(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_envelope_is_parsed() {
+        let body = br#"{"error":{"code":"not_found","message":"Region not found: price"}}"#;
+
+        let error = ClientError::from_response(StatusCode::NOT_FOUND, body);
+        assert_eq!(error.code(), Some("not_found"));
+        assert_eq!(error.status(), Some(StatusCode::NOT_FOUND));
+        assert!(error.to_string().contains("Region not found: price"));
+
+        let error = ClientError::from_response(StatusCode::BAD_GATEWAY, b"Bad Gateway\n");
+        assert_eq!(error.code(), Some("http_error"));
+        assert!(error.to_string().ends_with("Bad Gateway"));
+    }
+}
@@ -0,0 +1,211 @@
+//! Server-sent event streams of the API
+
+use futures_util::stream::{self, BoxStream, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+use crate::error::ClientError;
+use crate::models::EventsLagged;
+
+/// Event type the server sends when it dropped events for a slow client
+const LAGGED_EVENT: &str = "lagged";
+
+/// Stream of typed events, e.g. text changes or job updates
+///
+/// The data of every event of the expected type is decoded as JSON into
+/// `T`, other events are skipped. Events the server dropped because the
+/// client fell behind are reported as `ClientError::Lagged`, after which
+/// the stream goes on. The stream ends when the server closes the
+/// connection.
+pub struct EventStream<T> {
+    /// Event type carrying `T`
+    name: &'static str,
+
+    /// Body chunks of the response
+    body: BoxStream<'static, reqwest::Result<Vec<u8>>>,
+
+    /// Events split out of the chunks received so far
+    parser: EventParser,
+
+    /// Type of the event data
+    data: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> EventStream<T> {
+    /// Read the events of type `name` from a `text/event-stream` response
+    pub(crate) fn new(response: reqwest::Response, name: &'static str) -> Self {
+        Self {
+            name,
+            body: response
+                .bytes_stream()
+                .map(|chunk| chunk.map(|bytes| bytes.to_vec()))
+                .boxed(),
+            parser: EventParser::default(),
+            data: PhantomData,
+        }
+    }
+
+    /// Wait for the next event, `None` once the stream ended
+    pub async fn next(&mut self) -> Option<Result<T, ClientError>> {
+        loop {
+            if let Some(event) = self.parser.next_event() {
+                match event.name.as_deref() {
+                    Some(LAGGED_EVENT) => return Some(Err(lagged_error(&event.data))),
+                    Some(name) if name != self.name => continue,
+                    _ => {}
+                }
+                return Some(serde_json::from_str(&event.data).map_err(ClientError::from));
+            }
+
+            match self.body.next().await? {
+                Ok(chunk) => self.parser.push(&chunk),
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
+    }
+
+    /// Turn the events into a `Stream` for use with stream combinators
+    pub fn into_stream(self) -> impl Stream<Item = Result<T, ClientError>> {
+        stream::unfold(self, |mut events| async move {
+            let event = events.next().await?;
+            Some((event, events))
+        })
+    }
+}
+
+/// Turn the data of a `lagged` event into the error reporting it
+fn lagged_error(data: &str) -> ClientError {
+    match serde_json::from_str::<EventsLagged>(data) {
+        Ok(lagged) => ClientError::Lagged {
+            skipped: lagged.skipped,
+        },
+        Err(e) => e.into(),
+    }
+}
+
+/// A single server-sent event
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Event {
+    /// Event type from the `event:` field, if given
+    name: Option<String>,
+
+    /// Lines of the `data:` fields joined by newlines
+    data: String,
+}
+
+/// Incremental parser of the `text/event-stream` format
+///
+/// Chunks may end anywhere, also within a line or a UTF-8 sequence.
+#[derive(Debug, Default)]
+struct EventParser {
+    /// Bytes after the last complete line
+    pending: Vec<u8>,
+
+    /// Fields of the event being read
+    name: Option<String>,
+    data: Vec<String>,
+
+    /// Complete events not yet handed out
+    events: VecDeque<Event>,
+}
+
+impl EventParser {
+    /// Feed the next chunk of the response body
+    fn push(&mut self, chunk: &[u8]) {
+        self.pending.extend_from_slice(chunk);
+
+        while let Some(end) = self.pending.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            self.process_line(line.trim_end_matches(['\n', '\r']));
+        }
+    }
+
+    /// Take the oldest complete event
+    fn next_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    /// Handle one line of the stream
+    fn process_line(&mut self, line: &str) {
+        // An empty line ends the event
+        if line.is_empty() {
+            let name = self.name.take();
+            if !self.data.is_empty() {
+                let data = std::mem::take(&mut self.data).join("\n");
+                self.events.push_back(Event { name, data });
+            }
+            return;
+        }
+
+        // Lines starting with a colon are comments, e.g. keep-alives
+        if line.starts_with(':') {
+            return;
+        }
+
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => self.name = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_split_across_chunks() {
+        let mut parser = EventParser::default();
+
+        parser.push(b"event: text\nda");
+        assert_eq!(parser.next_event(), None);
+
+        parser.push(b"ta: {\"a\":1}\r\n\r\n: keep-alive\n\ndata: first\ndata: second\n\n");
+        assert_eq!(
+            parser.next_event(),
+            Some(Event {
+                name: Some("text".to_string()),
+                data: "{\"a\":1}".to_string(),
+            })
+        );
+        assert_eq!(
+            parser.next_event(),
+            Some(Event {
+                name: None,
+                data: "first\nsecond".to_string(),
+            })
+        );
+        assert_eq!(parser.next_event(), None);
+    }
+
+    #[test]
+    fn test_multibyte_character_split_across_chunks() {
+        let mut parser = EventParser::default();
+        let line = "data: Größe\n\n".as_bytes();
+
+        // Split inside the two-byte 'ö'
+        parser.push(&line[..9]);
+        parser.push(&line[9..]);
+
+        assert_eq!(parser.next_event().unwrap().data, "Größe");
+    }
+
+    #[test]
+    fn test_lagged_event() {
+        let mut parser = EventParser::default();
+        parser.push(b"event: lagged\ndata: {\"skipped\":3}\n\n");
+
+        let event = parser.next_event().unwrap();
+        assert_eq!(event.name.as_deref(), Some(LAGGED_EVENT));
+        assert!(matches!(
+            lagged_error(&event.data),
+            ClientError::Lagged { skipped: 3 }
+        ));
+        assert!(matches!(lagged_error("{}"), ClientError::InvalidEvent(_)));
+    }
+}
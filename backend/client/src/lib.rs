@@ -0,0 +1,38 @@
+//! Async client of the Screen Text Reader HTTP API
+//!
+//! Requests and responses use the model types of `screen-text-core`, the
+//! same types the server serializes, so the two can't drift apart. Failed
+//! calls return a `ClientError`, which carries the server's error code.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), screen_text_client::ClientError> {
+//! use screen_text_client::{models::Region, Client, ClientError};
+//!
+//! let client = Client::new("http://127.0.0.1:8080")?;
+//! client.set_region(&Region::new(100, 100, 400, 50)).await?;
+//! client.start_monitoring().await?;
+//!
+//! let mut events = client.text_events(None).await?;
+//! while let Some(change) = events.next().await {
+//!     match change {
+//!         Ok(change) => println!("{}", change.text),
+//!         // Changes were dropped, the history endpoint has them
+//!         Err(ClientError::Lagged { skipped }) => eprintln!("Missed {} change(s)", skipped),
+//!         Err(e) => return Err(e),
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod client;
+mod error;
+mod events;
+
+pub use client::{Client, Result, Screenshot};
+pub use error::ClientError;
+pub use events::EventStream;
+
+/// API models shared with the server
+pub use screen_text_core::models;
+pub use screen_text_core::DisplayInfo;
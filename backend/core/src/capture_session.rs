@@ -0,0 +1,698 @@
+use anyhow::{anyhow, Result};
+use log::{debug, error, info, warn};
+use once_cell::sync::Lazy;
+use scap::{
+    capturer::{Capturer, Options, Resolution},
+    frame::FrameType,
+    Target,
+};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use super::frame_convert;
+use super::screen_capture::{CaptureError, DisplayInfo};
+use crate::hooks;
+
+pub use scap::frame::Frame;
+
+/// Frame rate used for new capture sessions unless configured otherwise
+pub const DEFAULT_STREAM_FPS: u32 = 5;
+
+/// How long a caller waits for a fresh frame before giving up
+const FRAME_WAIT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Delay before the first restart attempt of a failed stream
+const RESTART_BACKOFF_MIN: Duration = Duration::from_millis(250);
+
+/// Upper bound for the delay between restart attempts
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(10);
+
+/// Sessions nobody asked for a frame for this long are stopped
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often the reaper looks for idle sessions and exited threads
+const REAPER_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long the list of displays reported by the platform is reused
+const DISPLAY_LIST_MAX_AGE: Duration = Duration::from_secs(10);
+
+/// Frame rate applied to sessions started after the last `set_stream_fps` call
+static STREAM_FPS: AtomicU32 = AtomicU32::new(DEFAULT_STREAM_FPS);
+
+/// Displays reported by the platform, once they were listed
+static DISPLAYS: Lazy<Mutex<Option<DisplayList>>> = Lazy::new(|| Mutex::new(None));
+
+/// Displays reported by the platform at one point in time
+struct DisplayList {
+    /// When the platform was asked
+    listed_at: Instant,
+
+    /// The reported displays
+    displays: Vec<DisplayInfo>,
+}
+
+/// Capture sessions of all displays
+static SESSIONS: Lazy<Mutex<SessionRegistry>> =
+    Lazy::new(|| Mutex::new(SessionRegistry::default()));
+
+/// Running and stopping capture sessions
+#[derive(Default)]
+struct SessionRegistry {
+    /// Running sessions, keyed by display id
+    active: HashMap<u32, Arc<CaptureSession>>,
+
+    /// Stopped sessions whose thread hasn't exited yet
+    ///
+    /// scap's frame read can't be interrupted, so a thread waiting on an
+    /// idle display only sees the stop request with the next frame. Until
+    /// then the session is kept here and no second stream is started for
+    /// its display.
+    retiring: Vec<Arc<CaptureSession>>,
+
+    /// Whether the reaper thread is running
+    reaper_running: bool,
+}
+
+/// A frame delivered by a capture session
+#[derive(Clone)]
+pub struct CapturedFrame {
+    /// The full display frame
+    pub frame: Arc<Frame>,
+
+    /// Monotonic frame counter within the session
+    pub sequence: u64,
+
+    /// When the frame was received from the capturer
+    pub captured_at: Instant,
+}
+
+/// State shared between a session handle and its capture thread
+struct SessionShared {
+    /// Most recent frame received from the stream
+    latest: Mutex<Option<CapturedFrame>>,
+
+    /// Signalled whenever a new frame is stored in `latest`
+    frame_ready: Condvar,
+
+    /// Stop requests and whether the capture thread has exited
+    control: Mutex<SessionControl>,
+
+    /// Number of times the stream had to be rebuilt
+    restarts: AtomicU64,
+}
+
+/// Lifecycle of a capture thread
+#[derive(Default)]
+struct SessionControl {
+    /// Set to ask the capture thread to exit
+    stopping: bool,
+
+    /// Set by the capture thread once it saw a stop request and exits
+    exited: bool,
+}
+
+impl SessionShared {
+    /// Check whether the capture thread should keep going
+    ///
+    /// A thread that is told to stop is marked as exited in the same step,
+    /// so a session is never resumed after its thread decided to leave.
+    fn keep_running(&self) -> bool {
+        let mut control = lock(&self.control);
+        if control.stopping {
+            control.exited = true;
+        }
+        !control.exited
+    }
+
+    /// Store a frame received from the stream as the latest one
+    ///
+    /// macOS reports an idle screen with an empty frame. Frames without
+    /// pixels are dropped so the last real frame stays current. Returns
+    /// whether the frame was stored.
+    fn publish(&self, frame: Frame, sequence: u64) -> bool {
+        if !frame_convert::has_pixels(&frame) {
+            return false;
+        }
+
+        *lock(&self.latest) = Some(CapturedFrame {
+            frame: Arc::new(frame),
+            sequence,
+            captured_at: Instant::now(),
+        });
+        self.frame_ready.notify_all();
+        true
+    }
+}
+
+/// A long-lived capture stream for a single display
+///
+/// The session owns a background thread that keeps a scap `Capturer`
+/// running at the configured frame rate and always holds on to the most
+/// recent frame. If the stream ends, the capturer is rebuilt with an
+/// exponential backoff until the session is stopped.
+pub struct CaptureSession {
+    /// Display the session is streaming
+    display_id: u32,
+
+    /// Frame rate of the stream
+    fps: u32,
+
+    /// State shared with the capture thread
+    shared: Arc<SessionShared>,
+
+    /// The capture thread, until it was joined
+    thread: Mutex<Option<JoinHandle<()>>>,
+
+    /// When a frame was last asked for
+    last_used: Mutex<Instant>,
+}
+
+impl CaptureSession {
+    /// Start a new capture session for the given display
+    pub fn start(display_id: u32, fps: u32) -> Result<Self> {
+        let fps = fps.max(1);
+        let shared = Arc::new(SessionShared {
+            latest: Mutex::new(None),
+            frame_ready: Condvar::new(),
+            control: Mutex::new(SessionControl::default()),
+            restarts: AtomicU64::new(0),
+        });
+
+        let thread_shared = shared.clone();
+        let thread = std::thread::Builder::new()
+            .name(format!("capture-display-{}", display_id))
+            .spawn(move || Self::run(display_id, fps, thread_shared))
+            .map_err(|e| anyhow!("Failed to spawn capture thread: {}", e))?;
+
+        info!(
+            "Started capture session for display {} at {} fps",
+            display_id, fps
+        );
+
+        Ok(Self {
+            display_id,
+            fps,
+            shared,
+            thread: Mutex::new(Some(thread)),
+            last_used: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Capture loop executed on the session thread
+    fn run(display_id: u32, fps: u32, shared: Arc<SessionShared>) {
+        let mut backoff = RESTART_BACKOFF_MIN;
+        let mut sequence: u64 = 0;
+
+        while shared.keep_running() {
+            let options = Options {
+                fps,
+                target: target_for_display(display_id),
+                show_cursor: false,
+                show_highlight: false,
+                excluded_targets: None,
+                output_type: FrameType::BGRAFrame,
+                output_resolution: Resolution::Captured,
+                crop_area: None, // Regions are cropped from the full frame in memory
+            };
+
+            match Capturer::build(options) {
+                Ok(mut capturer) => {
+                    capturer.start_capture();
+                    debug!("Capture stream for display {} started", display_id);
+
+                    while shared.keep_running() {
+                        match capturer.get_next_frame() {
+                            Ok(frame) => {
+                                backoff = RESTART_BACKOFF_MIN;
+
+                                if shared.publish(frame, sequence + 1) {
+                                    sequence += 1;
+                                }
+                            }
+                            Err(e) => {
+                                warn!("Capture stream for display {} ended: {}", display_id, e);
+                                hooks::report_error(
+                                    "capture_stream",
+                                    format!("Display {}: {}", display_id, e),
+                                );
+                                break;
+                            }
+                        }
+                    }
+
+                    capturer.stop_capture();
+                }
+                Err(e) => {
+                    error!("Failed to build capturer for display {}: {}", display_id, e);
+                    hooks::report_error("capture_stream", format!("Display {}: {}", display_id, e));
+                }
+            }
+
+            if !shared.keep_running() {
+                break;
+            }
+
+            // The stream died on its own - rebuild it after a short pause
+            let restarts = shared.restarts.fetch_add(1, Ordering::SeqCst) + 1;
+            warn!(
+                "Restarting capture stream for display {} in {}ms (restart #{})",
+                display_id,
+                backoff.as_millis(),
+                restarts
+            );
+            std::thread::sleep(backoff);
+            backoff = (backoff * 2).min(RESTART_BACKOFF_MAX);
+        }
+
+        lock(&shared.control).exited = true;
+        info!("Capture session for display {} stopped", display_id);
+    }
+
+    /// Get the most recent frame, waiting for the first one if necessary
+    ///
+    /// Some platforms only deliver frames when the screen content changes,
+    /// so an old frame is still an accurate picture of an idle display.
+    pub fn current_frame(&self) -> Result<CapturedFrame> {
+        let deadline = Instant::now() + FRAME_WAIT_TIMEOUT;
+
+        let mut latest = self
+            .shared
+            .latest
+            .lock()
+            .map_err(|e| anyhow!("Failed to lock capture session frame: {}", e))?;
+
+        loop {
+            if let Some(frame) = latest.as_ref() {
+                return Ok(frame.clone());
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(CaptureError::Timeout(self.display_id).into());
+            }
+
+            latest = self
+                .shared
+                .frame_ready
+                .wait_timeout(latest, deadline - now)
+                .map_err(|e| anyhow!("Failed to wait for capture frame: {}", e))?
+                .0;
+        }
+    }
+
+    /// Ask the capture thread to stop
+    ///
+    /// The thread exits once the capturer delivers its next frame.
+    pub fn stop(&self) {
+        lock(&self.shared.control).stopping = true;
+    }
+
+    /// Take back a stop request the capture thread hasn't acted on yet
+    ///
+    /// Returns whether the session is running again.
+    fn resume(&self) -> bool {
+        let mut control = lock(&self.shared.control);
+        if !control.exited {
+            control.stopping = false;
+        }
+        !control.exited
+    }
+
+    /// Whether the stream delivered a frame since it started
+    fn has_frame(&self) -> bool {
+        lock(&self.shared.latest).is_some()
+    }
+
+    /// Record that a frame was asked for
+    fn touch(&self) {
+        *lock(&self.last_used) = Instant::now();
+    }
+
+    /// Time since a frame was last asked for
+    fn idle_for(&self) -> Duration {
+        lock(&self.last_used).elapsed()
+    }
+
+    /// Join the capture thread if it has exited, returns whether it has
+    fn try_join(&self) -> bool {
+        let mut thread = lock(&self.thread);
+        match thread.as_ref() {
+            Some(handle) if !handle.is_finished() => false,
+            Some(_) => {
+                if let Some(Err(e)) = thread.take().map(JoinHandle::join) {
+                    error!(
+                        "Capture thread of display {} panicked: {:?}",
+                        self.display_id, e
+                    );
+                }
+                true
+            }
+            None => true,
+        }
+    }
+}
+
+impl Drop for CaptureSession {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Check if screen capture is supported on the current platform
+pub fn is_supported() -> bool {
+    scap::is_supported()
+}
+
+/// Check if we have permission to capture the screen
+pub fn has_permission() -> bool {
+    scap::has_permission()
+}
+
+/// Request permission to capture the screen
+pub fn request_permission() -> bool {
+    scap::request_permission()
+}
+
+/// Resolve the scap target for a display id (0 is the primary display)
+fn target_for_display(display_id: u32) -> Option<Target> {
+    if display_id == 0 {
+        return None;
+    }
+
+    let target = scap::get_all_targets()
+        .into_iter()
+        .find(|target| match target {
+            Target::Display(display) => display.id == display_id,
+            _ => false,
+        });
+
+    if target.is_none() {
+        warn!(
+            "Display {} not found, falling back to the primary display",
+            display_id
+        );
+    }
+
+    target
+}
+
+/// List the displays the platform reports, with their size and scale factor
+///
+/// The list is cached for a few seconds, as enumerating displays is slow on
+/// some platforms. Platforms without display enumeration (Linux) report none.
+pub fn displays() -> Vec<DisplayInfo> {
+    let mut cached = lock(&DISPLAYS);
+    if let Some(list) = cached.as_ref() {
+        if list.listed_at.elapsed() < DISPLAY_LIST_MAX_AGE {
+            return list.displays.clone();
+        }
+    }
+
+    let displays: Vec<DisplayInfo> = scap::get_all_targets()
+        .into_iter()
+        .filter_map(|target| match target {
+            Target::Display(display) => platform::describe_display(&display),
+            _ => None,
+        })
+        .collect();
+    debug!("Platform reports {} display(s)", displays.len());
+
+    *cached = Some(DisplayList {
+        listed_at: Instant::now(),
+        displays: displays.clone(),
+    });
+    displays
+}
+
+/// Describe a display as reported by the platform (0 is the primary display)
+pub fn display_info(display_id: u32) -> Option<DisplayInfo> {
+    displays()
+        .into_iter()
+        .find(|display| display.id == display_id || (display_id == 0 && display.primary))
+}
+
+/// Get the frame size of a display from its running session
+///
+/// Never starts a session, so it is `None` unless the display is captured
+/// already and delivered a frame.
+pub fn cached_frame_size(display_id: u32) -> Option<(i32, i32)> {
+    let session = lock(&SESSIONS).active.get(&display_id).cloned()?;
+    let latest = lock(&session.shared.latest);
+    latest
+        .as_ref()
+        .map(|captured| frame_convert::frame_size(&captured.frame))
+}
+
+/// Size and scale factor of displays from the platform's display APIs
+mod platform {
+    use super::DisplayInfo;
+
+    /// Describe a display through its CoreGraphics handle
+    #[cfg(target_os = "macos")]
+    pub fn describe_display(display: &scap::Display) -> Option<DisplayInfo> {
+        let handle = display.raw_handle;
+        let width = handle.pixels_wide();
+        let logical_width = handle.bounds().size.width;
+
+        Some(DisplayInfo {
+            id: display.id,
+            name: display.title.clone(),
+            width: width as u32,
+            height: handle.pixels_high() as u32,
+            scale_factor: if logical_width > 0.0 {
+                width as f64 / logical_width
+            } else {
+                1.0
+            },
+            primary: handle.is_main(),
+        })
+    }
+
+    /// Describe a display through its monitor handle
+    #[cfg(target_os = "windows")]
+    pub fn describe_display(display: &scap::Display) -> Option<DisplayInfo> {
+        use windows::Win32::Graphics::Gdi::{GetMonitorInfoW, MONITORINFO};
+        use windows::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+
+        /// DPI of a display at 100% scaling
+        const BASE_DPI: f64 = 96.0;
+
+        /// `MONITORINFO::dwFlags` bit of the primary monitor
+        const MONITORINFOF_PRIMARY: u32 = 1;
+
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        // SAFETY: the handle comes from scap's monitor enumeration and `info`
+        // is a MONITORINFO with its size set, as the call requires
+        if !unsafe { GetMonitorInfoW(display.raw_handle, &mut info) }.as_bool() {
+            return None;
+        }
+
+        let (mut dpi_x, mut dpi_y) = (0, 0);
+        // SAFETY: same handle, the out pointers are valid for the call
+        let dpi = unsafe {
+            GetDpiForMonitor(
+                display.raw_handle,
+                MDT_EFFECTIVE_DPI,
+                &mut dpi_x,
+                &mut dpi_y,
+            )
+        }
+        .map_or(BASE_DPI, |_| f64::from(dpi_x));
+
+        let rect = info.rcMonitor;
+        Some(DisplayInfo {
+            id: display.id,
+            name: display.title.clone(),
+            width: (rect.right - rect.left) as u32,
+            height: (rect.bottom - rect.top) as u32,
+            scale_factor: dpi / BASE_DPI,
+            primary: info.dwFlags & MONITORINFOF_PRIMARY != 0,
+        })
+    }
+
+    /// The platform doesn't describe its displays
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    pub fn describe_display(_display: &scap::Display) -> Option<DisplayInfo> {
+        None
+    }
+}
+
+/// Set the frame rate used by capture sessions
+///
+/// Running sessions are restarted so the new rate takes effect.
+pub fn set_stream_fps(fps: u32) {
+    let fps = fps.max(1);
+    let previous = STREAM_FPS.swap(fps, Ordering::SeqCst);
+    if previous != fps {
+        stop_all_sessions();
+    }
+}
+
+/// Get the current frame of a display, starting its capture session on demand
+///
+/// A session that never delivers a frame is replaced, so the next call
+/// starts from a fresh capturer, unless the display already has a stalled
+/// session waiting to exit.
+pub fn current_frame(display_id: u32) -> Result<CapturedFrame> {
+    let session = session_for(display_id)?;
+
+    match session.current_frame() {
+        Ok(frame) => Ok(frame),
+        Err(e) => {
+            let mut registry = lock(&SESSIONS);
+            let is_current = registry
+                .active
+                .get(&display_id)
+                .is_some_and(|existing| Arc::ptr_eq(existing, &session));
+            let retiring = registry
+                .retiring
+                .iter()
+                .any(|retiring| retiring.display_id == display_id && !retiring.try_join());
+
+            if is_current && !retiring {
+                warn!(
+                    "Capture session for display {} is stalled, replacing it",
+                    display_id
+                );
+                registry.active.remove(&display_id);
+                session.stop();
+                registry.retiring.push(session);
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Get the running session for a display, starting one if needed
+///
+/// A stopping session of the display at the current frame rate is resumed
+/// instead of starting a second stream next to it.
+fn session_for(display_id: u32) -> Result<Arc<CaptureSession>> {
+    let mut registry = lock(&SESSIONS);
+    let fps = STREAM_FPS.load(Ordering::SeqCst);
+
+    if let Some(session) = registry.active.get(&display_id) {
+        session.touch();
+        return Ok(session.clone());
+    }
+
+    let resumable = registry.retiring.iter().position(|session| {
+        session.display_id == display_id && session.fps == fps && session.has_frame()
+    });
+    let session = match resumable {
+        Some(index) if registry.retiring[index].resume() => {
+            debug!("Resuming capture session for display {}", display_id);
+            registry.retiring.remove(index)
+        }
+        _ => Arc::new(CaptureSession::start(display_id, fps)?),
+    };
+
+    session.touch();
+    registry.active.insert(display_id, session.clone());
+    start_reaper(&mut registry);
+
+    Ok(session)
+}
+
+/// Stop every running capture session
+pub fn stop_all_sessions() {
+    let mut registry = lock(&SESSIONS);
+    let sessions: Vec<_> = registry
+        .active
+        .drain()
+        .map(|(_, session)| session)
+        .collect();
+    for session in sessions {
+        session.stop();
+        registry.retiring.push(session);
+    }
+}
+
+/// Start the thread that stops idle sessions, unless it is running
+fn start_reaper(registry: &mut SessionRegistry) {
+    if registry.reaper_running {
+        return;
+    }
+
+    match std::thread::Builder::new()
+        .name("capture-reaper".into())
+        .spawn(reap_sessions)
+    {
+        Ok(_) => registry.reaper_running = true,
+        Err(e) => error!("Failed to spawn capture reaper thread: {}", e),
+    }
+}
+
+/// Stop idle sessions and join exited threads until no session is left
+fn reap_sessions() {
+    loop {
+        std::thread::sleep(REAPER_INTERVAL);
+
+        let mut registry = lock(&SESSIONS);
+        let idle: Vec<u32> = registry
+            .active
+            .iter()
+            .filter(|(_, session)| session.idle_for() >= SESSION_IDLE_TIMEOUT)
+            .map(|(display_id, _)| *display_id)
+            .collect();
+        for display_id in idle {
+            if let Some(session) = registry.active.remove(&display_id) {
+                info!("Stopping idle capture session for display {}", display_id);
+                session.stop();
+                registry.retiring.push(session);
+            }
+        }
+
+        registry.retiring.retain(|session| !session.try_join());
+
+        if registry.active.is_empty() && registry.retiring.is_empty() {
+            registry.reaper_running = false;
+            return;
+        }
+    }
+}
+
+/// Lock a mutex, recovering the data if a capture thread panicked
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scap::frame::BGRAFrame;
+
+    fn bgra_frame(width: i32, height: i32) -> Frame {
+        Frame::BGRA(BGRAFrame {
+            display_time: 0,
+            width,
+            height,
+            data: vec![0; (width * height * 4) as usize],
+        })
+    }
+
+    #[test]
+    fn test_empty_frame_keeps_previous_frame() {
+        let shared = SessionShared {
+            latest: Mutex::new(None),
+            frame_ready: Condvar::new(),
+            control: Mutex::new(SessionControl::default()),
+            restarts: AtomicU64::new(0),
+        };
+
+        assert!(shared.publish(bgra_frame(2, 1), 1));
+        assert!(!shared.publish(bgra_frame(0, 0), 2));
+
+        let latest = lock(&shared.latest).clone().unwrap();
+        assert_eq!(latest.sequence, 1);
+        assert_eq!(frame_convert::frame_size(&latest.frame), (2, 1));
+    }
+}
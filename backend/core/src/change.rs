@@ -0,0 +1,20 @@
+//! Change detection on encoded region images
+
+/// Hash sampled bytes of an image to tell whether it changed
+///
+/// At most 1000 evenly spaced bytes are read, so this is cheap enough to run
+/// on every capture. Equal images always give equal hashes.
+pub fn content_hash(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0;
+
+    // Sample pixels at regular intervals for quick comparison
+    let stride = data.len().max(1000) / 1000; // Sample at most 1000 points
+
+    for i in (0..data.len()).step_by(stride) {
+        if i < data.len() {
+            hash = hash.wrapping_add((data[i] as u64).wrapping_mul(i as u64 + 1));
+        }
+    }
+
+    hash
+}
@@ -0,0 +1,209 @@
+use crate::models::{CharSpan, LineChange, TextDiff};
+
+/// Largest line count product diffed line by line
+///
+/// Beyond this the whole text is reported as replaced, which keeps the
+/// quadratic table small for huge readings.
+const MAX_DIFF_CELLS: usize = 1_000_000;
+
+/// Line operation produced by the longest common subsequence walk
+enum Op<'a> {
+    Keep,
+    Remove(usize, &'a str),
+    Add(usize, &'a str),
+}
+
+/// Compute the line-level difference between two readings
+///
+/// Runs of removed lines directly followed by added lines are paired up
+/// as edited lines, with the edited character range of each pair.
+pub fn diff_text(old: &str, new: &str) -> TextDiff {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    let mut diff = TextDiff::default();
+    let mut removed: Vec<(usize, &str)> = Vec::new();
+    let mut added: Vec<(usize, &str)> = Vec::new();
+
+    for op in line_ops(&old_lines, &new_lines) {
+        match op {
+            Op::Remove(line, text) => removed.push((line, text)),
+            Op::Add(line, text) => added.push((line, text)),
+            Op::Keep => flush(&mut diff, &mut removed, &mut added),
+        }
+    }
+    flush(&mut diff, &mut removed, &mut added);
+
+    diff
+}
+
+/// Walk both texts along their longest common subsequence of lines
+fn line_ops<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Op<'a>> {
+    let (n, m) = (old.len(), new.len());
+
+    if n.saturating_mul(m) > MAX_DIFF_CELLS {
+        let removed = old
+            .iter()
+            .enumerate()
+            .map(|(i, text)| Op::Remove(i + 1, text));
+        let added = new.iter().enumerate().map(|(j, text)| Op::Add(j + 1, text));
+        return removed.chain(added).collect();
+    }
+
+    // lcs[i][j] is the common subsequence length of old[i..] and new[j..]
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && old[i] == new[j] {
+            ops.push(Op::Keep);
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            ops.push(Op::Add(j + 1, new[j]));
+            j += 1;
+        } else {
+            ops.push(Op::Remove(i + 1, old[i]));
+            i += 1;
+        }
+    }
+
+    // Report removals of a block before its additions
+    let mut ordered = Vec::with_capacity(ops.len());
+    let mut pending_adds = Vec::new();
+    for op in ops {
+        match op {
+            Op::Add(..) => pending_adds.push(op),
+            Op::Remove(..) => ordered.push(op),
+            Op::Keep => {
+                ordered.append(&mut pending_adds);
+                ordered.push(op);
+            }
+        }
+    }
+    ordered.append(&mut pending_adds);
+    ordered
+}
+
+/// Turn a block of removed and added lines into line changes
+fn flush(diff: &mut TextDiff, removed: &mut Vec<(usize, &str)>, added: &mut Vec<(usize, &str)>) {
+    let paired = removed.len().min(added.len());
+
+    for (&(old_line, old_text), &(new_line, new_text)) in removed.iter().zip(added.iter()) {
+        diff.lines.push(LineChange::Changed {
+            old_line,
+            new_line,
+            old_text: old_text.to_string(),
+            new_text: new_text.to_string(),
+            spans: vec![char_span(old_text, new_text)],
+        });
+    }
+    for &(old_line, text) in &removed[paired..] {
+        diff.lines.push(LineChange::Removed {
+            old_line,
+            text: text.to_string(),
+        });
+    }
+    for &(new_line, text) in &added[paired..] {
+        diff.lines.push(LineChange::Added {
+            new_line,
+            text: text.to_string(),
+        });
+    }
+
+    diff.changed += paired;
+    diff.removed += removed.len() - paired;
+    diff.added += added.len() - paired;
+    removed.clear();
+    added.clear();
+}
+
+/// Find the range that differs between two lines, after their common prefix and suffix
+fn char_span(old: &str, new: &str) -> CharSpan {
+    let old: Vec<char> = old.chars().collect();
+    let new: Vec<char> = new.chars().collect();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    CharSpan {
+        old_start: prefix,
+        old_end: old.len() - suffix,
+        new_start: prefix,
+        new_end: new.len() - suffix,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_text_has_no_changes() {
+        let diff = diff_text("a\nb", "a\nb");
+        assert!(diff.lines.is_empty());
+    }
+
+    #[test]
+    fn test_edited_line_has_char_span() {
+        let diff = diff_text("Total: 10\nStatus: OK", "Total: 12\nStatus: OK");
+
+        assert_eq!(diff.changed, 1);
+        assert_eq!(
+            diff.lines,
+            vec![LineChange::Changed {
+                old_line: 1,
+                new_line: 1,
+                old_text: "Total: 10".to_string(),
+                new_text: "Total: 12".to_string(),
+                spans: vec![CharSpan {
+                    old_start: 8,
+                    old_end: 9,
+                    new_start: 8,
+                    new_end: 9,
+                }],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_added_and_removed_lines() {
+        let diff = diff_text("a\nb\nc", "a\nc\nd");
+
+        assert_eq!((diff.added, diff.removed, diff.changed), (1, 1, 0));
+        assert_eq!(
+            diff.lines,
+            vec![
+                LineChange::Removed {
+                    old_line: 2,
+                    text: "b".to_string(),
+                },
+                LineChange::Added {
+                    new_line: 3,
+                    text: "d".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_char_span_counts_characters() {
+        let span = char_span("Grüße 1", "Grüße 2");
+        assert_eq!((span.old_start, span.old_end), (6, 7));
+    }
+}
@@ -0,0 +1,100 @@
+use anyhow::{Context, Result};
+use image::RgbaImage;
+
+use super::{EngineKind, OcrEngine};
+use crate::models::{BoundingBox, OcrResult, OcrWord};
+
+/// Engine that doesn't read text but describes the image deterministically
+///
+/// The same pixels always give the same text and different pixels almost
+/// always a different one, which is all change detection needs.
+#[derive(Debug, Clone, Default)]
+pub struct FakeEngine {
+    /// Text returned for every image instead of the description
+    text: Option<String>,
+}
+
+impl FakeEngine {
+    /// Create an engine that describes every image by its size and content hash
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an engine that returns the given text for every image
+    pub fn with_text(text: impl Into<String>) -> Self {
+        Self {
+            text: Some(text.into()),
+        }
+    }
+}
+
+impl OcrEngine for FakeEngine {
+    fn kind(&self) -> EngineKind {
+        EngineKind::Fake
+    }
+
+    fn recognize_bytes(&mut self, data: &[u8]) -> Result<OcrResult> {
+        let image = image::load_from_memory(data).context("Failed to decode image for OCR")?;
+        self.recognize_image(&image.to_rgba8())
+    }
+
+    fn recognize_image(&mut self, image: &RgbaImage) -> Result<OcrResult> {
+        let (width, height) = image.dimensions();
+        let text = self
+            .text
+            .clone()
+            .unwrap_or_else(|| format!("{}x{} {:016x}", width, height, fnv1a(image.as_raw())));
+
+        let mut result = OcrResult::new(text.clone());
+        result.words = vec![OcrWord {
+            text,
+            confidence: 100.0,
+            bbox: BoundingBox {
+                x: 0,
+                y: 0,
+                width: width as i32,
+                height: height as i32,
+            },
+        }];
+        result.confidence = Some(100.0);
+
+        Ok(result)
+    }
+}
+
+/// FNV-1a hash, stable across builds and platforms
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn test_same_pixels_give_same_text() {
+        let mut engine = FakeEngine::new();
+        let black = RgbaImage::from_pixel(4, 2, Rgba([0, 0, 0, 255]));
+        let white = RgbaImage::from_pixel(4, 2, Rgba([255, 255, 255, 255]));
+
+        let first = engine.recognize_image(&black).unwrap();
+        assert_eq!(engine.recognize_image(&black).unwrap().text, first.text);
+        assert_ne!(engine.recognize_image(&white).unwrap().text, first.text);
+        assert!(first.text.starts_with("4x2 "));
+    }
+
+    #[test]
+    fn test_fixed_text_from_encoded_image() {
+        let image = RgbaImage::from_pixel(3, 3, Rgba([10, 20, 30, 255]));
+        let png_data = crate::ScreenCaptureService::to_png(&image).unwrap();
+
+        let result = FakeEngine::with_text("Hello")
+            .recognize_bytes(&png_data)
+            .unwrap();
+        assert_eq!(result.text, "Hello");
+        assert_eq!(result.words[0].bbox.width, 3);
+    }
+}
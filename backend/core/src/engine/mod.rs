@@ -0,0 +1,216 @@
+//! OCR engines
+//!
+//! `OcrService` recognizes text through the `OcrEngine` trait. Tesseract
+//! is the default engine (`tesseract` cargo feature), `ocrs` is a pure-Rust
+//! engine available with the `ocrs` cargo feature, and the fake engine
+//! returns deterministic text without reading the image, for tests and
+//! hosts without an engine.
+
+mod fake;
+#[cfg(feature = "ocrs")]
+mod ocrs;
+#[cfg(feature = "tesseract")]
+mod tesseract;
+
+use anyhow::{Context, Result};
+use image::RgbaImage;
+use log::error;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+#[cfg(feature = "tesseract")]
+use super::ocr_worker::{self, OcrWorker};
+use super::screen_capture::ScreenCaptureService;
+use crate::models::{OcrResult, OcrSettings};
+
+pub use self::fake::FakeEngine;
+#[cfg(feature = "ocrs")]
+pub use self::ocrs::OcrsEngine;
+#[cfg(feature = "tesseract")]
+pub use self::tesseract::{
+    tesseract_languages, tesseract_version, InstalledLanguages, TesseractEngine,
+};
+
+/// Engine used by OCR services created from now on
+static ENGINE_CONFIG: Lazy<RwLock<EngineConfig>> =
+    Lazy::new(|| RwLock::new(EngineConfig::default()));
+
+/// A text recognition backend
+pub trait OcrEngine: Send {
+    /// Engine that recognizes the text
+    fn kind(&self) -> EngineKind;
+
+    /// Recognize text in an encoded image (PNG, JPEG, ...)
+    fn recognize_bytes(&mut self, data: &[u8]) -> Result<OcrResult>;
+
+    /// Recognize text in a captured image
+    fn recognize_image(&mut self, image: &RgbaImage) -> Result<OcrResult> {
+        let png_data =
+            ScreenCaptureService::to_png(image).context("Failed to encode image for OCR")?;
+        self.recognize_bytes(&png_data)
+    }
+}
+
+/// Available OCR engines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EngineKind {
+    /// Tesseract through libtesseract, optionally in a worker process
+    Tesseract,
+
+    /// The pure-Rust ocrs engine
+    Ocrs,
+
+    /// Deterministic fake text, no recognition
+    Fake,
+}
+
+impl EngineKind {
+    /// Every engine, available in this build or not
+    pub const ALL: [EngineKind; 3] = [EngineKind::Tesseract, EngineKind::Ocrs, EngineKind::Fake];
+
+    /// Name of the engine as used in the configuration
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EngineKind::Tesseract => "tesseract",
+            EngineKind::Ocrs => "ocrs",
+            EngineKind::Fake => "fake",
+        }
+    }
+
+    /// Check whether the engine was compiled into this binary
+    pub fn is_available(self) -> bool {
+        match self {
+            EngineKind::Tesseract => cfg!(feature = "tesseract"),
+            EngineKind::Ocrs => cfg!(feature = "ocrs"),
+            EngineKind::Fake => true,
+        }
+    }
+}
+
+impl Default for EngineKind {
+    /// Tesseract, or ocrs in builds with ocrs but without Tesseract
+    fn default() -> Self {
+        if !cfg!(feature = "tesseract") && cfg!(feature = "ocrs") {
+            EngineKind::Ocrs
+        } else {
+            EngineKind::Tesseract
+        }
+    }
+}
+
+impl std::str::FromStr for EngineKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.trim().to_lowercase().as_str() {
+            "tesseract" => Ok(EngineKind::Tesseract),
+            "ocrs" => Ok(EngineKind::Ocrs),
+            "fake" => Ok(EngineKind::Fake),
+            _ => Err(format!(
+                "Unknown OCR engine '{}', expected tesseract, ocrs or fake",
+                name
+            )),
+        }
+    }
+}
+
+/// Which engine new OCR services use and where it finds its models
+#[derive(Debug, Clone, PartialEq)]
+pub struct EngineConfig {
+    /// Engine of new OCR services
+    pub kind: EngineKind,
+
+    /// Directory with the detection and recognition models of ocrs
+    pub model_dir: PathBuf,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            kind: EngineKind::default(),
+            model_dir: PathBuf::from("./models"),
+        }
+    }
+}
+
+/// Set the engine of OCR services created from now on
+pub fn configure(config: EngineConfig) {
+    match ENGINE_CONFIG.write() {
+        Ok(mut engine_config) => *engine_config = config,
+        Err(e) => error!("Failed to lock OCR engine configuration: {}", e),
+    }
+}
+
+/// Get the configured engine
+pub fn config() -> EngineConfig {
+    ENGINE_CONFIG
+        .read()
+        .map(|config| config.clone())
+        .unwrap_or_default()
+}
+
+/// Create an engine of the configured kind for the given settings
+///
+/// Tesseract runs in a child process when the out-of-process worker is enabled.
+#[cfg_attr(
+    not(any(feature = "tesseract", feature = "ocrs")),
+    allow(unused_variables)
+)]
+pub fn create_engine(settings: &OcrSettings) -> Result<Box<dyn OcrEngine>> {
+    let config = config();
+
+    match config.kind {
+        #[cfg(feature = "tesseract")]
+        EngineKind::Tesseract if ocr_worker::is_enabled() => {
+            Ok(Box::new(OcrWorker::spawn(settings)?))
+        }
+        #[cfg(feature = "tesseract")]
+        EngineKind::Tesseract => Ok(Box::new(TesseractEngine::new(settings)?)),
+        #[cfg(feature = "ocrs")]
+        EngineKind::Ocrs => Ok(Box::new(OcrsEngine::new(&config.model_dir, settings)?)),
+        EngineKind::Fake => Ok(Box::new(FakeEngine::new())),
+        #[allow(unreachable_patterns)]
+        kind => Err(anyhow::anyhow!(
+            "OCR engine '{}' is not available, rebuild with the '{}' feature",
+            kind.as_str(),
+            kind.as_str()
+        )),
+    }
+}
+
+/// Check whether new OCR services run Tesseract in a worker process
+pub fn uses_worker() -> bool {
+    #[cfg(feature = "tesseract")]
+    {
+        config().kind == EngineKind::Tesseract && ocr_worker::is_enabled()
+    }
+    #[cfg(not(feature = "tesseract"))]
+    {
+        false
+    }
+}
+
+/// Names of the OCR engines compiled into this binary
+pub fn available_engines() -> Vec<&'static str> {
+    EngineKind::ALL
+        .into_iter()
+        .filter(|kind| kind.is_available())
+        .map(EngineKind::as_str)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_engine_kind() {
+        assert_eq!("Tesseract".parse(), Ok(EngineKind::Tesseract));
+        assert_eq!(" fake ".parse(), Ok(EngineKind::Fake));
+        assert!("easyocr".parse::<EngineKind>().is_err());
+        assert_eq!(EngineKind::Ocrs.as_str(), "ocrs");
+    }
+}
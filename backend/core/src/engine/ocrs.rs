@@ -0,0 +1,135 @@
+use anyhow::{anyhow, Context, Result};
+use image::RgbaImage;
+use log::debug;
+use ocrs::{ImageSource, OcrEngineParams};
+use once_cell::sync::Lazy;
+use rten::Model;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::{EngineKind, OcrEngine};
+use crate::models::{BoundingBox, OcrResult, OcrSettings, OcrWord};
+
+/// File name of the text detection model in the model directory
+const DETECTION_MODEL: &str = "text-detection.rten";
+
+/// File name of the text recognition model in the model directory
+const RECOGNITION_MODEL: &str = "text-recognition.rten";
+
+/// Engines by model directory, so the models are loaded only once
+static ENGINES: Lazy<Mutex<HashMap<PathBuf, Arc<ocrs::OcrEngine>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// The pure-Rust ocrs engine
+///
+/// Needs no system libraries, only the two models from
+/// <https://github.com/robertknight/ocrs-models> in the model directory.
+/// The models only read English text and segment pages on their own, so
+/// the language and segmentation mode of the OCR settings can't be changed.
+pub struct OcrsEngine {
+    engine: Arc<ocrs::OcrEngine>,
+}
+
+impl OcrsEngine {
+    /// Get the engine for the models in a directory
+    ///
+    /// The models are loaded by the first engine of the directory and shared
+    /// with the later ones. Settings other than the default are rejected.
+    pub fn new(model_dir: &Path, settings: &OcrSettings) -> Result<Self> {
+        let defaults = OcrSettings::default();
+        if settings.language != defaults.language || settings.psm != defaults.psm {
+            return Err(anyhow!(
+                "The ocrs engine only supports language '{}' and page segmentation mode {}",
+                defaults.language,
+                defaults.psm
+            ));
+        }
+
+        let mut engines = ENGINES
+            .lock()
+            .map_err(|e| anyhow!("Failed to lock ocrs engines: {}", e))?;
+        let engine = match engines.get(model_dir) {
+            Some(engine) => Arc::clone(engine),
+            None => {
+                let engine = Arc::new(Self::load(model_dir)?);
+                engines.insert(model_dir.to_path_buf(), Arc::clone(&engine));
+                engine
+            }
+        };
+
+        Ok(Self { engine })
+    }
+
+    /// Load the detection and recognition models from a directory
+    fn load(model_dir: &Path) -> Result<ocrs::OcrEngine> {
+        debug!("Loading ocrs models from {}", model_dir.display());
+        let load = |name: &str| {
+            let path = model_dir.join(name);
+            Model::load_file(&path)
+                .with_context(|| format!("Failed to load ocrs model {}", path.display()))
+        };
+
+        ocrs::OcrEngine::new(OcrEngineParams {
+            detection_model: Some(load(DETECTION_MODEL)?),
+            recognition_model: Some(load(RECOGNITION_MODEL)?),
+            ..Default::default()
+        })
+        .context("Failed to initialize ocrs engine")
+    }
+}
+
+impl OcrEngine for OcrsEngine {
+    fn kind(&self) -> EngineKind {
+        EngineKind::Ocrs
+    }
+
+    fn recognize_bytes(&mut self, data: &[u8]) -> Result<OcrResult> {
+        let image = image::load_from_memory(data).context("Failed to decode image for OCR")?;
+        self.recognize_image(&image.to_rgba8())
+    }
+
+    fn recognize_image(&mut self, image: &RgbaImage) -> Result<OcrResult> {
+        let source = ImageSource::from_bytes(image.as_raw(), image.dimensions())
+            .context("Failed to read image for OCR")?;
+        let input = self
+            .engine
+            .prepare_input(source)
+            .context("Failed to prepare image for OCR")?;
+
+        let word_rects = self
+            .engine
+            .detect_words(&input)
+            .context("Failed to detect words with OCR")?;
+        let line_rects = self.engine.find_text_lines(&input, &word_rects);
+        let lines = self
+            .engine
+            .recognize_text(&input, &line_rects)
+            .context("Failed to extract text with OCR")?;
+
+        let mut text_lines = Vec::new();
+        let mut words = Vec::new();
+        for line in lines.iter().flatten() {
+            text_lines.push(line.to_string());
+            for word in line.words() {
+                let rect = word.rotated_rect().bounding_rect();
+                words.push(OcrWord {
+                    text: word.to_string(),
+                    // ocrs doesn't score words, -1 is Tesseract's "no confidence"
+                    confidence: -1.0,
+                    bbox: BoundingBox {
+                        x: rect.left().round() as i32,
+                        y: rect.top().round() as i32,
+                        width: rect.width().round() as i32,
+                        height: rect.height().round() as i32,
+                    },
+                });
+            }
+        }
+        debug!("ocrs recognized {} line(s)", text_lines.len());
+
+        let mut result = OcrResult::new(text_lines.join("\n").trim().to_string());
+        result.words = words;
+        Ok(result)
+    }
+}
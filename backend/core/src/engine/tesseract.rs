@@ -0,0 +1,295 @@
+use anyhow::{anyhow, Context, Result};
+use image::RgbaImage;
+use log::{debug, error};
+use std::ffi::{CStr, CString};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tesseract::Tesseract;
+
+use super::{EngineKind, OcrEngine};
+use crate::models::{BoundingBox, OcrResult, OcrSettings, OcrWord};
+use crate::screen_capture::ScreenCaptureService;
+
+/// Counter keeping temporary file names unique
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Tesseract OCR running in this process
+pub struct TesseractEngine {
+    /// Language and segmentation mode of the engine
+    settings: OcrSettings,
+
+    /// The engine
+    ///
+    /// Tesseract's builder methods consume the engine and drop it on
+    /// error, so it is `None` until the next call initializes a new one.
+    tesseract: Option<Tesseract>,
+}
+
+impl TesseractEngine {
+    /// Initialize Tesseract for the given language and segmentation mode
+    pub fn new(settings: &OcrSettings) -> Result<Self> {
+        Ok(Self {
+            settings: settings.clone(),
+            tesseract: Some(Self::init_tesseract(settings)?),
+        })
+    }
+
+    /// Initialize and configure a Tesseract engine
+    fn init_tesseract(settings: &OcrSettings) -> Result<Tesseract> {
+        Tesseract::new(None, Some(&settings.language))
+            .with_context(|| {
+                format!(
+                    "Failed to initialize Tesseract OCR engine for language '{}'",
+                    settings.language
+                )
+            })?
+            .set_variable("tessedit_pageseg_mode", &settings.psm.to_string())
+            .context("Failed to set page segmentation mode")
+    }
+
+    /// Run a Tesseract builder step on the engine
+    ///
+    /// The engine is initialized again if a previous step consumed it.
+    fn update_tesseract<E>(
+        &mut self,
+        step: impl FnOnce(Tesseract) -> std::result::Result<Tesseract, E>,
+    ) -> Result<()>
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let tesseract = match self.tesseract.take() {
+            Some(tesseract) => tesseract,
+            None => {
+                debug!("Re-initializing Tesseract after a failed call");
+                Self::init_tesseract(&self.settings)?
+            }
+        };
+
+        self.tesseract = Some(step(tesseract)?);
+        Ok(())
+    }
+
+    /// Load an image file into the engine
+    fn set_image_file(&mut self, image_path: &Path) -> Result<()> {
+        let path = image_path.to_string_lossy();
+        debug!("Performing OCR on file: {}", path);
+
+        self.update_tesseract(|tesseract| tesseract.set_image(&path))
+            .context("Failed to set image for OCR")
+    }
+
+    /// Get the text, words and confidence of the loaded image
+    fn recognize(&mut self) -> Result<OcrResult> {
+        let Some(tesseract) = self.tesseract.as_mut() else {
+            return Err(anyhow!("No image loaded for OCR"));
+        };
+
+        let text = tesseract
+            .get_text()
+            .context("Failed to extract text with OCR")?
+            .trim()
+            .to_string();
+
+        let tsv = tesseract
+            .get_tsv_text(0)
+            .context("Failed to extract word boxes with OCR")?;
+        let confidence = tesseract.mean_text_conf();
+
+        let mut result = OcrResult::new(text);
+        result.words = parse_tsv_words(&tsv);
+        result.confidence = Some(confidence as f32);
+
+        Ok(result)
+    }
+}
+
+impl OcrEngine for TesseractEngine {
+    fn kind(&self) -> EngineKind {
+        EngineKind::Tesseract
+    }
+
+    /// Any format Leptonica can read is accepted (PNG, JPEG, TIFF, ...)
+    fn recognize_bytes(&mut self, data: &[u8]) -> Result<OcrResult> {
+        debug!("Performing OCR on {} byte image", data.len());
+
+        self.update_tesseract(|tesseract| tesseract.set_image_from_mem(data))
+            .context("Failed to read image for OCR")?;
+
+        self.recognize()
+    }
+
+    fn recognize_image(&mut self, image: &RgbaImage) -> Result<OcrResult> {
+        // Save to temporary file
+        let temp_file =
+            save_to_temp_file(image).context("Failed to save captured region to file")?;
+
+        // Clean up temporary file when done - corrected syntax
+        let _cleanup = scopeguard::guard(temp_file.clone(), |path| {
+            if let Err(e) = std::fs::remove_file(&path) {
+                error!("Failed to remove temporary file: {}", e);
+            }
+        });
+
+        self.set_image_file(Path::new(&temp_file))?;
+        self.recognize()
+    }
+}
+
+/// Save an image to a temporary file
+fn save_to_temp_file(image: &RgbaImage) -> Result<String> {
+    let temp_dir = std::env::temp_dir();
+    let timestamp = chrono::Utc::now().timestamp();
+    // Several regions can be saved within the same second
+    let sequence = TEMP_FILE_COUNTER.fetch_add(1, Ordering::SeqCst);
+    let file_path = temp_dir
+        .join(format!(
+            "screen_text_reader_{}_{}_{}.png",
+            std::process::id(),
+            timestamp,
+            sequence
+        ))
+        .to_string_lossy()
+        .to_string();
+
+    // Convert image to PNG
+    let png_data = ScreenCaptureService::to_png(image)?;
+
+    // Write to file
+    std::fs::write(&file_path, png_data)?;
+
+    Ok(file_path)
+}
+
+/// Languages installed where the linked library looks for them
+#[derive(Debug, Clone)]
+pub struct InstalledLanguages {
+    /// tessdata directory the library loads language data from
+    pub datapath: String,
+
+    /// Languages with data in that directory
+    pub languages: Vec<String>,
+}
+
+/// Version of the linked Tesseract library
+pub fn tesseract_version() -> String {
+    tesseract::plumbing::version()
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// List the languages the linked library can load
+///
+/// The library is initialized for `language` with the default data path,
+/// as engines are, so the languages are the ones OCR requests can use.
+pub fn tesseract_languages(language: &str) -> Result<InstalledLanguages> {
+    use tesseract::plumbing::tesseract_sys::{
+        TessBaseAPICreate, TessBaseAPIDelete, TessBaseAPIEnd,
+        TessBaseAPIGetAvailableLanguagesAsVector, TessBaseAPIGetDatapath, TessBaseAPIInit3,
+        TessDeleteTextArray,
+    };
+
+    let c_language = CString::new(language).context("Invalid language name")?;
+
+    // SAFETY: the handle never leaves this function, and every string is
+    // copied before the array or handle holding it is freed
+    unsafe {
+        let api = TessBaseAPICreate();
+        if TessBaseAPIInit3(api, std::ptr::null(), c_language.as_ptr()) != 0 {
+            TessBaseAPIDelete(api);
+            return Err(anyhow!(
+                "Failed to initialize Tesseract for language '{}'",
+                language
+            ));
+        }
+
+        let datapath = TessBaseAPIGetDatapath(api);
+        let datapath = if datapath.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(datapath).to_string_lossy().into_owned()
+        };
+
+        let mut languages = Vec::new();
+        let names = TessBaseAPIGetAvailableLanguagesAsVector(api);
+        if !names.is_null() {
+            let mut name = names;
+            while !(*name).is_null() {
+                languages.push(CStr::from_ptr(*name).to_string_lossy().into_owned());
+                name = name.add(1);
+            }
+            TessDeleteTextArray(names);
+        }
+
+        TessBaseAPIEnd(api);
+        TessBaseAPIDelete(api);
+
+        languages.sort();
+        Ok(InstalledLanguages {
+            datapath,
+            languages,
+        })
+    }
+}
+
+/// Tesseract TSV level of word rows
+const TSV_WORD_LEVEL: &str = "5";
+
+/// Parse the words out of Tesseract TSV output
+///
+/// Columns are: level, page_num, block_num, par_num, line_num, word_num,
+/// left, top, width, height, conf, text. Only word rows carry text.
+fn parse_tsv_words(tsv: &str) -> Vec<OcrWord> {
+    tsv.lines()
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split('\t').collect();
+            if columns.len() < 12 || columns[0] != TSV_WORD_LEVEL {
+                return None;
+            }
+
+            let text = columns[11].trim();
+            if text.is_empty() {
+                return None;
+            }
+
+            Some(OcrWord {
+                text: text.to_string(),
+                confidence: columns[10].parse().ok()?,
+                bbox: BoundingBox {
+                    x: columns[6].parse().ok()?,
+                    y: columns[7].parse().ok()?,
+                    width: columns[8].parse().ok()?,
+                    height: columns[9].parse().ok()?,
+                },
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tsv_words() {
+        let tsv = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext\n\
+                   1\t1\t0\t0\t0\t0\t0\t0\t200\t50\t-1\t\n\
+                   5\t1\t1\t1\t1\t1\t10\t12\t40\t15\t96.5\tHello\n\
+                   5\t1\t1\t1\t1\t2\t55\t12\t48\t15\t91\tworld\n\
+                   5\t1\t1\t1\t1\t3\t110\t12\t4\t15\t-1\t \n";
+
+        let words = parse_tsv_words(tsv);
+
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[0].text, "Hello");
+        assert_eq!(words[0].confidence, 96.5);
+        assert_eq!(
+            words[1].bbox,
+            BoundingBox {
+                x: 55,
+                y: 12,
+                width: 48,
+                height: 15
+            }
+        );
+    }
+}
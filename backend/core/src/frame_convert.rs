@@ -0,0 +1,319 @@
+use anyhow::{anyhow, Context, Result};
+use image::RgbaImage;
+use scap::frame::{Frame, YUVFrame};
+
+use crate::models::Region;
+
+/// Byte layout of a packed (single plane) frame
+#[derive(Debug, Clone, Copy)]
+enum PackedLayout {
+    Bgra,
+    Bgr,
+    Bgrx,
+    Rgb,
+    Rgbx,
+    Xbgr,
+}
+
+impl PackedLayout {
+    /// Number of bytes used by one pixel
+    fn bytes_per_pixel(self) -> usize {
+        match self {
+            PackedLayout::Bgr | PackedLayout::Rgb => 3,
+            _ => 4,
+        }
+    }
+
+    /// Convert one pixel to RGBA
+    fn to_rgba(self, px: &[u8]) -> [u8; 4] {
+        match self {
+            PackedLayout::Bgra => [px[2], px[1], px[0], px[3]],
+            PackedLayout::Bgr | PackedLayout::Bgrx => [px[2], px[1], px[0], 255],
+            PackedLayout::Rgb => [px[0], px[1], px[2], 255],
+            PackedLayout::Rgbx => [px[0], px[1], px[2], 255],
+            PackedLayout::Xbgr => [px[3], px[2], px[1], 255],
+        }
+    }
+}
+
+/// Pixel rectangle inside a frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PixelRect {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+/// Get the width and height of a frame in pixels
+pub fn frame_size(frame: &Frame) -> (i32, i32) {
+    match frame {
+        Frame::YUVFrame(f) => (f.width, f.height),
+        Frame::RGB(f) => (f.width, f.height),
+        Frame::RGBx(f) => (f.width, f.height),
+        Frame::XBGR(f) => (f.width, f.height),
+        Frame::BGRx(f) => (f.width, f.height),
+        Frame::BGR0(f) => (f.width, f.height),
+        Frame::BGRA(f) => (f.width, f.height),
+    }
+}
+
+/// Check whether a frame has any pixels to convert
+pub fn has_pixels(frame: &Frame) -> bool {
+    let (width, height) = frame_size(frame);
+    let has_data = match frame {
+        Frame::YUVFrame(f) => !f.luminance_bytes.is_empty(),
+        Frame::RGB(f) => !f.data.is_empty(),
+        Frame::RGBx(f) => !f.data.is_empty(),
+        Frame::XBGR(f) => !f.data.is_empty(),
+        Frame::BGRx(f) => !f.data.is_empty(),
+        Frame::BGR0(f) => !f.data.is_empty(),
+        Frame::BGRA(f) => !f.data.is_empty(),
+    };
+
+    width > 0 && height > 0 && has_data
+}
+
+/// Convert a frame, or the part of it covered by `region`, to an RGBA image
+///
+/// The region is clamped to the frame bounds. Only the pixels inside the
+/// region are converted, so cropping a small area out of a large frame is cheap.
+pub fn frame_to_image(frame: &Frame, region: Option<&Region>) -> Result<RgbaImage> {
+    let (width, height) = frame_size(frame);
+    let rect = clamp_to_frame(region, width, height)?;
+
+    match frame {
+        Frame::BGRA(f) => convert_packed(&f.data, width, height, PackedLayout::Bgra, rect),
+        Frame::BGRx(f) => convert_packed(&f.data, width, height, PackedLayout::Bgrx, rect),
+        // scap strips the alpha channel of BGR0 frames, leaving three bytes per pixel
+        Frame::BGR0(f) => convert_packed(&f.data, width, height, PackedLayout::Bgr, rect),
+        Frame::RGB(f) => convert_packed(&f.data, width, height, PackedLayout::Rgb, rect),
+        Frame::RGBx(f) => convert_packed(&f.data, width, height, PackedLayout::Rgbx, rect),
+        Frame::XBGR(f) => convert_packed(&f.data, width, height, PackedLayout::Xbgr, rect),
+        Frame::YUVFrame(f) => convert_nv12(f, rect),
+    }
+}
+
+/// Resolve the pixel rectangle to convert, clamped to the frame bounds
+fn clamp_to_frame(region: Option<&Region>, width: i32, height: i32) -> Result<PixelRect> {
+    if width <= 0 || height <= 0 {
+        return Err(anyhow!("Frame has no pixels ({}x{})", width, height));
+    }
+
+    let (left, top, right, bottom) = match region {
+        Some(region) => (
+            region.x.clamp(0, width),
+            region.y.clamp(0, height),
+            region.x.saturating_add(region.width).clamp(0, width),
+            region.y.saturating_add(region.height).clamp(0, height),
+        ),
+        None => (0, 0, width, height),
+    };
+
+    if right <= left || bottom <= top {
+        return Err(anyhow!(
+            "Region lies outside the captured frame ({}x{})",
+            width,
+            height
+        ));
+    }
+
+    Ok(PixelRect {
+        x: left as usize,
+        y: top as usize,
+        width: (right - left) as usize,
+        height: (bottom - top) as usize,
+    })
+}
+
+/// Work out the row stride of a packed frame from its buffer size
+///
+/// Capture backends may pad rows or hand over a larger buffer than the
+/// image needs, so the stride is only trusted when it divides evenly.
+fn packed_stride(data_len: usize, width: usize, height: usize, bytes_per_pixel: usize) -> usize {
+    let tight = width * bytes_per_pixel;
+    if height > 0 && data_len.is_multiple_of(height) && data_len / height >= tight {
+        data_len / height
+    } else {
+        tight
+    }
+}
+
+/// Convert a rectangle of a packed frame to RGBA
+fn convert_packed(
+    data: &[u8],
+    width: i32,
+    height: i32,
+    layout: PackedLayout,
+    rect: PixelRect,
+) -> Result<RgbaImage> {
+    let bpp = layout.bytes_per_pixel();
+    let stride = packed_stride(data.len(), width as usize, height as usize, bpp);
+
+    let mut rgba = Vec::with_capacity(rect.width * rect.height * 4);
+    for row in rect.y..rect.y + rect.height {
+        let start = row * stride + rect.x * bpp;
+        let row_data = data
+            .get(start..start + rect.width * bpp)
+            .context("Frame data is smaller than its dimensions")?;
+
+        for px in row_data.chunks_exact(bpp) {
+            rgba.extend_from_slice(&layout.to_rgba(px));
+        }
+    }
+
+    RgbaImage::from_raw(rect.width as u32, rect.height as u32, rgba)
+        .context("Failed to create image from frame data")
+}
+
+/// Convert a rectangle of an NV12 (4:2:0, video range) frame to RGBA
+fn convert_nv12(frame: &YUVFrame, rect: PixelRect) -> Result<RgbaImage> {
+    let y_stride = frame.luminance_stride.max(frame.width) as usize;
+    let uv_stride = frame.chrominance_stride.max(frame.width) as usize;
+
+    let mut rgba = Vec::with_capacity(rect.width * rect.height * 4);
+    for row in rect.y..rect.y + rect.height {
+        for col in rect.x..rect.x + rect.width {
+            let luma = *frame
+                .luminance_bytes
+                .get(row * y_stride + col)
+                .context("Luminance plane is smaller than the frame")?;
+
+            // Chroma is subsampled 2x2 and stored as interleaved Cb/Cr pairs
+            let uv_index = (row / 2) * uv_stride + (col / 2) * 2;
+            let chroma = frame
+                .chrominance_bytes
+                .get(uv_index..uv_index + 2)
+                .context("Chrominance plane is smaller than the frame")?;
+
+            rgba.extend_from_slice(&yuv_to_rgba(luma, chroma[0], chroma[1]));
+        }
+    }
+
+    RgbaImage::from_raw(rect.width as u32, rect.height as u32, rgba)
+        .context("Failed to create image from frame data")
+}
+
+/// Convert a BT.601 video range YCbCr sample to RGBA
+fn yuv_to_rgba(y: u8, cb: u8, cr: u8) -> [u8; 4] {
+    let y = (y as f32 - 16.0) * 1.164;
+    let cb = cb as f32 - 128.0;
+    let cr = cr as f32 - 128.0;
+
+    let r = y + 1.596 * cr;
+    let g = y - 0.392 * cb - 0.813 * cr;
+    let b = y + 2.017 * cb;
+
+    [
+        r.round().clamp(0.0, 255.0) as u8,
+        g.round().clamp(0.0, 255.0) as u8,
+        b.round().clamp(0.0, 255.0) as u8,
+        255,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scap::frame::{BGRAFrame, BGRFrame, BGRxFrame, RGBFrame};
+
+    fn bgra_frame(width: i32, height: i32) -> Frame {
+        let data = (0..width * height)
+            .flat_map(|i| [i as u8, 0, 200, 255])
+            .collect();
+
+        Frame::BGRA(BGRAFrame {
+            display_time: 0,
+            width,
+            height,
+            data,
+        })
+    }
+
+    #[test]
+    fn test_bgra_channels_are_swapped() {
+        let image = frame_to_image(&bgra_frame(2, 1), None).unwrap();
+        assert_eq!(image.get_pixel(1, 0).0, [200, 0, 1, 255]);
+    }
+
+    #[test]
+    fn test_crop_region() {
+        let image = frame_to_image(&bgra_frame(4, 3), Some(&Region::new(1, 1, 2, 2))).unwrap();
+
+        assert_eq!(image.dimensions(), (2, 2));
+        let blues: Vec<u8> = image.pixels().map(|px| px.0[2]).collect();
+        assert_eq!(blues, vec![5, 6, 9, 10]);
+    }
+
+    #[test]
+    fn test_crop_region_clamps_to_bounds() {
+        let frame = bgra_frame(4, 3);
+        let image = frame_to_image(&frame, Some(&Region::new(2, -1, 10, 10))).unwrap();
+        assert_eq!(image.dimensions(), (2, 3));
+
+        assert!(frame_to_image(&frame, Some(&Region::new(10, 10, 5, 5))).is_err());
+    }
+
+    #[test]
+    fn test_padded_rows() {
+        // Two BGRx pixels per row, padded to 12 bytes
+        let data = vec![
+            1, 2, 3, 0, 4, 5, 6, 0, 9, 9, 9, 9, //
+            7, 8, 9, 0, 10, 11, 12, 0, 9, 9, 9, 9,
+        ];
+        let frame = Frame::BGRx(BGRxFrame {
+            display_time: 0,
+            width: 2,
+            height: 2,
+            data,
+        });
+
+        let image = frame_to_image(&frame, None).unwrap();
+        assert_eq!(image.get_pixel(0, 1).0, [9, 8, 7, 255]);
+        assert_eq!(image.get_pixel(1, 1).0, [12, 11, 10, 255]);
+    }
+
+    #[test]
+    fn test_rgb_frame() {
+        let frame = Frame::RGB(RGBFrame {
+            display_time: 0,
+            width: 1,
+            height: 1,
+            data: vec![10, 20, 30],
+        });
+
+        let image = frame_to_image(&frame, None).unwrap();
+        assert_eq!(image.get_pixel(0, 0).0, [10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn test_bgr0_frame() {
+        let frame = Frame::BGR0(BGRFrame {
+            display_time: 0,
+            width: 2,
+            height: 2,
+            data: vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12],
+        });
+
+        let image = frame_to_image(&frame, None).unwrap();
+        assert_eq!(image.get_pixel(1, 0).0, [6, 5, 4, 255]);
+        assert_eq!(image.get_pixel(0, 1).0, [9, 8, 7, 255]);
+    }
+
+    #[test]
+    fn test_nv12_frame() {
+        // 2x2 white pixels share one neutral chroma sample
+        let frame = Frame::YUVFrame(YUVFrame {
+            display_time: 0,
+            width: 2,
+            height: 2,
+            luminance_bytes: vec![235; 4],
+            luminance_stride: 2,
+            chrominance_bytes: vec![128, 128],
+            chrominance_stride: 2,
+        });
+
+        let image = frame_to_image(&frame, None).unwrap();
+        assert_eq!(image.get_pixel(1, 1).0, [255, 255, 255, 255]);
+    }
+}
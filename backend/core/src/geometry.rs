@@ -0,0 +1,383 @@
+use log::error;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use thiserror::Error;
+
+use super::capture_session;
+use crate::models::{Anchor, CoordinateSpace, DisplaySize, Region};
+
+/// Scale factor of every display with a configured override
+static SCALE_FACTORS: Lazy<RwLock<HashMap<u32, f64>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// How a region was translated to pixels of a captured frame
+#[derive(Debug, Clone, Serialize)]
+pub struct RegionMapping {
+    /// Coordinate space the region was given in
+    pub space: CoordinateSpace,
+
+    /// Scale factor of the display (physical pixels per logical pixel)
+    pub scale_factor: f64,
+
+    /// The region in physical pixels of the frame
+    pub physical: Region,
+
+    /// The region extended past the display and was shrunk to fit
+    pub clamped: bool,
+}
+
+/// A region that can't be placed on a display
+#[derive(Debug, Error, PartialEq)]
+pub enum GeometryError {
+    /// The region extends past the display and clamping is off
+    #[error(
+        "Region {width}x{height} at ({x}, {y}) extends past the display ({display_width}x{display_height})"
+    )]
+    OutOfBounds {
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        display_width: i32,
+        display_height: i32,
+    },
+
+    /// Not a single pixel of the region is on the display
+    #[error("Region lies outside the display ({display_width}x{display_height})")]
+    Outside {
+        display_width: i32,
+        display_height: i32,
+    },
+
+    /// Anchoring moved the region beyond representable coordinates
+    #[error("Region coordinates overflow")]
+    Overflow,
+}
+
+/// Override the scale factors of displays
+///
+/// Displays not listed use the factor reported by the platform, or 1.0 if
+/// it reports none.
+pub fn set_scale_factors(factors: HashMap<u32, f64>) {
+    match SCALE_FACTORS.write() {
+        Ok(mut scale_factors) => *scale_factors = factors,
+        Err(e) => error!("Failed to lock display scale factors: {}", e),
+    }
+}
+
+/// Get the scale factor of a display
+pub fn scale_factor(display_id: u32) -> f64 {
+    SCALE_FACTORS
+        .read()
+        .ok()
+        .and_then(|factors| factors.get(&display_id).copied())
+        .or_else(|| capture_session::display_info(display_id).map(|display| display.scale_factor))
+        .unwrap_or(1.0)
+}
+
+/// Parse scale factors given as `display=factor` pairs, e.g. `0=2,1=1.25`
+pub fn parse_scale_factors(spec: &str) -> Result<HashMap<u32, f64>, String> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (display, factor) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("Expected display=factor, got '{}'", entry))?;
+            let display = display
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid display id in '{}'", entry))?;
+            let factor = factor
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|factor| factor.is_finite() && *factor > 0.0)
+                .ok_or_else(|| format!("Invalid scale factor in '{}'", entry))?;
+            Ok((display, factor))
+        })
+        .collect()
+}
+
+/// Translate a region to physical pixels of a frame of the given size
+///
+/// Edges are rounded separately, so adjacent regions stay adjacent.
+pub fn to_physical(
+    region: &Region,
+    frame_width: i32,
+    frame_height: i32,
+    scale_factor: f64,
+) -> Region {
+    let (scale_x, scale_y) = match region.space {
+        CoordinateSpace::Physical => return region.clone(),
+        CoordinateSpace::Logical => (scale_factor, scale_factor),
+        CoordinateSpace::Fraction => (
+            f64::from(frame_width) / f64::from(Region::FRACTION_SCALE),
+            f64::from(frame_height) / f64::from(Region::FRACTION_SCALE),
+        ),
+    };
+
+    let scale = |value: i32, factor: f64| (f64::from(value) * factor).round() as i32;
+    let left = scale(region.x, scale_x);
+    let top = scale(region.y, scale_y);
+    let right = scale(region.x.saturating_add(region.width), scale_x);
+    let bottom = scale(region.y.saturating_add(region.height), scale_y);
+
+    Region {
+        x: left,
+        y: top,
+        width: right.saturating_sub(left),
+        height: bottom.saturating_sub(top),
+        space: CoordinateSpace::Physical,
+        ..region.clone()
+    }
+}
+
+/// Move or scale a region defined on its reference display to a display of another size
+///
+/// `display` is the current display size in the region's coordinate space.
+/// The returned region is anchored to the top-left corner.
+pub fn apply_anchor(region: &Region, display: DisplaySize) -> Result<Region, GeometryError> {
+    let reference = match (region.anchor, region.reference) {
+        (Anchor::TopLeft, _) | (_, None) => return Ok(region.clone()),
+        (_, Some(reference)) => reference,
+    };
+
+    let (x, y, width, height) = (
+        i64::from(region.x),
+        i64::from(region.y),
+        i64::from(region.width),
+        i64::from(region.height),
+    );
+    let grow_x = i64::from(display.width) - i64::from(reference.width);
+    let grow_y = i64::from(display.height) - i64::from(reference.height);
+
+    let (left, top, right, bottom) = match region.anchor {
+        Anchor::TopLeft => (x, y, x + width, y + height),
+        Anchor::BottomRight => (
+            x + grow_x,
+            y + grow_y,
+            x + width + grow_x,
+            y + height + grow_y,
+        ),
+        Anchor::Center => (
+            x + grow_x / 2,
+            y + grow_y / 2,
+            x + width + grow_x / 2,
+            y + height + grow_y / 2,
+        ),
+        Anchor::Percentage => {
+            let scale = |value: i64, current: i32, reference: i32| {
+                (value as f64 * f64::from(current) / f64::from(reference)).round() as i64
+            };
+            (
+                scale(x, display.width, reference.width),
+                scale(y, display.height, reference.height),
+                scale(x + width, display.width, reference.width),
+                scale(y + height, display.height, reference.height),
+            )
+        }
+    };
+
+    let coordinate = |value: i64| i32::try_from(value).map_err(|_| GeometryError::Overflow);
+    Ok(Region {
+        x: coordinate(left)?,
+        y: coordinate(top)?,
+        width: coordinate(right - left)?,
+        height: coordinate(bottom - top)?,
+        anchor: Anchor::TopLeft,
+        reference: None,
+        ..region.clone()
+    })
+}
+
+/// Check a physical region against the frame, shrinking it to fit if `clamp` is set
+///
+/// Returns the region and whether it was shrunk.
+pub fn fit_to_frame(
+    region: Region,
+    frame_width: i32,
+    frame_height: i32,
+    clamp: bool,
+) -> Result<(Region, bool), GeometryError> {
+    let left = i64::from(region.x);
+    let top = i64::from(region.y);
+    let right = left + i64::from(region.width);
+    let bottom = top + i64::from(region.height);
+    let (frame_right, frame_bottom) = (i64::from(frame_width), i64::from(frame_height));
+
+    if left >= 0 && top >= 0 && right <= frame_right && bottom <= frame_bottom {
+        return Ok((region, false));
+    }
+
+    if !clamp {
+        return Err(GeometryError::OutOfBounds {
+            x: region.x,
+            y: region.y,
+            width: region.width,
+            height: region.height,
+            display_width: frame_width,
+            display_height: frame_height,
+        });
+    }
+
+    let (left, top) = (left.clamp(0, frame_right), top.clamp(0, frame_bottom));
+    let (right, bottom) = (right.clamp(0, frame_right), bottom.clamp(0, frame_bottom));
+    if right <= left || bottom <= top {
+        return Err(GeometryError::Outside {
+            display_width: frame_width,
+            display_height: frame_height,
+        });
+    }
+
+    // Everything is within the frame now, so it fits in i32
+    Ok((
+        Region {
+            x: left as i32,
+            y: top as i32,
+            width: (right - left) as i32,
+            height: (bottom - top) as i32,
+            ..region
+        },
+        true,
+    ))
+}
+
+/// Translate a region to physical pixels of a frame of its display
+///
+/// The region is anchored to the current display size, converted to
+/// physical pixels and checked against (or clamped to) the frame.
+pub fn map_region(
+    region: &Region,
+    frame_width: i32,
+    frame_height: i32,
+) -> Result<RegionMapping, GeometryError> {
+    let scale_factor = scale_factor(region.display_id);
+
+    // Anchors work in the region's own space
+    let display = match region.space {
+        CoordinateSpace::Logical => DisplaySize {
+            width: (f64::from(frame_width) / scale_factor).round() as i32,
+            height: (f64::from(frame_height) / scale_factor).round() as i32,
+        },
+        _ => DisplaySize {
+            width: frame_width,
+            height: frame_height,
+        },
+    };
+    let anchored = apply_anchor(region, display)?;
+    let physical = to_physical(&anchored, frame_width, frame_height, scale_factor);
+    let (physical, clamped) = fit_to_frame(physical, frame_width, frame_height, region.clamp)?;
+
+    Ok(RegionMapping {
+        space: region.space,
+        scale_factor,
+        physical,
+        clamped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(space: CoordinateSpace, x: i32, y: i32, width: i32, height: i32) -> Region {
+        Region {
+            space,
+            ..Region::new(x, y, width, height)
+        }
+    }
+
+    fn bounds(region: &Region) -> (i32, i32, i32, i32) {
+        (region.x, region.y, region.width, region.height)
+    }
+
+    #[test]
+    fn test_logical_regions_are_scaled() {
+        let logical = region(CoordinateSpace::Logical, 10, 20, 100, 50);
+        assert_eq!(
+            bounds(&to_physical(&logical, 3840, 2160, 2.0)),
+            (20, 40, 200, 100)
+        );
+
+        let logical = region(CoordinateSpace::Logical, 1, 1, 1, 1);
+        assert_eq!(
+            bounds(&to_physical(&logical, 3840, 2160, 1.5)),
+            (2, 2, 1, 1)
+        );
+    }
+
+    #[test]
+    fn test_fraction_regions_cover_the_frame() {
+        let right_half = region(CoordinateSpace::Fraction, 5_000, 0, 5_000, 10_000);
+        let physical = to_physical(&right_half, 1921, 1080, 2.0);
+
+        assert_eq!(bounds(&physical), (961, 0, 960, 1080));
+        assert_eq!(physical.space, CoordinateSpace::Physical);
+    }
+
+    fn anchored(anchor: Anchor, x: i32, y: i32) -> Region {
+        Region {
+            anchor,
+            reference: Some(DisplaySize {
+                width: 1920,
+                height: 1080,
+            }),
+            ..Region::new(x, y, 100, 50)
+        }
+    }
+
+    #[test]
+    fn test_anchors_follow_display_size() {
+        let display = DisplaySize {
+            width: 2560,
+            height: 1440,
+        };
+
+        let region = apply_anchor(&anchored(Anchor::TopLeft, 10, 20), display).unwrap();
+        assert_eq!(bounds(&region), (10, 20, 100, 50));
+
+        let region = apply_anchor(&anchored(Anchor::BottomRight, 1800, 1000), display).unwrap();
+        assert_eq!(bounds(&region), (2440, 1360, 100, 50));
+
+        let region = apply_anchor(&anchored(Anchor::Center, 910, 515), display).unwrap();
+        assert_eq!(bounds(&region), (1230, 695, 100, 50));
+
+        let region = apply_anchor(&anchored(Anchor::Percentage, 960, 540), display).unwrap();
+        assert_eq!(bounds(&region), (1280, 720, 133, 67));
+        assert_eq!(region.anchor, Anchor::TopLeft);
+    }
+
+    #[test]
+    fn test_fit_to_frame() {
+        let inside = Region::new(0, 0, 1920, 1080);
+        assert!(!fit_to_frame(inside, 1920, 1080, false).unwrap().1);
+
+        let past_edge = Region::new(1900, 1000, 100, 100);
+        assert!(matches!(
+            fit_to_frame(past_edge.clone(), 1920, 1080, false),
+            Err(GeometryError::OutOfBounds { .. })
+        ));
+
+        let (clamped, was_clamped) = fit_to_frame(past_edge, 1920, 1080, true).unwrap();
+        assert_eq!(bounds(&clamped), (1900, 1000, 20, 80));
+        assert!(was_clamped);
+
+        assert!(matches!(
+            fit_to_frame(Region::new(2000, 0, 10, 10), 1920, 1080, true),
+            Err(GeometryError::Outside { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_scale_factors() {
+        let factors = parse_scale_factors("0=2, 1=1.25").unwrap();
+        assert_eq!(factors[&0], 2.0);
+        assert_eq!(factors[&1], 1.25);
+
+        assert!(parse_scale_factors("").unwrap().is_empty());
+        assert!(parse_scale_factors("0=0").is_err());
+        assert!(parse_scale_factors("primary=2").is_err());
+    }
+}
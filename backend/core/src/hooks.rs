@@ -0,0 +1,48 @@
+//! Callbacks the embedding application installs to observe the pipeline
+//!
+//! Capture streams and OCR workers fail in the background, where no caller
+//! can receive the error, so they report it here. Without a hook installed
+//! errors are only logged.
+
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// Receives the failed component and the error message
+pub type ErrorHook = fn(&'static str, &str);
+
+/// Receives the time the OCR engine spent recognizing one image
+pub type OcrTimeHook = fn(Duration);
+
+/// Hook receiving background errors
+static ERROR_HOOK: RwLock<Option<ErrorHook>> = RwLock::new(None);
+
+/// Hook receiving OCR times
+static OCR_TIME_HOOK: RwLock<Option<OcrTimeHook>> = RwLock::new(None);
+
+/// Report errors of background components to the given function
+pub fn set_error_hook(hook: ErrorHook) {
+    if let Ok(mut current) = ERROR_HOOK.write() {
+        *current = Some(hook);
+    }
+}
+
+/// Report the duration of every OCR call to the given function
+pub fn set_ocr_time_hook(hook: OcrTimeHook) {
+    if let Ok(mut current) = OCR_TIME_HOOK.write() {
+        *current = Some(hook);
+    }
+}
+
+/// Pass an error of a background component to the error hook
+pub(crate) fn report_error(component: &'static str, error: impl std::fmt::Display) {
+    if let Some(hook) = ERROR_HOOK.read().ok().and_then(|hook| *hook) {
+        hook(component, &error.to_string());
+    }
+}
+
+/// Pass the duration of an OCR call to the OCR time hook
+pub(crate) fn record_ocr_time(duration: Duration) {
+    if let Some(hook) = OCR_TIME_HOOK.read().ok().and_then(|hook| *hook) {
+        hook(duration);
+    }
+}
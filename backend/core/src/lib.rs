@@ -0,0 +1,48 @@
+//! Screen capture and OCR pipeline of the Screen Text Reader
+//!
+//! Captures display frames, crops regions out of them, detects whether a
+//! region changed and reads its text with an OCR engine. The readings can
+//! be normalized, held back until stable and diffed against the previous
+//! one. The HTTP server is one user of this crate, other tools can embed
+//! the pipeline directly.
+//!
+//! `models` also holds the request and response types of the HTTP API,
+//! shared by the server and `screen-text-client`.
+//!
+//! Cargo features select the backends: `capture` (screen capture through
+//! scap), `tesseract` (Tesseract OCR, optionally in a worker process) and
+//! `ocrs` (pure-Rust OCR).
+
+#[cfg(feature = "capture")]
+pub mod capture_session;
+pub mod change;
+pub mod diff;
+pub mod engine;
+#[cfg(feature = "capture")]
+pub mod frame_convert;
+pub mod geometry;
+pub mod hooks;
+pub mod models;
+pub mod monitor;
+#[cfg(not(feature = "capture"))]
+pub mod no_capture;
+pub mod normalize;
+pub mod numeric;
+pub mod ocr;
+#[cfg(feature = "tesseract")]
+pub mod ocr_worker;
+pub mod scheduler;
+pub mod screen_capture;
+pub mod stability;
+
+#[cfg(not(feature = "capture"))]
+pub use no_capture as capture_session;
+#[cfg(not(feature = "capture"))]
+pub use no_capture as frame_convert;
+
+pub use engine::{EngineConfig, EngineKind, OcrEngine};
+pub use models::{MonitoredRegion, OcrResult, OcrSettings, Region};
+pub use monitor::{Monitor, MonitorSink};
+pub use ocr::OcrService;
+pub use screen_capture::{CaptureError, DisplayInfo, ScreenCaptureService};
+pub use stability::Stabilizer;
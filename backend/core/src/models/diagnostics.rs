@@ -0,0 +1,246 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Response of the health endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HealthResponse {
+    /// The server is up and its monitor thread isn't hung
+    pub live: bool,
+
+    /// Every component needed to read the screen is working
+    pub ready: bool,
+
+    /// Outcome of the individual readiness checks
+    pub checks: HealthChecks,
+}
+
+/// Individual readiness checks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HealthChecks {
+    /// Screen capture is supported on this platform
+    pub capture_supported: bool,
+
+    /// Screen capture permission has been granted
+    pub capture_permission: bool,
+
+    /// The monitor's OCR engine is initialized
+    pub ocr_ready: bool,
+
+    /// The monitor thread is running
+    pub monitor_alive: bool,
+}
+
+/// Liveness of the monitor thread
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MonitorLiveness {
+    /// The monitor reported in recently
+    pub alive: bool,
+
+    /// When the monitor last reported in
+    pub last_heartbeat: Option<DateTime<Utc>>,
+}
+
+/// Lifecycle state of the supervised monitor thread
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum MonitorState {
+    /// The monitor is initializing OCR
+    Starting,
+
+    /// The monitor is initialized and watching regions
+    Running,
+
+    /// The monitor failed and waits before restarting
+    Restarting,
+
+    /// The monitor exited for good (screen capture is not supported)
+    Stopped,
+}
+
+/// Why the monitor last stopped unexpectedly
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MonitorFailure {
+    /// Error or panic message
+    pub message: String,
+
+    /// The monitor panicked instead of returning an error
+    pub panicked: bool,
+
+    /// When the failure occurred
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Supervision status of the monitor thread
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MonitorSupervision {
+    /// Current lifecycle state
+    pub state: MonitorState,
+
+    /// Number of times the monitor was restarted
+    pub restarts: u64,
+
+    /// Number of times the monitor failed or panicked
+    pub failures: u64,
+
+    /// Most recent failure, if any
+    pub last_failure: Option<MonitorFailure>,
+
+    /// When the monitor was last started
+    pub started_at: Option<DateTime<Utc>>,
+
+    /// When the monitor will be restarted, while waiting to restart
+    pub next_restart_at: Option<DateTime<Utc>>,
+}
+
+/// Most recent error of a component
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ComponentError {
+    /// Error message
+    pub message: String,
+
+    /// When the error occurred
+    pub timestamp: DateTime<Utc>,
+
+    /// Number of errors since startup
+    pub count: u64,
+}
+
+/// Outcome of reading the bundled sample image
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SelfTestResult {
+    /// The expected text was recognized
+    pub passed: bool,
+
+    /// Text printed on the sample image
+    pub expected: String,
+
+    /// Text that was recognized
+    pub text: Option<String>,
+
+    /// Mean recognition confidence (0-100)
+    pub confidence: Option<f32>,
+
+    /// Time the test took (in milliseconds)
+    pub duration_ms: u64,
+
+    /// Why the test could not run
+    pub error: Option<String>,
+}
+
+/// Information about the host the server runs on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PlatformInfo {
+    /// Operating system
+    pub os: String,
+
+    /// CPU architecture
+    pub arch: String,
+
+    /// Version of this application
+    pub version: String,
+}
+
+/// Where the Tesseract details of a diagnostics report come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum TesseractSource {
+    /// The Tesseract library the server reads text with
+    Library,
+
+    /// The `tesseract` binary on `PATH`, in builds without the library
+    Cli,
+}
+
+/// Response of the diagnostics endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DiagnosticsResponse {
+    /// Host information
+    pub platform: PlatformInfo,
+
+    /// Readiness checks, as reported by the health endpoint
+    pub health: HealthResponse,
+
+    /// OCR engine of new OCR services
+    pub ocr_engine: String,
+
+    /// Tesseract version, if it could be found out
+    pub tesseract_version: Option<String>,
+
+    /// Installed tessdata languages
+    pub tesseract_languages: Vec<String>,
+
+    /// tessdata directory the languages were found in
+    pub tesseract_datapath: Option<String>,
+
+    /// Where the Tesseract details come from, absent if neither source answered
+    pub tesseract_source: Option<TesseractSource>,
+
+    /// Liveness of the monitor thread
+    pub monitor: MonitorLiveness,
+
+    /// Restarts and failures of the monitor thread
+    pub supervisor: MonitorSupervision,
+
+    /// Most recent error of every component that failed since startup
+    pub last_errors: std::collections::BTreeMap<String, ComponentError>,
+
+    /// Jobs waiting for a worker
+    pub jobs_queued: usize,
+
+    /// Jobs being processed
+    pub jobs_running: usize,
+
+    /// OCR self-test, unless it was skipped
+    pub self_test: Option<SelfTestResult>,
+}
+
+/// What this build of the server can do
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Capabilities {
+    /// Version of this application
+    pub version: String,
+
+    /// Cargo features the server was built with
+    pub features: Vec<String>,
+
+    /// Screen capture was compiled in
+    pub capture: bool,
+
+    /// Screens can be captured on this host
+    pub capture_supported: bool,
+
+    /// OCR engines compiled in
+    pub engines: Vec<String>,
+
+    /// OCR engine of new OCR services
+    pub ocr_engine: String,
+
+    /// Tesseract runs in worker processes
+    pub ocr_worker: bool,
+}
+
+/// Query parameters of the diagnostics endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema, utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct DiagnosticsQuery {
+    /// Run the OCR self-test (default true)
+    #[serde(default = "default_self_test")]
+    pub self_test: bool,
+}
+
+fn default_self_test() -> bool {
+    true
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+/// Line-level difference between two readings of a region
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TextDiff {
+    /// Changed lines in order, unchanged lines are left out
+    pub lines: Vec<LineChange>,
+
+    /// Number of added lines
+    pub added: usize,
+
+    /// Number of removed lines
+    pub removed: usize,
+
+    /// Number of lines edited in place
+    pub changed: usize,
+}
+
+/// A line that differs between two readings
+///
+/// Line numbers start at 1 and refer to the previous (`old_line`) or
+/// the new (`new_line`) text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LineChange {
+    /// A line only present in the new text
+    Added { new_line: usize, text: String },
+
+    /// A line only present in the previous text
+    Removed { old_line: usize, text: String },
+
+    /// A line that was edited
+    Changed {
+        old_line: usize,
+        new_line: usize,
+        old_text: String,
+        new_text: String,
+
+        /// Edited character ranges
+        spans: Vec<CharSpan>,
+    },
+}
+
+/// Character range replaced within an edited line
+///
+/// Offsets count characters (not bytes), end offsets are exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CharSpan {
+    /// Start of the replaced range in the previous line
+    pub old_start: usize,
+
+    /// End of the replaced range in the previous line
+    pub old_end: usize,
+
+    /// Start of the replacement in the new line
+    pub new_start: usize,
+
+    /// End of the replacement in the new line
+    pub new_end: usize,
+}
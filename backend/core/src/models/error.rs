@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// Body of every error response of the HTTP API
+///
+/// Serialized as `{"error": {"code": "...", "message": "..."}}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ErrorResponse {
+    /// What went wrong
+    pub error: ErrorDetail,
+}
+
+/// Code and message of an error response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ErrorDetail {
+    /// Stable machine readable code, e.g. `not_found`
+    pub code: String,
+
+    /// Human readable description
+    pub message: String,
+}
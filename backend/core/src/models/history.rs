@@ -0,0 +1,49 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::TextDiff;
+
+/// A published change of a region's text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TextChange {
+    /// Region the text was read from
+    pub region_id: String,
+
+    /// When the new text was captured
+    pub timestamp: DateTime<Utc>,
+
+    /// The new text
+    pub text: String,
+
+    /// Difference to the previously published text
+    pub diff: TextDiff,
+}
+
+/// Query parameters of the region history endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema, utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct HistoryQuery {
+    /// Return at most this many of the latest changes
+    pub limit: Option<usize>,
+}
+
+/// Query parameters of the text event stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema, utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct EventsQuery {
+    /// Only stream changes of this region
+    pub region: Option<String>,
+}
+
+/// Sent on the text event stream when changes were dropped for a slow client
+///
+/// The client should resync through the history endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct EventsLagged {
+    /// Number of changes the client missed
+    pub skipped: u64,
+}
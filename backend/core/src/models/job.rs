@@ -0,0 +1,114 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{OcrResult, OcrSettings, Region};
+
+/// Lifecycle state of an OCR job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// Waiting for a worker
+    Queued,
+
+    /// Being processed by a worker
+    Running,
+
+    /// All items were processed
+    Completed,
+
+    /// The job could not be processed
+    Failed,
+
+    /// The job was cancelled before it finished
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Whether the job has reached a final state
+    pub fn is_finished(self) -> bool {
+        matches!(
+            self,
+            JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+}
+
+/// What a job operates on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum JobKind {
+    /// A live capture of a screen region
+    Region,
+
+    /// A single uploaded image
+    Image,
+
+    /// Several uploaded images
+    Batch,
+}
+
+/// Outcome of one item of a job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct JobItem {
+    /// Position of the item in the submitted job
+    pub index: usize,
+
+    /// OCR result if the item succeeded
+    pub result: Option<OcrResult>,
+
+    /// Error message if the item failed
+    pub error: Option<String>,
+}
+
+/// An asynchronous capture/OCR job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Job {
+    /// Unique job identifier
+    pub id: String,
+
+    /// What the job operates on
+    pub kind: JobKind,
+
+    /// Current state of the job
+    pub status: JobStatus,
+
+    /// OCR settings used by the job
+    pub settings: OcrSettings,
+
+    /// When the job was submitted
+    pub created_at: DateTime<Utc>,
+
+    /// When a worker picked the job up
+    pub started_at: Option<DateTime<Utc>>,
+
+    /// When the job reached a final state
+    pub finished_at: Option<DateTime<Utc>>,
+
+    /// Number of items in the job
+    pub total: usize,
+
+    /// Number of items processed so far
+    pub completed: usize,
+
+    /// Outcome of every processed item, in submission order
+    pub items: Vec<JobItem>,
+
+    /// Reason the job failed, if it did
+    pub error: Option<String>,
+}
+
+/// Request to OCR a live screen region in the background
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RegionJobRequest {
+    /// The region to capture
+    pub region: Region,
+
+    /// OCR settings, defaults are used when omitted
+    #[serde(default)]
+    pub settings: OcrSettings,
+}
@@ -0,0 +1,36 @@
+pub mod diagnostics;
+pub mod diff;
+pub mod error;
+pub mod history;
+pub mod job;
+pub mod ocr;
+pub mod recording;
+pub mod region;
+pub mod screenshot;
+
+// Re-export common types
+pub use diagnostics::{
+    Capabilities, ComponentError, DiagnosticsQuery, DiagnosticsResponse, HealthChecks,
+    HealthResponse, MonitorFailure, MonitorLiveness, MonitorState, MonitorSupervision,
+    PlatformInfo, SelfTestResult, TesseractSource,
+};
+pub use diff::{CharSpan, LineChange, TextDiff};
+pub use error::{ErrorDetail, ErrorResponse};
+pub use history::{EventsLagged, EventsQuery, HistoryQuery, TextChange};
+pub use job::{Job, JobItem, JobKind, JobStatus, RegionJobRequest};
+pub use ocr::{BoundingBox, OcrResult, OcrSettings, OcrWord, RegionStatus, StatusResponse};
+pub use recording::{RecordedFrame, RecordingMode, RecordingSession, StartRecordingRequest};
+pub use region::{
+    Anchor, CaptureRate, CaptureSchedule, CoordinateSpace, DisplaySize, FieldType, MonitoredRegion,
+    NumericExport, Region, RegionError, RegionErrorKind, RegionHealth, StabilityFilter,
+    TextNormalization, UnicodeForm,
+};
+pub use screenshot::{OutputFormat, ScreenshotQuery};
+
+/// Request to set a screen region for monitoring
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SetRegionRequest {
+    /// The region to monitor
+    pub region: Region,
+}
@@ -0,0 +1,173 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{CaptureRate, Region, RegionHealth, TextDiff};
+
+/// Represents the result of an OCR operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct OcrResult {
+    /// The extracted text
+    pub text: String,
+
+    /// The timestamp when the text was extracted
+    pub timestamp: DateTime<Utc>,
+
+    /// Recognized words with their positions
+    #[serde(default)]
+    pub words: Vec<OcrWord>,
+
+    /// Mean confidence of the recognition (0-100), if known
+    #[serde(default)]
+    pub confidence: Option<f32>,
+}
+
+impl OcrResult {
+    /// Creates a new OCR result with the given text and current timestamp
+    pub fn new(text: String) -> Self {
+        Self {
+            text,
+            timestamp: Utc::now(),
+            words: Vec::new(),
+            confidence: None,
+        }
+    }
+
+    /// Creates an empty OCR result
+    pub fn empty() -> Self {
+        Self::new(String::new())
+    }
+}
+
+/// A single word recognized by OCR
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct OcrWord {
+    /// The recognized word
+    pub text: String,
+
+    /// Recognition confidence (0-100)
+    pub confidence: f32,
+
+    /// Position of the word within the image
+    pub bbox: BoundingBox,
+}
+
+/// Axis-aligned rectangle in image pixel coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BoundingBox {
+    /// X-coordinate of the top-left corner
+    pub x: i32,
+
+    /// Y-coordinate of the top-left corner
+    pub y: i32,
+
+    /// Width of the box
+    pub width: i32,
+
+    /// Height of the box
+    pub height: i32,
+}
+
+/// Tunable settings of the OCR engine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema, utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+#[serde(default)]
+pub struct OcrSettings {
+    /// Tesseract language code(s), e.g. `eng` or `eng+deu`
+    pub language: String,
+
+    /// Tesseract page segmentation mode (0-13)
+    pub psm: u8,
+}
+
+impl OcrSettings {
+    /// Validates the settings before they are handed to the engine
+    pub fn validate(&self) -> Result<(), String> {
+        let valid_language = !self.language.is_empty()
+            && self
+                .language
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '+');
+        if !valid_language {
+            return Err(format!("Invalid OCR language: {:?}", self.language));
+        }
+
+        if self.psm > 13 {
+            return Err(format!(
+                "Invalid page segmentation mode {}: must be between 0 and 13",
+                self.psm
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for OcrSettings {
+    fn default() -> Self {
+        Self {
+            language: "eng".to_string(),
+            // PSM 6: Assume a single uniform block of text
+            psm: 6,
+        }
+    }
+}
+
+/// Represents a response from the status API endpoint
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct StatusResponse {
+    /// Whether monitoring is currently active
+    pub is_monitoring: bool,
+
+    /// The currently selected region (if any)
+    pub region: Option<Region>,
+
+    /// The most recently extracted text
+    pub last_text: String,
+
+    /// The timestamp of the most recent text extraction
+    pub last_update: DateTime<Utc>,
+
+    /// Whether the OCR service is ready
+    pub ocr_ready: bool,
+
+    /// Whether there is a latest screenshot available
+    pub has_screenshot: bool,
+
+    /// Status of every monitored region
+    pub regions: Vec<RegionStatus>,
+}
+
+/// Represents the monitoring status of a single region
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RegionStatus {
+    /// Identifier of the region
+    pub id: String,
+
+    /// The monitored screen area
+    pub region: Region,
+
+    /// The most recently extracted text
+    pub last_text: String,
+
+    /// The timestamp of the most recent text extraction
+    pub last_update: DateTime<Utc>,
+
+    /// Rate the region is currently captured at (absent until first captured)
+    pub capture_rate: Option<CaptureRate>,
+
+    /// Capture and OCR times, failures and the last error
+    #[serde(flatten)]
+    pub health: RegionHealth,
+
+    /// Whether `last_text` may no longer match the screen
+    pub stale: bool,
+
+    /// Difference of `last_text` to the text read before it
+    pub last_diff: Option<TextDiff>,
+}
@@ -0,0 +1,112 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{MonitoredRegion, OcrResult, Region};
+
+/// Which captured frames a recording keeps
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum RecordingMode {
+    /// Every captured region frame, including unchanged ones
+    All,
+
+    /// Only frames whose image changed and went through OCR
+    #[default]
+    Changed,
+}
+
+/// Request to start recording the monitor's captures
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct StartRecordingRequest {
+    /// Which frames to keep, only changed ones by default
+    #[serde(default)]
+    pub mode: RecordingMode,
+
+    /// Ids of the regions to record, all regions when omitted
+    #[serde(default)]
+    pub regions: Option<Vec<String>>,
+
+    /// Free text describing the session, e.g. the misread being reproduced
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// A recorded capture session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RecordingSession {
+    /// Unique session identifier
+    pub id: String,
+
+    /// Free text describing the session
+    pub label: Option<String>,
+
+    /// Which frames the session keeps
+    pub mode: RecordingMode,
+
+    /// Ids of the recorded regions, all regions when absent
+    pub region_filter: Option<Vec<String>>,
+
+    /// Monitored regions when the recording started
+    pub regions: Vec<MonitoredRegion>,
+
+    /// When the recording started
+    pub started_at: DateTime<Utc>,
+
+    /// When the recording stopped, absent while recording or if the server died
+    pub stopped_at: Option<DateTime<Utc>>,
+
+    /// Why the recording stopped on its own, if it did
+    pub stop_reason: Option<String>,
+
+    /// Number of frames in the archive
+    pub frame_count: u64,
+
+    /// Size of the archive in bytes
+    pub size_bytes: u64,
+
+    /// Whether frames are still being recorded
+    pub active: bool,
+}
+
+/// Metadata of one frame in a session archive
+///
+/// Stored next to the frame's PNG image as `frames/<sequence>-<region>.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RecordedFrame {
+    /// Position of the frame in the session, starting at 1
+    pub sequence: u64,
+
+    /// Id of the region the frame was cropped for
+    pub region_id: String,
+
+    /// Region geometry the frame was cropped with
+    pub region: Region,
+
+    /// Part of the display frame that was cropped, in physical pixels
+    pub crop: Region,
+
+    /// Scale factor of the display (physical pixels per logical pixel)
+    pub scale_factor: f64,
+
+    /// When the display frame was captured
+    pub timestamp: DateTime<Utc>,
+
+    /// Whether the image differed from the region's previous capture
+    pub changed: bool,
+
+    /// Path of the PNG image in the archive
+    pub image: String,
+
+    /// OCR engine output before normalization, absent if OCR didn't run or failed
+    pub raw_result: Option<OcrResult>,
+
+    /// Normalized OCR reading of the frame, absent if OCR didn't run or failed
+    pub result: Option<OcrResult>,
+
+    /// Error message if OCR failed
+    pub error: Option<String>,
+}
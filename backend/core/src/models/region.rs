@@ -0,0 +1,614 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Represents a rectangular region on the screen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Region {
+    /// X-coordinate of the top-left corner (in units of `space`)
+    pub x: i32,
+
+    /// Y-coordinate of the top-left corner (in units of `space`)
+    pub y: i32,
+
+    /// Width of the region (in units of `space`)
+    pub width: i32,
+
+    /// Height of the region (in units of `space`)
+    pub height: i32,
+
+    /// Display the region is located on (0 is the primary display)
+    #[serde(default)]
+    pub display_id: u32,
+
+    /// Unit of the coordinates (physical pixels by default)
+    #[serde(default)]
+    pub space: CoordinateSpace,
+
+    /// Edge or point of the display the region keeps its position relative to
+    #[serde(default)]
+    pub anchor: Anchor,
+
+    /// Display size the coordinates were chosen on, needed by every anchor but `top_left`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<DisplaySize>,
+
+    /// Shrink the region to the display instead of rejecting it when it extends past an edge
+    #[serde(default)]
+    pub clamp: bool,
+}
+
+/// How a region follows changes of the display size
+///
+/// The region is defined on a display of the `reference` size; on a
+/// display of another size it is moved (or scaled) accordingly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Anchor {
+    /// Keep the distance to the top-left corner
+    #[default]
+    TopLeft,
+
+    /// Keep the distance to the bottom-right corner
+    BottomRight,
+
+    /// Keep the distance to the display center
+    Center,
+
+    /// Keep position and size proportional to the display size
+    Percentage,
+}
+
+/// Size of a display in the coordinate space of a region
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DisplaySize {
+    /// Display width
+    pub width: i32,
+
+    /// Display height
+    pub height: i32,
+}
+
+/// Unit of region coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum CoordinateSpace {
+    /// Pixels of the captured frame
+    #[default]
+    Physical,
+
+    /// Pixels as seen by applications, physical pixels divided by the display's scale factor
+    Logical,
+
+    /// Fraction of the display size in basis points (10000 is the full width or height)
+    Fraction,
+}
+
+impl CoordinateSpace {
+    /// Name of the space as used in requests
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CoordinateSpace::Physical => "physical",
+            CoordinateSpace::Logical => "logical",
+            CoordinateSpace::Fraction => "fraction",
+        }
+    }
+}
+
+/// A region watched by the background monitor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MonitoredRegion {
+    /// Unique identifier of the region
+    pub id: String,
+
+    /// Screen area to read text from
+    pub region: Region,
+
+    /// How often the region is captured
+    #[serde(default)]
+    pub schedule: CaptureSchedule,
+
+    /// Export the number read from the region as a metric
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub numeric: Option<NumericExport>,
+
+    /// Hold back new readings until they are stable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stability: Option<StabilityFilter>,
+
+    /// Clean up the text read from the region before it is published
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalization: Option<TextNormalization>,
+}
+
+/// Rule a new reading must pass before it replaces the current text
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum StabilityFilter {
+    /// The reading must repeat in `count` consecutive captures
+    Consecutive { count: u32 },
+
+    /// The reading must persist for at least `min_ms` milliseconds
+    Duration { min_ms: u64 },
+
+    /// The reading must make up more than half of the last `window` captures
+    Majority { window: u32 },
+}
+
+impl StabilityFilter {
+    /// Largest number of captures a filter may look at
+    pub const MAX_CAPTURES: u32 = 100;
+
+    /// Longest time a reading may be held back (in milliseconds)
+    pub const MAX_DURATION_MS: u64 = 600_000;
+
+    /// Validates that the filter settings are within the supported range
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            StabilityFilter::Consecutive { count } => (1..=Self::MAX_CAPTURES).contains(&count),
+            StabilityFilter::Duration { min_ms } => min_ms <= Self::MAX_DURATION_MS,
+            StabilityFilter::Majority { window } => (1..=Self::MAX_CAPTURES).contains(&window),
+        }
+    }
+}
+
+/// Clean-up steps applied to the text of a region after OCR
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(default)]
+pub struct TextNormalization {
+    /// Unify line endings, collapse runs of spaces and drop blank lines
+    pub collapse_whitespace: bool,
+
+    /// Unicode normalization form, `None` keeps the text as recognized
+    pub unicode: Option<UnicodeForm>,
+
+    /// Replace typographic ligatures such as "ﬁ" with their letters
+    pub expand_ligatures: bool,
+
+    /// Kind of content, used to fix characters OCR commonly confuses
+    pub field: FieldType,
+
+    /// Known words that near misses are replaced with
+    pub dictionary: Vec<String>,
+
+    /// Largest number of edits for a word to be replaced by a dictionary word
+    pub max_edits: u32,
+}
+
+impl TextNormalization {
+    /// Largest number of dictionary words
+    pub const MAX_DICTIONARY_WORDS: usize = 10_000;
+
+    /// Largest supported edit distance for dictionary matches
+    pub const MAX_EDITS: u32 = 3;
+
+    /// Validates the dictionary size and edit distance
+    pub fn is_valid(&self) -> bool {
+        self.dictionary.len() <= Self::MAX_DICTIONARY_WORDS
+            && self.max_edits <= Self::MAX_EDITS
+            && self.dictionary.iter().all(|word| !word.trim().is_empty())
+    }
+}
+
+impl Default for TextNormalization {
+    fn default() -> Self {
+        Self {
+            collapse_whitespace: true,
+            unicode: Some(UnicodeForm::Nfc),
+            expand_ligatures: true,
+            field: FieldType::Text,
+            dictionary: Vec::new(),
+            max_edits: 1,
+        }
+    }
+}
+
+/// Unicode normalization form
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum UnicodeForm {
+    /// Canonical composition
+    Nfc,
+
+    /// Compatibility composition, also folds full-width and superscript characters
+    Nfkc,
+}
+
+/// Kind of content a region shows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    /// Free text, no characters are swapped
+    #[default]
+    Text,
+
+    /// Numbers, letters that look like digits are read as digits (O → 0, l → 1, S → 5)
+    Numeric,
+
+    /// Words, digits that look like letters are read as letters (0 → O, 1 → l, 5 → S)
+    Alphabetic,
+}
+
+/// Settings of a region whose text is exported as a numeric metric
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(default)]
+pub struct NumericExport {
+    /// Character separating the integer from the fractional part ('.' or ',')
+    pub decimal_separator: char,
+
+    /// Whether to count how often the value changes
+    pub count_changes: bool,
+
+    /// How long a value is exported without being confirmed by a capture (in milliseconds)
+    pub stale_after_ms: u64,
+}
+
+impl NumericExport {
+    /// Shortest time a value may stay valid (in milliseconds)
+    pub const MIN_STALE_AFTER_MS: u64 = 1_000;
+
+    /// Validates the separator and staleness timeout
+    pub fn is_valid(&self) -> bool {
+        matches!(self.decimal_separator, '.' | ',')
+            && self.stale_after_ms >= Self::MIN_STALE_AFTER_MS
+    }
+}
+
+impl Default for NumericExport {
+    fn default() -> Self {
+        Self {
+            decimal_separator: '.',
+            count_changes: false,
+            stale_after_ms: 30_000,
+        }
+    }
+}
+
+/// Capture timing of a monitored region
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(default)]
+pub struct CaptureSchedule {
+    /// Interval between captures (in milliseconds)
+    pub interval_ms: u64,
+
+    /// Whether to back off while the region is static
+    pub adaptive: bool,
+
+    /// Longest interval the adaptive mode backs off to (in milliseconds)
+    pub max_interval_ms: u64,
+}
+
+impl CaptureSchedule {
+    /// Shortest interval a region can be captured at (in milliseconds)
+    pub const MIN_INTERVAL_MS: u64 = 50;
+
+    /// Longest interval a region can be captured at, one day (in milliseconds)
+    pub const MAX_INTERVAL_MS: u64 = 24 * 60 * 60 * 1000;
+
+    /// Validates that the intervals are within the supported range
+    pub fn is_valid(&self) -> bool {
+        self.interval_ms >= Self::MIN_INTERVAL_MS
+            && self.max_interval_ms >= self.interval_ms
+            && self.max_interval_ms <= Self::MAX_INTERVAL_MS
+    }
+}
+
+impl Default for CaptureSchedule {
+    fn default() -> Self {
+        Self {
+            interval_ms: 200,
+            adaptive: false,
+            max_interval_ms: 10_000,
+        }
+    }
+}
+
+/// Capture rate a region is currently monitored at
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CaptureRate {
+    /// Interval the scheduler currently applies (in milliseconds)
+    pub interval_ms: u64,
+
+    /// Smoothed interval between the last captures (in milliseconds)
+    pub measured_interval_ms: Option<u64>,
+}
+
+/// Step of the monitor pipeline that failed for a region
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum RegionErrorKind {
+    /// Capturing the display failed
+    Capture,
+
+    /// Cropping the region out of the display frame failed
+    Crop,
+
+    /// Encoding the region image failed
+    Encode,
+
+    /// Running OCR on the region failed
+    Ocr,
+}
+
+/// Most recent failure of a region
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RegionError {
+    /// Step that failed
+    pub kind: RegionErrorKind,
+
+    /// Error message
+    pub message: String,
+
+    /// When the failure occurred
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Capture and OCR outcomes of a monitored region
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RegionHealth {
+    /// When the region was last captured successfully
+    pub last_capture: Option<DateTime<Utc>>,
+
+    /// When OCR last ran successfully on the region
+    pub last_ocr: Option<DateTime<Utc>>,
+
+    /// Failures since the last successful capture
+    pub consecutive_failures: u32,
+
+    /// Most recent failure, if any
+    pub last_error: Option<RegionError>,
+}
+
+impl RegionHealth {
+    /// Number of capture intervals without a capture after which text is stale
+    pub const STALE_AFTER_INTERVALS: u32 = 3;
+
+    /// Shortest time without a capture after which text is stale (in milliseconds)
+    pub const MIN_STALE_AFTER_MS: u64 = 5_000;
+
+    /// Record a successful capture, on which OCR ran if `ocr` is set
+    pub fn record_success(&mut self, timestamp: DateTime<Utc>, ocr: bool) {
+        self.last_capture = Some(timestamp);
+        if ocr {
+            self.last_ocr = Some(timestamp);
+        }
+        self.consecutive_failures = 0;
+    }
+
+    /// Record a failed capture or OCR run
+    pub fn record_failure(&mut self, kind: RegionErrorKind, message: String) {
+        self.consecutive_failures += 1;
+        self.last_error = Some(RegionError {
+            kind,
+            message,
+            timestamp: Utc::now(),
+        });
+    }
+
+    /// Check whether the text of the region no longer reflects the screen
+    ///
+    /// Text is stale while the latest attempts failed or when the region
+    /// wasn't captured for several of its (longest) capture intervals.
+    pub fn is_stale(&self, schedule: &CaptureSchedule, now: DateTime<Utc>) -> bool {
+        let interval_ms = if schedule.adaptive {
+            schedule.max_interval_ms
+        } else {
+            schedule.interval_ms
+        };
+        let stale_after_ms = interval_ms
+            .saturating_mul(u64::from(Self::STALE_AFTER_INTERVALS))
+            .max(Self::MIN_STALE_AFTER_MS);
+
+        match self.last_capture {
+            Some(last_capture) => {
+                self.consecutive_failures > 0
+                    || (now - last_capture).num_milliseconds()
+                        > i64::try_from(stale_after_ms).unwrap_or(i64::MAX)
+            }
+            None => true,
+        }
+    }
+}
+
+impl MonitoredRegion {
+    /// Identifier of the region set through the single-region API
+    pub const DEFAULT_ID: &'static str = "default";
+
+    /// Creates a new monitored region
+    pub fn new(id: impl Into<String>, region: Region) -> Self {
+        Self {
+            id: id.into(),
+            region,
+            schedule: CaptureSchedule::default(),
+            numeric: None,
+            stability: None,
+            normalization: None,
+        }
+    }
+}
+
+impl Region {
+    /// Creates a new Region with the specified coordinates and dimensions
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+            display_id: 0,
+            space: CoordinateSpace::Physical,
+            anchor: Anchor::TopLeft,
+            reference: None,
+            clamp: false,
+        }
+    }
+
+    /// Basis points covering a full display dimension in the fraction space
+    pub const FRACTION_SCALE: i32 = 10_000;
+
+    /// Validates the geometry that doesn't depend on the display
+    ///
+    /// Returns a message describing the first problem found.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.width <= 0 || self.height <= 0 {
+            return Err("dimensions must be positive".to_string());
+        }
+        if self.x < 0 || self.y < 0 {
+            return Err("origin must not be negative".to_string());
+        }
+        if self.x.checked_add(self.width).is_none() || self.y.checked_add(self.height).is_none() {
+            return Err("coordinates overflow".to_string());
+        }
+
+        if self.space == CoordinateSpace::Fraction {
+            if self.x + self.width > Self::FRACTION_SCALE
+                || self.y + self.height > Self::FRACTION_SCALE
+            {
+                return Err(format!(
+                    "fractions must lie within the display ({} basis points)",
+                    Self::FRACTION_SCALE
+                ));
+            }
+            if self.anchor != Anchor::TopLeft {
+                return Err(
+                    "fractions already follow the display size, anchor must be top_left"
+                        .to_string(),
+                );
+            }
+        }
+
+        match (self.anchor, self.reference) {
+            (Anchor::TopLeft, _) => Ok(()),
+            (_, Some(reference)) if reference.width > 0 && reference.height > 0 => Ok(()),
+            (_, Some(_)) => Err("reference size must be positive".to_string()),
+            (_, None) => {
+                Err("anchors other than top_left need a reference display size".to_string())
+            }
+        }
+    }
+
+    /// Validates the geometry that doesn't depend on the display
+    pub fn is_valid(&self) -> bool {
+        self.validate().is_ok()
+    }
+
+    /// Returns the area of the region in square units of its space
+    pub fn area(&self) -> i64 {
+        i64::from(self.width) * i64::from(self.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule_validity() {
+        let schedule = |interval_ms, max_interval_ms| CaptureSchedule {
+            interval_ms,
+            adaptive: true,
+            max_interval_ms,
+        };
+
+        assert!(CaptureSchedule::default().is_valid());
+        assert!(schedule(
+            CaptureSchedule::MIN_INTERVAL_MS,
+            CaptureSchedule::MAX_INTERVAL_MS
+        )
+        .is_valid());
+        assert!(!schedule(CaptureSchedule::MIN_INTERVAL_MS - 1, 1_000).is_valid());
+        assert!(!schedule(1_000, 500).is_valid());
+        assert!(!schedule(1_000, CaptureSchedule::MAX_INTERVAL_MS + 1).is_valid());
+        assert!(!schedule(u64::MAX, u64::MAX).is_valid());
+    }
+
+    #[test]
+    fn test_region_validity() {
+        let valid_region = Region::new(10, 20, 100, 50);
+        let invalid_region = Region::new(10, 20, 0, 50);
+
+        assert!(valid_region.is_valid());
+        assert!(!invalid_region.is_valid());
+
+        let fraction = |x, width| Region {
+            space: CoordinateSpace::Fraction,
+            ..Region::new(x, 0, width, 10_000)
+        };
+        assert!(fraction(2_500, 7_500).is_valid());
+        assert!(!fraction(2_500, 7_501).is_valid());
+
+        assert!(!Region::new(-1, 0, 10, 10).is_valid());
+        assert!(!Region::new(i32::MAX - 5, 0, 10, 10).is_valid());
+
+        let anchored = Region {
+            anchor: Anchor::BottomRight,
+            ..Region::new(10, 20, 100, 50)
+        };
+        assert!(!anchored.is_valid());
+        assert!(Region {
+            reference: Some(DisplaySize {
+                width: 1920,
+                height: 1080
+            }),
+            ..anchored
+        }
+        .is_valid());
+    }
+
+    #[test]
+    fn test_region_area() {
+        let region = Region::new(10, 20, 100, 50);
+        assert_eq!(region.area(), 5000);
+
+        let huge = Region::new(0, 0, i32::MAX, i32::MAX);
+        assert_eq!(huge.area(), i64::from(i32::MAX) * i64::from(i32::MAX));
+    }
+
+    #[test]
+    fn test_region_health_staleness() {
+        let schedule = CaptureSchedule::default();
+        let now = Utc::now();
+        let mut health = RegionHealth::default();
+        assert!(health.is_stale(&schedule, now));
+
+        health.record_success(now, true);
+        assert!(!health.is_stale(&schedule, now));
+        assert!(health.is_stale(&schedule, now + chrono::Duration::seconds(6)));
+
+        health.record_failure(RegionErrorKind::Capture, "timeout".to_string());
+        assert_eq!(health.consecutive_failures, 1);
+        assert!(health.is_stale(&schedule, now));
+
+        health.record_success(now, false);
+        assert_eq!(health.consecutive_failures, 0);
+        assert!(health.last_error.is_some());
+    }
+
+    #[test]
+    fn test_region_health_staleness_huge_interval() {
+        let schedule = CaptureSchedule {
+            interval_ms: u64::MAX,
+            adaptive: false,
+            ..CaptureSchedule::default()
+        };
+        let now = Utc::now();
+        let mut health = RegionHealth::default();
+        health.record_success(now, true);
+
+        assert!(!health.is_stale(&schedule, now + chrono::Duration::days(365)));
+    }
+}
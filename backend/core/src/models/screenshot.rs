@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+
+use super::{CoordinateSpace, Region};
+
+/// Image format of a screenshot response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Lossless PNG (default)
+    #[default]
+    Png,
+
+    /// Lossy JPEG, honours `quality`
+    #[serde(alias = "jpg")]
+    Jpeg,
+
+    /// Lossless WebP
+    Webp,
+}
+
+impl OutputFormat {
+    /// Name of the format as used in requests
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Png => "png",
+            OutputFormat::Jpeg => "jpeg",
+            OutputFormat::Webp => "webp",
+        }
+    }
+
+    /// MIME type of the encoded image
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            OutputFormat::Png => "image/png",
+            OutputFormat::Jpeg => "image/jpeg",
+            OutputFormat::Webp => "image/webp",
+        }
+    }
+}
+
+/// Query parameters accepted by the screenshot endpoints
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema, utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct ScreenshotQuery {
+    /// Output image format
+    pub format: Option<OutputFormat>,
+
+    /// JPEG quality from 1 to 100, only accepted for JPEG
+    pub quality: Option<u8>,
+
+    /// Maximum width of the returned image; the aspect ratio is preserved
+    pub max_width: Option<u32>,
+
+    /// Maximum height of the returned image; the aspect ratio is preserved
+    pub max_height: Option<u32>,
+
+    /// Display to capture (0 is the primary display)
+    pub display: Option<u32>,
+
+    /// X-coordinate of the crop rectangle
+    pub x: Option<i32>,
+
+    /// Y-coordinate of the crop rectangle
+    pub y: Option<i32>,
+
+    /// Width of the crop rectangle
+    pub width: Option<i32>,
+
+    /// Height of the crop rectangle
+    pub height: Option<i32>,
+
+    /// Unit of the crop rectangle (physical pixels by default)
+    pub space: Option<CoordinateSpace>,
+}
+
+impl ScreenshotQuery {
+    /// Default JPEG quality
+    pub const DEFAULT_QUALITY: u8 = 85;
+
+    /// Get the crop rectangle, if one was requested
+    ///
+    /// Returns an error message when the rectangle is only partially given or invalid.
+    pub fn crop(&self) -> Result<Option<Region>, String> {
+        match (self.x, self.y, self.width, self.height) {
+            (None, None, None, None) => Ok(None),
+            (Some(x), Some(y), Some(width), Some(height)) => {
+                // Crops past the display edge are cut off
+                let region = Region {
+                    display_id: self.display.unwrap_or(0),
+                    space: self.space.unwrap_or_default(),
+                    clamp: true,
+                    ..Region::new(x, y, width, height)
+                };
+                region
+                    .validate()
+                    .map(|()| Some(region))
+                    .map_err(|e| format!("Invalid crop: {}", e))
+            }
+            _ => Err("Invalid crop: x, y, width and height must be given together".to_string()),
+        }
+    }
+
+    /// Get the JPEG quality, validating its range
+    ///
+    /// PNG and WebP are encoded losslessly, so a quality is rejected for
+    /// them rather than silently ignored.
+    pub fn quality(&self) -> Result<u8, String> {
+        let format = self.format.unwrap_or_default();
+        if format != OutputFormat::Jpeg && self.quality.is_some() {
+            return Err(format!(
+                "quality is only supported for JPEG, {} is encoded losslessly",
+                format.as_str()
+            ));
+        }
+
+        match self.quality {
+            None => Ok(Self::DEFAULT_QUALITY),
+            Some(quality) if (1..=100).contains(&quality) => Ok(quality),
+            Some(quality) => Err(format!(
+                "Invalid quality {}: must be between 1 and 100",
+                quality
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(format: Option<OutputFormat>, quality: Option<u8>) -> ScreenshotQuery {
+        ScreenshotQuery {
+            format,
+            quality,
+            ..ScreenshotQuery::default()
+        }
+    }
+
+    #[test]
+    fn test_quality_only_for_jpeg() {
+        assert_eq!(query(Some(OutputFormat::Jpeg), Some(50)).quality(), Ok(50));
+        assert_eq!(
+            query(Some(OutputFormat::Jpeg), None).quality(),
+            Ok(ScreenshotQuery::DEFAULT_QUALITY)
+        );
+        assert!(query(Some(OutputFormat::Jpeg), Some(0)).quality().is_err());
+
+        assert!(query(Some(OutputFormat::Png), Some(50)).quality().is_err());
+        assert!(query(None, Some(50)).quality().is_err());
+        assert!(query(Some(OutputFormat::Webp), Some(50)).quality().is_err());
+        assert!(query(Some(OutputFormat::Webp), None).quality().is_ok());
+    }
+}
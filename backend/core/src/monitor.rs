@@ -0,0 +1,568 @@
+//! The monitor pipeline watching regions on screen
+//!
+//! `Monitor` keeps the state of every region between cycles. Each call to
+//! `Monitor::poll` captures the displays of the regions that are due once,
+//! crops the regions out of the frame, reads the ones whose image changed,
+//! normalizes and stabilizes the readings and diffs them against the
+//! previous one. Everything that happens is passed to a `MonitorSink`;
+//! storing, publishing or recording the results is up to the sink.
+
+use chrono::{DateTime, Utc};
+use image::RgbaImage;
+use log::{debug, error, info};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use super::capture_session::Frame;
+use super::geometry::RegionMapping;
+use super::numeric;
+use super::scheduler::IntervalScheduler;
+use super::stability::Stabilizer;
+use super::{change, diff, hooks, normalize};
+use crate::models::{CaptureRate, MonitoredRegion, OcrResult, RegionErrorKind, TextChange};
+use crate::{OcrService, ScreenCaptureService};
+
+/// Delay before retrying a display after its first failed capture
+const CAPTURE_RETRY_MIN: Duration = Duration::from_secs(1);
+
+/// Upper bound for the delay between capture attempts of a failing display
+const CAPTURE_RETRY_MAX: Duration = Duration::from_secs(30);
+
+/// Longest wait between polls, so changed regions are picked up quickly
+const MAX_POLL_WAIT: Duration = Duration::from_millis(500);
+
+/// What the monitor did with a captured region image
+pub enum FrameOutcome<'a> {
+    /// The image didn't change, OCR was skipped
+    Unchanged,
+
+    /// The image changed and OCR read it
+    Read {
+        /// What the engine returned
+        raw: &'a OcrResult,
+
+        /// The reading after the region's normalization rules
+        result: &'a OcrResult,
+    },
+
+    /// The image changed but OCR failed
+    Failed(String),
+}
+
+/// A region image cropped from a display frame
+pub struct RegionFrame<'a> {
+    /// The region the image was cropped for
+    pub region: &'a MonitoredRegion,
+
+    /// How the region was translated to pixels of the display frame
+    pub mapping: &'a RegionMapping,
+
+    /// When the display frame was captured
+    pub timestamp: DateTime<Utc>,
+
+    /// The image encoded as PNG
+    pub png_data: &'a [u8],
+
+    /// Time spent encoding the image
+    pub encode_time: Duration,
+
+    /// What OCR made of the image
+    pub outcome: FrameOutcome<'a>,
+}
+
+/// A stable reading of a region
+pub struct Reading<'a> {
+    /// The region that was read
+    pub region: &'a MonitoredRegion,
+
+    /// The normalized reading
+    pub result: OcrResult,
+
+    /// Difference to the previous reading, absent if the text is the same
+    pub change: Option<TextChange>,
+
+    /// Number parsed from the text of a numeric region
+    pub value: Option<f64>,
+}
+
+/// Receives the results of the monitor
+///
+/// Every method does nothing by default, so a sink only implements what
+/// it is interested in. They are called on the thread polling the monitor.
+pub trait MonitorSink {
+    /// A display frame was captured
+    fn display_captured(&self, _display_id: u32, _capture_time: Duration) {}
+
+    /// Capturing, cropping or encoding the image of a region failed
+    fn region_failed(&self, _region: &MonitoredRegion, _kind: RegionErrorKind, _error: &str) {}
+
+    /// The image of a region was captured, with what OCR made of it
+    fn frame(&self, _frame: &RegionFrame<'_>) {}
+
+    /// A stable reading of a region is published
+    fn reading(&self, _reading: Reading<'_>) {}
+
+    /// The published reading of a region is still what the region shows
+    fn reading_confirmed(&self, _region: &MonitoredRegion, _timestamp: DateTime<Utc>) {}
+
+    /// A cycle over the due regions finished
+    fn cycle_finished(&self, _rates: &HashMap<String, CaptureRate>, _duration: Duration) {}
+}
+
+/// Per-region bookkeeping of the monitor
+struct RegionTracker {
+    /// Hash of the last region image that was sent to OCR
+    last_hash: Option<u64>,
+
+    /// Last text published for the region
+    last_text: String,
+
+    /// Decides when the region is captured next
+    scheduler: IntervalScheduler,
+
+    /// Holds back readings until they are stable, if the region has a filter
+    stabilizer: Option<Stabilizer>,
+
+    /// Latest OCR reading, published or not
+    last_reading: Option<OcrResult>,
+}
+
+impl RegionTracker {
+    fn new(region: &MonitoredRegion, now: Instant) -> Self {
+        Self {
+            last_hash: None,
+            last_text: String::new(),
+            scheduler: IntervalScheduler::new(&region.schedule, now),
+            stabilizer: region.stability.as_ref().map(Stabilizer::new),
+            last_reading: None,
+        }
+    }
+
+    /// Check whether the region's schedule or stability filter changed since tracking began
+    fn is_outdated(&self, region: &MonitoredRegion) -> bool {
+        self.scheduler.schedule() != &region.schedule
+            || self.stabilizer.as_ref().map(Stabilizer::filter) != region.stability.as_ref()
+    }
+
+    /// Pass a reading through the stability filter
+    ///
+    /// Returns the reading if it is the stable one, `None` while the
+    /// previously published text stays current.
+    fn stabilize(&mut self, result: OcrResult) -> Option<OcrResult> {
+        let Some(stabilizer) = self.stabilizer.as_mut() else {
+            return Some(result);
+        };
+
+        self.last_reading = Some(result.clone());
+        let stable = stabilizer.observe(&result.text, Instant::now())?;
+        (stable == result.text).then_some(result)
+    }
+
+    /// Feed the latest reading again for a capture whose image didn't change
+    fn restabilize(&mut self, timestamp: DateTime<Utc>) -> Option<OcrResult> {
+        let mut result = self.last_reading.take()?;
+        result.timestamp = timestamp;
+        self.stabilize(result)
+    }
+}
+
+/// Captures and reads monitored regions on their schedules
+#[derive(Default)]
+pub struct Monitor {
+    /// Change detection state of every region, keyed by region id
+    trackers: HashMap<String, RegionTracker>,
+
+    /// Retry delay of every display whose last capture failed
+    capture_retries: HashMap<u32, Duration>,
+}
+
+impl Monitor {
+    /// Create a monitor that hasn't seen any region yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Capture and read the regions that are due
+    ///
+    /// Regions are tracked by id. A region whose schedule or stability
+    /// filter changed starts over, and removed regions are forgotten.
+    /// Returns how long to wait before polling again, zero if regions
+    /// were processed.
+    pub fn poll(
+        &mut self,
+        ocr_service: &mut OcrService,
+        regions: &[MonitoredRegion],
+        sink: &impl MonitorSink,
+    ) -> Duration {
+        // Forget regions that were removed
+        self.trackers
+            .retain(|id, _| regions.iter().any(|region| &region.id == id));
+
+        // Start tracking new regions and restart schedules that were changed
+        let now = Instant::now();
+        for region in regions {
+            let outdated = self
+                .trackers
+                .get(&region.id)
+                .is_none_or(|tracker| tracker.is_outdated(region));
+            if outdated {
+                self.trackers
+                    .insert(region.id.clone(), RegionTracker::new(region, now));
+            }
+        }
+
+        // Group the regions whose interval elapsed by display, so every
+        // display is captured once per cycle
+        let mut regions_by_display: BTreeMap<u32, Vec<&MonitoredRegion>> = BTreeMap::new();
+        for region in regions {
+            if self.trackers[&region.id].scheduler.is_due(now) {
+                regions_by_display
+                    .entry(region.region.display_id)
+                    .or_default()
+                    .push(region);
+            }
+        }
+
+        if regions_by_display.is_empty() {
+            // Wait until the next region is due, but keep reacting to changes
+            let next_due = self
+                .trackers
+                .values()
+                .map(|tracker| tracker.scheduler.next_due())
+                .min()
+                .unwrap_or(now + MAX_POLL_WAIT);
+            return next_due.saturating_duration_since(now).min(MAX_POLL_WAIT);
+        }
+
+        for (display_id, display_regions) in &regions_by_display {
+            if let Err(e) = self.process_display(ocr_service, *display_id, display_regions, sink) {
+                // Back off this display only, the others keep their schedule
+                let retry = self
+                    .capture_retries
+                    .entry(*display_id)
+                    .and_modify(|retry| *retry = (*retry * 2).min(CAPTURE_RETRY_MAX))
+                    .or_insert(CAPTURE_RETRY_MIN);
+                error!(
+                    "Error capturing display {}, retrying in {}ms: {}",
+                    display_id,
+                    retry.as_millis(),
+                    e
+                );
+
+                let failed_at = Instant::now();
+                for region in display_regions {
+                    if let Some(tracker) = self.trackers.get_mut(&region.id) {
+                        tracker.scheduler.record(false, failed_at);
+                        tracker.scheduler.defer(failed_at + *retry);
+                    }
+                    sink.region_failed(region, RegionErrorKind::Capture, &format!("{:#}", e));
+                }
+            } else {
+                self.capture_retries.remove(display_id);
+            }
+        }
+
+        let duration = now.elapsed();
+        sink.cycle_finished(&self.rates(), duration);
+        debug!("OCR cycle completed in {}ms", duration.as_millis());
+
+        Duration::ZERO
+    }
+
+    /// Current capture rate of every tracked region, keyed by region id
+    pub fn rates(&self) -> HashMap<String, CaptureRate> {
+        self.trackers
+            .iter()
+            .map(|(id, tracker)| (id.clone(), tracker.scheduler.rate()))
+            .collect()
+    }
+
+    /// Capture one frame of a display and process every region located on it
+    fn process_display(
+        &mut self,
+        ocr_service: &mut OcrService,
+        display_id: u32,
+        regions: &[&MonitoredRegion],
+        sink: &impl MonitorSink,
+    ) -> anyhow::Result<()> {
+        let capture_start = Instant::now();
+        let captured = ScreenCaptureService::capture_display(display_id).inspect_err(|e| {
+            hooks::report_error("capture", format!("Display {}: {:#}", display_id, e));
+        })?;
+        sink.display_captured(display_id, capture_start.elapsed());
+
+        // All regions cropped from the same frame share a timestamp
+        let timestamp = Utc::now();
+
+        debug!(
+            "Processing {} region(s) from frame #{} of display {}",
+            regions.len(),
+            captured.sequence,
+            display_id
+        );
+
+        for monitored in regions {
+            let Some(tracker) = self.trackers.get_mut(&monitored.id) else {
+                continue;
+            };
+
+            let changed = process_region(
+                ocr_service,
+                monitored,
+                &captured.frame,
+                timestamp,
+                tracker,
+                sink,
+            );
+            tracker.scheduler.record(changed, Instant::now());
+        }
+
+        Ok(())
+    }
+}
+
+/// Crop a region out of a display frame and read it
+///
+/// Returns whether the region's image changed since the previous capture
+/// and was read successfully.
+fn process_region(
+    ocr_service: &mut OcrService,
+    monitored: &MonitoredRegion,
+    display_frame: &Frame,
+    timestamp: DateTime<Utc>,
+    tracker: &mut RegionTracker,
+    sink: &impl MonitorSink,
+) -> bool {
+    match ScreenCaptureService::crop_frame(display_frame, &monitored.region) {
+        Ok((image, mapping)) => read_region(
+            ocr_service,
+            monitored,
+            &image,
+            &mapping,
+            timestamp,
+            tracker,
+            sink,
+        ),
+        Err(e) => {
+            error!("Error cropping region '{}': {}", monitored.id, e);
+            hooks::report_error("crop", format!("Region '{}': {:#}", monitored.id, e));
+            sink.region_failed(monitored, RegionErrorKind::Crop, &format!("{:#}", e));
+            false
+        }
+    }
+}
+
+/// Run change detection and OCR on the cropped image of a region
+fn read_region(
+    ocr_service: &mut OcrService,
+    monitored: &MonitoredRegion,
+    image: &RgbaImage,
+    mapping: &RegionMapping,
+    timestamp: DateTime<Utc>,
+    tracker: &mut RegionTracker,
+    sink: &impl MonitorSink,
+) -> bool {
+    // Convert image to PNG data for storage and comparison
+    let encode_start = Instant::now();
+    let png_data = match ScreenCaptureService::to_png(image) {
+        Ok(png_data) => png_data,
+        Err(e) => {
+            error!("Error converting region '{}' to PNG: {}", monitored.id, e);
+            hooks::report_error("encode", format!("Region '{}': {:#}", monitored.id, e));
+            sink.region_failed(monitored, RegionErrorKind::Encode, &format!("{:#}", e));
+            return false;
+        }
+    };
+    let encode_time = encode_start.elapsed();
+    let frame = |outcome| RegionFrame {
+        region: monitored,
+        mapping,
+        timestamp,
+        png_data: &png_data,
+        encode_time,
+        outcome,
+    };
+
+    // Check if the image has changed since the last OCR pass
+    let current_hash = change::content_hash(&png_data);
+    if tracker.last_hash == Some(current_hash) {
+        debug!("No visual change detected in region '{}'", monitored.id);
+        sink.frame(&frame(FrameOutcome::Unchanged));
+
+        // A held back reading may become stable by persisting
+        match tracker.restabilize(timestamp) {
+            Some(result) if result.text != tracker.last_text => {
+                publish_reading(monitored, tracker, result, sink);
+            }
+            _ => sink.reading_confirmed(monitored, timestamp),
+        }
+        return false;
+    }
+
+    debug!("Region '{}' changed, performing OCR", monitored.id);
+    tracker.last_hash = Some(current_hash);
+
+    // Extract text from the region
+    let mut result = match ocr_service.extract_text_from_image(image) {
+        Ok(result) => result,
+        Err(e) => {
+            error!(
+                "Error extracting text from region '{}': {}",
+                monitored.id, e
+            );
+            hooks::report_error("ocr", format!("Region '{}': {:#}", monitored.id, e));
+            sink.frame(&frame(FrameOutcome::Failed(format!("{:#}", e))));
+            // Retry OCR on the next cycle even if the image stays the same
+            tracker.last_hash = None;
+            return false;
+        }
+    };
+    result.timestamp = timestamp;
+    let raw = monitored.normalization.as_ref().map(|normalization| {
+        let raw = result.clone();
+        normalize::normalize_result(normalization, &mut result);
+        raw
+    });
+    sink.frame(&frame(FrameOutcome::Read {
+        raw: raw.as_ref().unwrap_or(&result),
+        result: &result,
+    }));
+
+    match tracker.stabilize(result) {
+        Some(result) => publish_reading(monitored, tracker, result, sink),
+        None => {
+            debug!("Holding back unstable reading of region '{}'", monitored.id);
+            sink.reading_confirmed(monitored, timestamp);
+        }
+    }
+
+    true
+}
+
+/// Pass a stable reading to the sink, diffed against the previous one
+fn publish_reading(
+    monitored: &MonitoredRegion,
+    tracker: &mut RegionTracker,
+    result: OcrResult,
+    sink: &impl MonitorSink,
+) {
+    let value = monitored.numeric.as_ref().and_then(|numeric| {
+        let value = numeric::parse_number(&result.text, numeric.decimal_separator);
+        if value.is_none() {
+            debug!("No number found in region '{}'", monitored.id);
+            hooks::report_error(
+                "numeric_parse",
+                format!("Region '{}': no number in {:?}", monitored.id, result.text),
+            );
+        }
+        value
+    });
+
+    let change = (result.text != tracker.last_text).then(|| {
+        info!(
+            "New text detected in region '{}' ({} characters): {}",
+            monitored.id,
+            result.text.len(),
+            result.text
+        );
+        let change = TextChange {
+            region_id: monitored.id.clone(),
+            timestamp: result.timestamp,
+            text: result.text.clone(),
+            diff: diff::diff_text(&tracker.last_text, &result.text),
+        };
+        debug!(
+            "Region '{}' changed: {} added, {} removed, {} edited line(s)",
+            monitored.id, change.diff.added, change.diff.removed, change.diff.changed
+        );
+        tracker.last_text = result.text.clone();
+        change
+    });
+
+    sink.reading(Reading {
+        region: monitored,
+        result,
+        change,
+        value,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::FakeEngine;
+    use crate::geometry;
+    use crate::models::{Region, TextNormalization};
+    use image::Rgba;
+    use std::cell::RefCell;
+
+    /// Sink remembering what happened, one line per event
+    #[derive(Default)]
+    struct EventLog(RefCell<Vec<String>>);
+
+    impl MonitorSink for EventLog {
+        fn frame(&self, frame: &RegionFrame<'_>) {
+            let event = match &frame.outcome {
+                FrameOutcome::Unchanged => "unchanged".to_string(),
+                FrameOutcome::Read { raw, result } => {
+                    format!("read {:?} {:?}", raw.text, result.text)
+                }
+                FrameOutcome::Failed(error) => format!("failed {}", error),
+            };
+            self.0.borrow_mut().push(event);
+        }
+
+        fn reading(&self, reading: Reading<'_>) {
+            self.0.borrow_mut().push(format!(
+                "reading {:?} changed={}",
+                reading.result.text,
+                reading.change.is_some()
+            ));
+        }
+
+        fn reading_confirmed(&self, _region: &MonitoredRegion, _timestamp: DateTime<Utc>) {
+            self.0.borrow_mut().push("confirmed".to_string());
+        }
+    }
+
+    #[test]
+    fn test_reads_changed_images_only() {
+        let mut ocr_service = OcrService::with_engine(Box::new(FakeEngine::with_text("4   2")));
+        let mut region = MonitoredRegion::new("a", Region::new(0, 0, 4, 4));
+        region.normalization = Some(TextNormalization::default());
+        let mapping = geometry::map_region(&region.region, 10, 10).unwrap();
+        let mut tracker = RegionTracker::new(&region, Instant::now());
+        let sink = EventLog::default();
+
+        let black = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 255]));
+        let white = RgbaImage::from_pixel(4, 4, Rgba([255, 255, 255, 255]));
+        let mut read = |image: &RgbaImage| {
+            read_region(
+                &mut ocr_service,
+                &region,
+                image,
+                &mapping,
+                Utc::now(),
+                &mut tracker,
+                &sink,
+            )
+        };
+
+        assert!(read(&black));
+        assert!(!read(&black));
+        // The text stays the same although the image changed
+        assert!(read(&white));
+
+        assert_eq!(
+            sink.0.into_inner(),
+            [
+                r#"read "4   2" "4 2""#,
+                r#"reading "4 2" changed=true"#,
+                "unchanged",
+                "confirmed",
+                r#"read "4   2" "4 2""#,
+                r#"reading "4 2" changed=false"#,
+            ]
+        );
+    }
+}
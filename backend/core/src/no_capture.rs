@@ -0,0 +1,80 @@
+//! Stand-in for the capture backend in builds without the `capture` feature
+//!
+//! It provides the API of `capture_session` and `frame_convert`, but no
+//! display is ever captured. `Frame` has no values, so code handling
+//! frames compiles without being reachable.
+
+use anyhow::Result;
+use image::RgbaImage;
+use std::sync::Arc;
+use std::time::Instant;
+
+use super::screen_capture::{CaptureError, DisplayInfo};
+use crate::models::Region;
+
+/// Frame rate used for new capture sessions unless configured otherwise
+pub const DEFAULT_STREAM_FPS: u32 = 5;
+
+/// A display frame, which this build can't produce
+pub enum Frame {}
+
+/// A frame delivered by a capture session
+#[derive(Clone)]
+pub struct CapturedFrame {
+    /// The full display frame
+    pub frame: Arc<Frame>,
+
+    /// Monotonic frame counter within the session
+    pub sequence: u64,
+
+    /// When the frame was received from the capturer
+    pub captured_at: Instant,
+}
+
+/// Screen capture is never supported without the `capture` feature
+pub fn is_supported() -> bool {
+    false
+}
+
+/// Without capture there is no permission to have
+pub fn has_permission() -> bool {
+    false
+}
+
+/// Without capture there is no permission to request
+pub fn request_permission() -> bool {
+    false
+}
+
+/// Capture streams don't exist in this build
+pub fn set_stream_fps(_fps: u32) {}
+
+/// No displays can be listed without capture
+pub fn displays() -> Vec<DisplayInfo> {
+    Vec::new()
+}
+
+/// No display can be described without capture
+pub fn display_info(_display_id: u32) -> Option<DisplayInfo> {
+    None
+}
+
+/// No session ever delivers a frame
+pub fn cached_frame_size(_display_id: u32) -> Option<(i32, i32)> {
+    None
+}
+
+/// Fail, as no display can be captured
+pub fn current_frame(_display_id: u32) -> Result<CapturedFrame> {
+    Err(CaptureError::Unsupported.into())
+}
+
+/// Get the width and height of a frame in pixels
+pub fn frame_size(frame: &Frame) -> (i32, i32) {
+    match *frame {}
+}
+
+/// Convert a frame, or the part of it covered by `region`, to an RGBA image
+pub fn frame_to_image(frame: &Frame, _region: Option<&Region>) -> Result<RgbaImage> {
+    match *frame {}
+}
@@ -0,0 +1,304 @@
+use unicode_normalization::UnicodeNormalization;
+
+use crate::models::{FieldType, OcrResult, TextNormalization, UnicodeForm};
+
+/// Shortest word (in characters) that is matched against the dictionary
+const MIN_DICTIONARY_WORD_CHARS: usize = 4;
+
+/// Typographic ligatures and the letters they stand for
+const LIGATURES: &[(char, &str)] = &[
+    ('\u{fb00}', "ff"),
+    ('\u{fb01}', "fi"),
+    ('\u{fb02}', "fl"),
+    ('\u{fb03}', "ffi"),
+    ('\u{fb04}', "ffl"),
+    ('\u{fb05}', "st"),
+    ('\u{fb06}', "st"),
+    ('\u{0132}', "IJ"),
+    ('\u{0133}', "ij"),
+];
+
+/// Punctuation that may appear within a number
+const NUMBER_PUNCTUATION: &[char] = &['.', ',', ':', '+', '-', '%', '\'', '/', '\u{2212}'];
+
+/// Punctuation that may surround or join words
+const WORD_PUNCTUATION: &[char] = &['.', ',', ':', ';', '!', '?', '\'', '"', '-', '(', ')'];
+
+/// Clean up the text and words of an OCR result
+pub fn normalize_result(settings: &TextNormalization, result: &mut OcrResult) {
+    result.text = normalize_text(settings, &result.text);
+    for word in &mut result.words {
+        word.text = normalize_text(settings, &word.text);
+    }
+}
+
+/// Clean up recognized text
+///
+/// Steps run in order: line endings, Unicode normalization, ligatures,
+/// confused characters, dictionary words and finally whitespace.
+pub fn normalize_text(settings: &TextNormalization, text: &str) -> String {
+    let mut text = if settings.collapse_whitespace {
+        text.replace("\r\n", "\n").replace('\r', "\n")
+    } else {
+        text.to_string()
+    };
+
+    text = match settings.unicode {
+        Some(UnicodeForm::Nfc) => text.nfc().collect(),
+        Some(UnicodeForm::Nfkc) => text.nfkc().collect(),
+        None => text,
+    };
+
+    if settings.expand_ligatures {
+        text = expand_ligatures(&text);
+    }
+
+    if settings.field != FieldType::Text || !settings.dictionary.is_empty() {
+        text = map_words(&text, |word| {
+            let word = fix_confusions(word, settings.field);
+            snap_to_dictionary(&word, &settings.dictionary, settings.max_edits).unwrap_or(word)
+        });
+    }
+
+    if settings.collapse_whitespace {
+        collapse_whitespace(&text)
+    } else {
+        text.trim().to_string()
+    }
+}
+
+/// Replace ligature characters with their letters
+fn expand_ligatures(text: &str) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    for c in text.chars() {
+        match LIGATURES.iter().find(|(ligature, _)| *ligature == c) {
+            Some((_, letters)) => expanded.push_str(letters),
+            None => expanded.push(c),
+        }
+    }
+    expanded
+}
+
+/// Collapse runs of whitespace within lines and drop blank lines
+fn collapse_whitespace(text: &str) -> String {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Apply a function to every whitespace separated word, keeping the whitespace
+fn map_words(text: &str, mut map: impl FnMut(&str) -> String) -> String {
+    let mut mapped = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let word_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        if word_end > 0 {
+            mapped.push_str(&map(&rest[..word_end]));
+        }
+        rest = &rest[word_end..];
+
+        let space_end = rest
+            .find(|c: char| !c.is_whitespace())
+            .unwrap_or(rest.len());
+        mapped.push_str(&rest[..space_end]);
+        rest = &rest[space_end..];
+    }
+
+    mapped
+}
+
+/// Digit a letter is commonly misread for
+fn digit_for(c: char) -> Option<char> {
+    match c {
+        'O' | 'o' | 'D' | 'Q' => Some('0'),
+        'I' | 'l' | 'i' | '|' => Some('1'),
+        'Z' => Some('2'),
+        'S' => Some('5'),
+        'G' => Some('6'),
+        'B' => Some('8'),
+        _ => None,
+    }
+}
+
+/// Letter a digit is commonly misread for
+fn letter_for(c: char) -> Option<char> {
+    match c {
+        '0' => Some('O'),
+        '1' => Some('l'),
+        '2' => Some('Z'),
+        '5' => Some('S'),
+        '6' => Some('G'),
+        '8' => Some('B'),
+        _ => None,
+    }
+}
+
+/// Swap confused characters in a word that otherwise fits the field type
+///
+/// A numeric word must contain a digit and consist of digits, number
+/// punctuation and letters that look like digits; an alphabetic word must
+/// contain a letter and consist of letters, punctuation and digits that
+/// look like letters. Other words are left alone, so "1O kg" becomes "10 kg".
+fn fix_confusions(word: &str, field: FieldType) -> String {
+    let fits = |real: fn(&char) -> bool, swap: fn(char) -> Option<char>, punctuation: &[char]| {
+        word.chars().any(|c| real(&c))
+            && word
+                .chars()
+                .all(|c| real(&c) || swap(c).is_some() || punctuation.contains(&c))
+    };
+
+    match field {
+        FieldType::Numeric if fits(char::is_ascii_digit, digit_for, NUMBER_PUNCTUATION) => {
+            word.chars().map(|c| digit_for(c).unwrap_or(c)).collect()
+        }
+        FieldType::Alphabetic
+            if fits(|c: &char| c.is_alphabetic(), letter_for, WORD_PUNCTUATION) =>
+        {
+            word.chars().map(|c| letter_for(c).unwrap_or(c)).collect()
+        }
+        _ => word.to_string(),
+    }
+}
+
+/// Find the dictionary word closest to a near miss, keeping surrounding punctuation
+///
+/// Returns `None` if the word is already known, too short, or no single
+/// dictionary word is within `max_edits`.
+fn snap_to_dictionary(word: &str, dictionary: &[String], max_edits: u32) -> Option<String> {
+    let is_punctuation = |c: char| WORD_PUNCTUATION.contains(&c);
+    let start = word.len() - word.trim_start_matches(is_punctuation).len();
+    let core = word[start..].trim_end_matches(is_punctuation);
+    if core.chars().count() < MIN_DICTIONARY_WORD_CHARS {
+        return None;
+    }
+
+    let lowercase = core.to_lowercase();
+    let mut best: Option<(usize, &str)> = None;
+    let mut tied = false;
+
+    for known in dictionary {
+        let Some(distance) = edit_distance(&lowercase, &known.to_lowercase(), max_edits as usize)
+        else {
+            continue;
+        };
+        if distance == 0 {
+            return None;
+        }
+        match best {
+            Some((best_distance, _)) if distance > best_distance => {}
+            Some((best_distance, _)) if distance == best_distance => tied = true,
+            _ => {
+                best = Some((distance, known.as_str()));
+                tied = false;
+            }
+        }
+    }
+
+    let (_, known) = best.filter(|_| !tied)?;
+    Some(format!(
+        "{}{}{}",
+        &word[..start],
+        known,
+        &word[start + core.len()..]
+    ))
+}
+
+/// Levenshtein distance between two words, `None` if it exceeds `max`
+fn edit_distance(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        if current.iter().min().is_some_and(|&min| min > max) {
+            return None;
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    Some(previous[b.len()]).filter(|&distance| distance <= max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_whitespace_and_ligatures() {
+        let settings = TextNormalization::default();
+        assert_eq!(
+            normalize_text(&settings, "  \u{fb01}le   name\r\n\r\n\tsecond  line "),
+            "file name\nsecond line"
+        );
+    }
+
+    #[test]
+    fn test_unicode_forms() {
+        let decomposed = "Cafe\u{301}";
+        let settings = TextNormalization::default();
+        assert_eq!(normalize_text(&settings, decomposed), "Caf\u{e9}");
+
+        let settings = TextNormalization {
+            unicode: Some(UnicodeForm::Nfkc),
+            ..TextNormalization::default()
+        };
+        assert_eq!(normalize_text(&settings, "\u{ff11}\u{ff12}"), "12");
+    }
+
+    #[test]
+    fn test_numeric_confusions() {
+        let settings = TextNormalization {
+            field: FieldType::Numeric,
+            ..TextNormalization::default()
+        };
+        assert_eq!(normalize_text(&settings, "1O.5S kg"), "10.55 kg");
+        assert_eq!(normalize_text(&settings, "Total: l2O"), "Total: 120");
+        assert_eq!(normalize_text(&settings, "SOLD"), "SOLD");
+    }
+
+    #[test]
+    fn test_alphabetic_confusions() {
+        let settings = TextNormalization {
+            field: FieldType::Alphabetic,
+            ..TextNormalization::default()
+        };
+        assert_eq!(
+            normalize_text(&settings, "HE1LO W0RLD 42"),
+            "HElLO WORLD 42"
+        );
+    }
+
+    #[test]
+    fn test_dictionary_snapping() {
+        let settings = TextNormalization {
+            dictionary: vec!["Running".to_string(), "Stopped".to_string()],
+            ..TextNormalization::default()
+        };
+        assert_eq!(
+            normalize_text(&settings, "Status: Runnlng."),
+            "Status: Running."
+        );
+        assert_eq!(normalize_text(&settings, "Stoped"), "Stopped");
+        assert_eq!(normalize_text(&settings, "Paused"), "Paused");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting", 3), Some(3));
+        assert_eq!(edit_distance("kitten", "sitting", 2), None);
+        assert_eq!(edit_distance("same", "same", 0), Some(0));
+    }
+}
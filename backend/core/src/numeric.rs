@@ -0,0 +1,88 @@
+use chrono::{DateTime, Utc};
+
+/// Last number read from a numeric region
+#[derive(Debug, Clone, Copy)]
+pub struct NumericReading {
+    /// The parsed value
+    pub value: f64,
+
+    /// When a capture last confirmed the value
+    pub confirmed_at: DateTime<Utc>,
+}
+
+/// Parse the first number in OCR text
+///
+/// Digit grouping with the other separator, apostrophes or non-breaking
+/// spaces is ignored, so `1,234.5` parses as 1234.5 with a '.' decimal
+/// separator and `1.234,5` does with ','.
+pub fn parse_number(text: &str, decimal_separator: char) -> Option<f64> {
+    let group_separators: &[char] = match decimal_separator {
+        ',' => &['.', '\'', '\u{a0}', '\u{202f}'],
+        _ => &[',', '\'', '\u{a0}', '\u{202f}'],
+    };
+
+    let chars: Vec<char> = text.chars().collect();
+    let digit_at = |index: usize| chars.get(index).is_some_and(char::is_ascii_digit);
+
+    // Find where the number starts, including a sign or a leading separator
+    let start = (0..chars.len())
+        .find(|&i| digit_at(i) || (chars[i] == decimal_separator && digit_at(i + 1)))?;
+    let negative = start > 0 && matches!(chars[start - 1], '-' | '\u{2212}');
+
+    let mut normalized = String::from(if negative { "-" } else { "" });
+    let mut seen_decimal = false;
+    let mut index = start;
+
+    while index < chars.len() {
+        let c = chars[index];
+        if c.is_ascii_digit() {
+            normalized.push(c);
+        } else if c == decimal_separator && !seen_decimal && digit_at(index + 1) {
+            seen_decimal = true;
+            normalized.push('.');
+        } else if group_separators.contains(&c) && !seen_decimal && digit_at(index + 1) {
+            // Grouping separator between digits, skip it
+        } else {
+            break;
+        }
+        index += 1;
+    }
+
+    normalized.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plain_numbers() {
+        assert_eq!(parse_number("42", '.'), Some(42.0));
+        assert_eq!(parse_number("Temp: -3.5 C", '.'), Some(-3.5));
+        assert_eq!(parse_number("\u{2212}7", '.'), Some(-7.0));
+        assert_eq!(parse_number(".25", '.'), Some(0.25));
+    }
+
+    #[test]
+    fn test_parse_grouped_numbers() {
+        assert_eq!(
+            parse_number("Total: 1,234,567.89 USD", '.'),
+            Some(1_234_567.89)
+        );
+        assert_eq!(parse_number("1.234,5", ','), Some(1234.5));
+        assert_eq!(parse_number("1'000", '.'), Some(1000.0));
+    }
+
+    #[test]
+    fn test_parse_stops_at_first_number() {
+        assert_eq!(parse_number("12 of 40", '.'), Some(12.0));
+        assert_eq!(parse_number("2.5.1", '.'), Some(2.5));
+    }
+
+    #[test]
+    fn test_parse_without_number() {
+        assert_eq!(parse_number("", '.'), None);
+        assert_eq!(parse_number("n/a", '.'), None);
+        assert_eq!(parse_number("- .", '.'), None);
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::{Context, Result};
+use image::RgbaImage;
+use log::debug;
+use std::time::Instant;
+
+use super::engine::{self, EngineKind, OcrEngine};
+use super::hooks;
+use super::screen_capture::ScreenCaptureService;
+use crate::models::{OcrResult, OcrSettings, Region};
+
+/// Service for performing OCR on screen regions
+pub struct OcrService {
+    /// Engine that recognizes the text
+    engine: Box<dyn OcrEngine>,
+}
+
+impl OcrService {
+    /// Create a new OCR service with the default settings
+    pub fn new() -> Result<Self> {
+        Self::with_settings(&OcrSettings::default())
+    }
+
+    /// Create a new OCR service for the given language and segmentation mode
+    ///
+    /// The service uses the configured engine, see `engine::configure`.
+    pub fn with_settings(settings: &OcrSettings) -> Result<Self> {
+        Ok(Self::with_engine(engine::create_engine(settings)?))
+    }
+
+    /// Create a new OCR service around an engine
+    pub fn with_engine(engine: Box<dyn OcrEngine>) -> Self {
+        Self { engine }
+    }
+
+    /// Engine that recognizes the text
+    pub fn engine_kind(&self) -> EngineKind {
+        self.engine.kind()
+    }
+
+    /// Extract text, words and confidence from an encoded image
+    ///
+    /// Any format the engine can read is accepted (PNG, JPEG, ...).
+    pub fn extract_text_from_bytes(&mut self, data: &[u8]) -> Result<OcrResult> {
+        let started = Instant::now();
+        let result = self.engine.recognize_bytes(data);
+        hooks::record_ocr_time(started.elapsed());
+
+        result
+    }
+
+    pub fn extract_text_from_region(&mut self, region: &Region) -> Result<OcrResult> {
+        debug!("Extracting text from region: {:?}", region);
+
+        // Capture the region
+        let image = ScreenCaptureService::capture_region(region)
+            .context("Failed to capture region for OCR")?;
+
+        self.extract_text_from_image(&image)
+    }
+
+    /// Extract text from an already captured image
+    pub fn extract_text_from_image(&mut self, image: &RgbaImage) -> Result<OcrResult> {
+        let started = Instant::now();
+        let result = self.engine.recognize_image(image);
+        hooks::record_ocr_time(started.elapsed());
+
+        let result = result?;
+        debug!(
+            "Extracted text: {} characters, {} words",
+            result.text.len(),
+            result.words.len()
+        );
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::FakeEngine;
+    use image::Rgba;
+
+    #[test]
+    fn test_service_uses_its_engine() {
+        let mut service = OcrService::with_engine(Box::new(FakeEngine::with_text("42")));
+        let image = RgbaImage::from_pixel(8, 8, Rgba([0, 0, 0, 255]));
+
+        assert_eq!(service.engine_kind(), EngineKind::Fake);
+        assert_eq!(service.extract_text_from_image(&image).unwrap().text, "42");
+    }
+}
@@ -0,0 +1,417 @@
+//! Out-of-process OCR worker
+//!
+//! When enabled, every Tesseract `OcrService` runs OCR in a child process
+//! started from the current executable with `--ocr-worker`, so every
+//! executable enabling workers has to call `run_worker` when it sees that
+//! flag. The processes talk over the child's stdin and stdout in
+//! length-prefixed frames, so a crash inside Leptonica or Tesseract only
+//! takes down the worker, which is restarted on the next request. A worker
+//! that doesn't answer in time is killed and replaced right away.
+
+use anyhow::{anyhow, Context, Result};
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+
+use super::engine::{EngineKind, OcrEngine, TesseractEngine};
+use crate::hooks;
+use crate::models::{OcrResult, OcrSettings};
+
+/// Command line flag that starts the binary as an OCR worker
+pub const OCR_WORKER_FLAG: &str = "--ocr-worker";
+
+/// Largest frame either side accepts
+const MAX_FRAME_BYTES: usize = 256 * 1024 * 1024;
+
+/// How long a worker may take to answer a request before it is killed
+const CALL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Whether new Tesseract OCR services run in a worker process
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Run OCR for services created from now on in worker processes
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+/// Check whether OCR runs in worker processes
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// Request sent to a worker
+///
+/// `Recognize` is followed by a frame with the encoded image.
+#[derive(Debug, Serialize, Deserialize)]
+enum WorkerRequest {
+    /// Initialize the engine with the given settings
+    Configure(OcrSettings),
+
+    /// Run OCR on the image in the next frame
+    Recognize,
+}
+
+/// Reply of a worker, the result of a `Recognize` request if successful
+type WorkerResponse = std::result::Result<Option<OcrResult>, String>;
+
+/// Write a frame: the payload length as little endian u32, then the payload
+fn write_frame(writer: &mut impl Write, payload: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(payload.len())
+        .ok()
+        .filter(|len| *len as usize <= MAX_FRAME_BYTES)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Frame too large"))?;
+
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+/// Read a frame, returning `None` if the stream ended cleanly before it
+fn read_frame(reader: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_FRAME_BYTES {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Frame of {} bytes exceeds the limit", len),
+        ));
+    }
+
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload)?;
+    Ok(Some(payload))
+}
+
+/// Write a JSON message as a frame
+fn write_message<T: Serialize>(writer: &mut impl Write, message: &T) -> io::Result<()> {
+    write_frame(writer, &serde_json::to_vec(message)?)
+}
+
+/// Read a JSON message from a frame
+fn read_message<T: for<'de> Deserialize<'de>>(reader: &mut impl Read) -> io::Result<Option<T>> {
+    read_frame(reader)?
+        .map(|payload| serde_json::from_slice(&payload).map_err(io::Error::from))
+        .transpose()
+}
+
+/// Serve OCR requests on stdin and stdout until stdin is closed
+///
+/// This is the main loop of the worker process. Logs go to stderr.
+pub fn run_worker() -> io::Result<()> {
+    info!("OCR worker started");
+
+    let mut reader = BufReader::new(io::stdin().lock());
+    let mut writer = BufWriter::new(io::stdout().lock());
+    let mut engine: Option<TesseractEngine> = None;
+
+    while let Some(request) = read_message::<WorkerRequest>(&mut reader)? {
+        let response: WorkerResponse = match request {
+            WorkerRequest::Configure(settings) => {
+                debug!("Configuring OCR worker: {:?}", settings);
+                engine = None;
+                TesseractEngine::new(&settings)
+                    .map(|configured| {
+                        engine = Some(configured);
+                        None
+                    })
+                    .map_err(|e| format!("{:#}", e))
+            }
+            WorkerRequest::Recognize => {
+                let image = read_frame(&mut reader)?.ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::UnexpectedEof, "Missing image frame")
+                })?;
+
+                match engine.as_mut() {
+                    Some(engine) => engine
+                        .recognize_bytes(&image)
+                        .map(Some)
+                        .map_err(|e| format!("{:#}", e)),
+                    None => Err("OCR worker is not configured".to_string()),
+                }
+            }
+        };
+
+        write_message(&mut writer, &response)?;
+    }
+
+    info!("OCR worker input closed, exiting");
+    Ok(())
+}
+
+/// Run `exchange` and kill the child if it doesn't finish within `timeout`
+///
+/// Killing the child closes its pipes, which ends a read or write that
+/// waits on them. Returns `None` if the deadline passed.
+fn with_deadline<T>(
+    child: &Mutex<Child>,
+    timeout: Duration,
+    exchange: impl FnOnce() -> T,
+) -> Option<T> {
+    let (done, finished) = mpsc::channel::<()>();
+    let timed_out = AtomicBool::new(false);
+
+    let result = std::thread::scope(|scope| {
+        let timed_out = &timed_out;
+        scope.spawn(move || {
+            if let Err(mpsc::RecvTimeoutError::Timeout) = finished.recv_timeout(timeout) {
+                timed_out.store(true, Ordering::SeqCst);
+                let mut child = child.lock().unwrap_or_else(|e| e.into_inner());
+                if let Err(e) = child.kill() {
+                    debug!("Failed to kill OCR worker process: {}", e);
+                }
+            }
+        });
+
+        let result = exchange();
+        drop(done);
+        result
+    });
+
+    (!timed_out.load(Ordering::SeqCst)).then_some(result)
+}
+
+/// A running worker process
+struct WorkerProcess {
+    child: Mutex<Child>,
+    stdin: BufWriter<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl WorkerProcess {
+    /// Start the current binary as a worker
+    fn spawn() -> Result<Self> {
+        let executable =
+            std::env::current_exe().context("Failed to locate the current executable")?;
+
+        let mut child = Command::new(executable)
+            .arg(OCR_WORKER_FLAG)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .context("Failed to start OCR worker process")?;
+
+        debug!("Started OCR worker process {}", child.id());
+
+        let stdin = child.stdin.take().context("OCR worker has no stdin")?;
+        let stdout = child.stdout.take().context("OCR worker has no stdout")?;
+
+        Ok(Self {
+            child: Mutex::new(child),
+            stdin: BufWriter::new(stdin),
+            stdout: BufReader::new(stdout),
+        })
+    }
+
+    /// Send a request, with an optional image frame, and read the reply
+    ///
+    /// The process is killed if it doesn't answer within `CALL_TIMEOUT`.
+    fn call(&mut self, request: &WorkerRequest, image: Option<&[u8]>) -> Result<WorkerResponse> {
+        let Self {
+            child,
+            stdin,
+            stdout,
+        } = self;
+
+        with_deadline(child, CALL_TIMEOUT, || -> Result<WorkerResponse> {
+            write_message(stdin, request)?;
+            if let Some(image) = image {
+                write_frame(stdin, image)?;
+            }
+
+            read_message(stdout)?.ok_or_else(|| anyhow!("OCR worker closed its output"))
+        })
+        .unwrap_or_else(|| {
+            Err(anyhow!(
+                "OCR worker did not answer within {}s and was killed",
+                CALL_TIMEOUT.as_secs()
+            ))
+        })
+    }
+
+    /// Describe how the process ended, if it did
+    fn exit_status(&mut self) -> String {
+        let child = self.child.get_mut().unwrap_or_else(|e| e.into_inner());
+        match child.try_wait() {
+            Ok(Some(status)) => status.to_string(),
+            Ok(None) => "still running".to_string(),
+            Err(e) => format!("unknown ({})", e),
+        }
+    }
+}
+
+impl Drop for WorkerProcess {
+    fn drop(&mut self) {
+        let child = self.child.get_mut().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = child.kill() {
+            debug!("Failed to stop OCR worker process: {}", e);
+        }
+        if let Err(e) = child.wait() {
+            warn!("Failed to reap OCR worker process: {}", e);
+        }
+    }
+}
+
+/// Client side of an OCR worker process
+///
+/// A worker that died is replaced by a new one on the next request, one
+/// that hung is replaced as soon as it was killed.
+pub struct OcrWorker {
+    /// Language and segmentation mode of the worker's engine
+    settings: OcrSettings,
+
+    /// Running process, `None` after it died
+    process: Option<WorkerProcess>,
+}
+
+impl OcrWorker {
+    /// Start a worker and initialize its engine with the given settings
+    pub fn spawn(settings: &OcrSettings) -> Result<Self> {
+        let mut worker = Self {
+            settings: settings.clone(),
+            process: None,
+        };
+        worker.process()?;
+        Ok(worker)
+    }
+
+    /// Run OCR on an encoded image in the worker
+    pub fn recognize(&mut self, image: &[u8]) -> Result<OcrResult> {
+        let process = self.process()?;
+
+        match process.call(&WorkerRequest::Recognize, Some(image)) {
+            Ok(Ok(Some(result))) => Ok(result),
+            Ok(Ok(None)) => Err(anyhow!("OCR worker returned no result")),
+            Ok(Err(message)) => Err(anyhow!(message)),
+            Err(e) => {
+                let error = self.lost(e);
+                if let Err(e) = self.process() {
+                    warn!("Failed to restart OCR worker: {:#}", e);
+                }
+                Err(error)
+            }
+        }
+    }
+
+    /// Get the running worker, starting and configuring one if needed
+    fn process(&mut self) -> Result<&mut WorkerProcess> {
+        if self.process.is_none() {
+            let mut process = WorkerProcess::spawn()?;
+            match process.call(&WorkerRequest::Configure(self.settings.clone()), None) {
+                Ok(Ok(_)) => {}
+                Ok(Err(message)) => return Err(anyhow!(message)),
+                Err(e) => {
+                    let status = process.exit_status();
+                    return Err(e.context(format!("OCR worker failed to start ({})", status)));
+                }
+            }
+            self.process = Some(process);
+        }
+
+        self.process
+            .as_mut()
+            .ok_or_else(|| anyhow!("OCR worker is not running"))
+    }
+
+    /// Drop a worker that stopped answering and describe what happened
+    fn lost(&mut self, error: anyhow::Error) -> anyhow::Error {
+        let status = self
+            .process
+            .as_mut()
+            .map_or_else(|| "not running".to_string(), WorkerProcess::exit_status);
+        self.process = None;
+
+        let error = error.context(format!("OCR worker process failed ({})", status));
+        error!("{:#}", error);
+        hooks::report_error("ocr_worker", format!("{:#}", error));
+        error
+    }
+}
+
+impl OcrEngine for OcrWorker {
+    fn kind(&self) -> EngineKind {
+        EngineKind::Tesseract
+    }
+
+    fn recognize_bytes(&mut self, data: &[u8]) -> Result<OcrResult> {
+        self.recognize(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_frames_round_trip() {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, b"first").unwrap();
+        write_frame(&mut buffer, b"").unwrap();
+
+        let mut reader = Cursor::new(buffer);
+        assert_eq!(read_frame(&mut reader).unwrap().unwrap(), b"first");
+        assert_eq!(read_frame(&mut reader).unwrap().unwrap(), b"");
+        assert!(read_frame(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_truncated_frame_is_an_error() {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, b"truncated").unwrap();
+        buffer.truncate(8);
+
+        assert!(read_frame(&mut Cursor::new(buffer)).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_deadline_kills_hung_child() {
+        let mut child = Command::new("sleep")
+            .arg("30")
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdout = child.stdout.take().unwrap();
+        let child = Mutex::new(child);
+
+        let started = std::time::Instant::now();
+        let result = with_deadline(&child, Duration::from_millis(100), || {
+            read_frame(&mut stdout)
+        });
+        assert!(result.is_none());
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert!(child.lock().unwrap().wait().is_ok());
+
+        let result = with_deadline(&child, Duration::from_secs(10), || 42);
+        assert_eq!(result, Some(42));
+    }
+
+    #[test]
+    fn test_messages_round_trip() {
+        let mut buffer = Vec::new();
+        write_message(
+            &mut buffer,
+            &WorkerRequest::Configure(OcrSettings::default()),
+        )
+        .unwrap();
+        let response: WorkerResponse = Ok(Some(OcrResult::new("text".to_string())));
+        write_message(&mut buffer, &response).unwrap();
+
+        let mut reader = Cursor::new(buffer);
+        let request: WorkerRequest = read_message(&mut reader).unwrap().unwrap();
+        assert!(matches!(request, WorkerRequest::Configure(settings) if settings.psm == 6));
+        let response: WorkerResponse = read_message(&mut reader).unwrap().unwrap();
+        assert_eq!(response.unwrap().unwrap().text, "text");
+    }
+}
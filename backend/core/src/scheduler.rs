@@ -0,0 +1,170 @@
+use std::time::{Duration, Instant};
+
+use crate::models::{CaptureRate, CaptureSchedule};
+
+/// Factor the adaptive interval grows by after each unchanged capture
+const BACKOFF_FACTOR: f64 = 1.5;
+
+/// Weight of the newest sample in the measured interval average
+const MEASURED_SMOOTHING: f64 = 0.2;
+
+/// Decides when a region is due for its next capture
+///
+/// With a fixed schedule the region is captured every `interval_ms`. In
+/// adaptive mode the interval grows while the region stays static, up to
+/// `max_interval_ms`, and snaps back to `interval_ms` as soon as it changes.
+#[derive(Debug, Clone)]
+pub struct IntervalScheduler {
+    /// Schedule the scheduler was created from
+    schedule: CaptureSchedule,
+
+    /// Interval applied after the last capture
+    current: Duration,
+
+    /// Earliest time of the next capture
+    next_due: Instant,
+
+    /// Time of the last recorded capture
+    last_run: Option<Instant>,
+
+    /// Smoothed interval between recorded captures
+    measured: Option<Duration>,
+}
+
+impl IntervalScheduler {
+    /// Create a scheduler that is due immediately
+    pub fn new(schedule: &CaptureSchedule, now: Instant) -> Self {
+        Self {
+            schedule: schedule.clone(),
+            current: Self::base_interval(schedule),
+            next_due: now,
+            last_run: None,
+            measured: None,
+        }
+    }
+
+    /// Shortest interval allowed by the schedule
+    fn base_interval(schedule: &CaptureSchedule) -> Duration {
+        Duration::from_millis(schedule.interval_ms.max(CaptureSchedule::MIN_INTERVAL_MS))
+    }
+
+    /// Schedule the scheduler was created from
+    pub fn schedule(&self) -> &CaptureSchedule {
+        &self.schedule
+    }
+
+    /// Whether the region should be captured now
+    pub fn is_due(&self, now: Instant) -> bool {
+        now >= self.next_due
+    }
+
+    /// Earliest time of the next capture
+    pub fn next_due(&self) -> Instant {
+        self.next_due
+    }
+
+    /// Record a capture and plan the next one
+    pub fn record(&mut self, changed: bool, now: Instant) {
+        let base = Self::base_interval(&self.schedule);
+
+        self.current = if changed || !self.schedule.adaptive {
+            base
+        } else {
+            let max = Duration::from_millis(self.schedule.max_interval_ms).max(base);
+            self.current.mul_f64(BACKOFF_FACTOR).min(max)
+        };
+
+        if let Some(last_run) = self.last_run {
+            let sample = now.saturating_duration_since(last_run);
+            self.measured = Some(match self.measured {
+                Some(measured) => {
+                    measured.mul_f64(1.0 - MEASURED_SMOOTHING) + sample.mul_f64(MEASURED_SMOOTHING)
+                }
+                None => sample,
+            });
+        }
+
+        self.last_run = Some(now);
+        self.next_due = now + self.current;
+    }
+
+    /// Hold the next capture back until `until` at the earliest
+    pub fn defer(&mut self, until: Instant) {
+        self.next_due = self.next_due.max(until);
+    }
+
+    /// Rate the region is currently captured at
+    pub fn rate(&self) -> CaptureRate {
+        CaptureRate {
+            interval_ms: self.current.as_millis() as u64,
+            measured_interval_ms: self.measured.map(|measured| measured.as_millis() as u64),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(interval_ms: u64, adaptive: bool, max_interval_ms: u64) -> CaptureSchedule {
+        CaptureSchedule {
+            interval_ms,
+            adaptive,
+            max_interval_ms,
+        }
+    }
+
+    #[test]
+    fn test_fixed_interval() {
+        let start = Instant::now();
+        let mut scheduler = IntervalScheduler::new(&schedule(1000, false, 10_000), start);
+        assert!(scheduler.is_due(start));
+
+        scheduler.record(false, start);
+        assert!(!scheduler.is_due(start + Duration::from_millis(999)));
+        assert!(scheduler.is_due(start + Duration::from_millis(1000)));
+        assert_eq!(scheduler.rate().interval_ms, 1000);
+    }
+
+    #[test]
+    fn test_adaptive_backoff_and_reset() {
+        let start = Instant::now();
+        let mut scheduler = IntervalScheduler::new(&schedule(200, true, 1000), start);
+
+        scheduler.record(false, start);
+        assert_eq!(scheduler.rate().interval_ms, 300);
+        scheduler.record(false, start);
+        assert_eq!(scheduler.rate().interval_ms, 450);
+        for _ in 0..10 {
+            scheduler.record(false, start);
+        }
+        assert_eq!(scheduler.rate().interval_ms, 1000);
+
+        scheduler.record(true, start);
+        assert_eq!(scheduler.rate().interval_ms, 200);
+    }
+
+    #[test]
+    fn test_defer_only_delays() {
+        let start = Instant::now();
+        let mut scheduler = IntervalScheduler::new(&schedule(1000, false, 10_000), start);
+        scheduler.record(false, start);
+
+        scheduler.defer(start + Duration::from_millis(500));
+        assert_eq!(scheduler.next_due(), start + Duration::from_millis(1000));
+        scheduler.defer(start + Duration::from_millis(4000));
+        assert!(!scheduler.is_due(start + Duration::from_millis(3999)));
+        assert!(scheduler.is_due(start + Duration::from_millis(4000)));
+    }
+
+    #[test]
+    fn test_measured_interval() {
+        let start = Instant::now();
+        let mut scheduler = IntervalScheduler::new(&schedule(100, false, 100), start);
+        assert_eq!(scheduler.rate().measured_interval_ms, None);
+
+        scheduler.record(true, start);
+        scheduler.record(true, start + Duration::from_millis(100));
+        assert_eq!(scheduler.rate().measured_interval_ms, Some(100));
+    }
+}
@@ -0,0 +1,207 @@
+use anyhow::{Context, Result};
+use image::RgbaImage;
+use log::debug;
+use std::io::Cursor;
+use thiserror::Error;
+
+use super::capture_session::{self, CapturedFrame, Frame};
+use super::frame_convert;
+use super::geometry::{self, RegionMapping};
+use crate::models::Region;
+
+/// Capture failures callers may want to tell apart
+#[derive(Debug, Error)]
+pub enum CaptureError {
+    /// The platform has no screen capture support
+    #[error("Screen capture is not supported on this platform")]
+    Unsupported,
+
+    /// The user did not grant screen capture permission
+    #[error("Screen capture permission denied")]
+    PermissionDenied,
+
+    /// The display did not deliver a frame in time
+    #[cfg_attr(not(feature = "capture"), allow(dead_code))]
+    #[error("Timed out waiting for a frame from display {0}")]
+    Timeout(u32),
+}
+
+/// Description of a display
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DisplayInfo {
+    /// Screen identifier
+    pub id: u32,
+
+    /// Display name
+    pub name: String,
+
+    /// Screen width in pixels
+    pub width: u32,
+
+    /// Screen height in pixels
+    pub height: u32,
+
+    /// Physical pixels per logical pixel
+    pub scale_factor: f64,
+
+    /// Whether this is the primary display, which id 0 also selects
+    #[serde(default)]
+    pub primary: bool,
+}
+
+/// Service for screen capture using scap
+///
+/// Without the `capture` feature nothing can be captured and every
+/// capture fails with `CaptureError::Unsupported`.
+pub struct ScreenCaptureService;
+
+impl ScreenCaptureService {
+    /// Check if screen capture is supported on the current platform
+    pub fn is_supported() -> bool {
+        capture_session::is_supported()
+    }
+
+    /// Check if we have permission to capture the screen
+    pub fn has_permission() -> bool {
+        capture_session::has_permission()
+    }
+
+    /// Request permission to capture the screen
+    pub fn request_permission() -> bool {
+        capture_session::request_permission()
+    }
+
+    /// Make sure the screen can be captured, requesting permission if needed
+    pub fn ensure_access() -> Result<(), CaptureError> {
+        if !Self::is_supported() {
+            return Err(CaptureError::Unsupported);
+        }
+
+        if !Self::has_permission() && !Self::request_permission() {
+            return Err(CaptureError::PermissionDenied);
+        }
+
+        Ok(())
+    }
+
+    /// Get information about available displays
+    ///
+    /// Platforms that don't list their displays report the primary display,
+    /// measured from a captured frame. Configured scale factors override
+    /// the ones reported by the platform.
+    pub fn get_display_info() -> Result<Vec<DisplayInfo>> {
+        Self::ensure_access()?;
+
+        let mut displays = capture_session::displays();
+        if displays.is_empty() {
+            let captured = Self::capture_display(0)?;
+            let (width, height) = frame_convert::frame_size(&captured.frame);
+            displays.push(DisplayInfo {
+                id: 0,
+                name: "Primary Display".to_string(),
+                width: width as u32,
+                height: height as u32,
+                scale_factor: 1.0,
+                primary: true,
+            });
+        }
+
+        for display in &mut displays {
+            display.scale_factor = geometry::scale_factor(display.id);
+        }
+
+        Ok(displays)
+    }
+
+    /// Capture a screenshot of a display, optionally cropped to a rectangle
+    ///
+    /// Returns the image with the frame area it shows in physical pixels.
+    pub fn capture_display_image(
+        display_id: u32,
+        crop: Option<&Region>,
+    ) -> Result<(RgbaImage, RegionMapping)> {
+        debug!("Capturing screenshot of display {}", display_id);
+
+        let captured = capture_session::current_frame(display_id)
+            .with_context(|| format!("Failed to capture display {}", display_id))?;
+
+        let (width, height) = frame_convert::frame_size(&captured.frame);
+        let full_frame = Region {
+            display_id,
+            ..Region::new(0, 0, width, height)
+        };
+        let mapping = geometry::map_region(crop.unwrap_or(&full_frame), width, height)?;
+        let image = frame_convert::frame_to_image(&captured.frame, Some(&mapping.physical))?;
+
+        Ok((image, mapping))
+    }
+
+    /// Capture the current full frame of a display
+    ///
+    /// Every region on the display can be cropped from the returned frame,
+    /// so readings taken from it are temporally consistent.
+    pub fn capture_display(display_id: u32) -> Result<CapturedFrame> {
+        debug!("Capturing full frame of display {}", display_id);
+
+        capture_session::current_frame(display_id)
+            .with_context(|| format!("Failed to capture display {}", display_id))
+    }
+
+    /// Get the size of a display in physical pixels, if it is known
+    ///
+    /// The size comes from the display's running capture session or from
+    /// the platform's display list. No capture is started to find it out.
+    pub fn display_size(display_id: u32) -> Option<(i32, i32)> {
+        capture_session::cached_frame_size(display_id).or_else(|| {
+            capture_session::display_info(display_id)
+                .map(|display| (display.width as i32, display.height as i32))
+        })
+    }
+
+    /// Capture a specific region of the screen
+    ///
+    /// The region is cropped from the current frame of the display's
+    /// capture session, so no capturer is created per call.
+    pub fn capture_region(region: &Region) -> Result<RgbaImage> {
+        debug!(
+            "Capturing region: display={}, x={}, y={}, width={}, height={}",
+            region.display_id, region.x, region.y, region.width, region.height
+        );
+
+        let captured = capture_session::current_frame(region.display_id)
+            .context("Failed to capture screen region")?;
+        debug!(
+            "Cropping region from frame #{} ({}ms old)",
+            captured.sequence,
+            captured.captured_at.elapsed().as_millis()
+        );
+
+        Self::crop_frame(&captured.frame, region).map(|(image, _)| image)
+    }
+
+    /// Crop a region out of a full display frame
+    ///
+    /// Logical and fractional coordinates are translated to frame pixels
+    /// first. The image is returned with the mapping that was applied.
+    pub fn crop_frame(frame: &Frame, region: &Region) -> Result<(RgbaImage, RegionMapping)> {
+        let (width, height) = frame_convert::frame_size(frame);
+        let mapping = geometry::map_region(region, width, height)?;
+        let image = frame_convert::frame_to_image(frame, Some(&mapping.physical))?;
+
+        Ok((image, mapping))
+    }
+
+    /// Encode an image as PNG data
+    pub fn to_png(image: &RgbaImage) -> Result<Vec<u8>> {
+        // Create a buffer for PNG data
+        let mut png_data = Vec::new();
+
+        // Save the image to a memory buffer
+        image
+            .write_to(&mut Cursor::new(&mut png_data), image::ImageFormat::Png)
+            .context("Failed to encode image as PNG")?;
+
+        Ok(png_data)
+    }
+}
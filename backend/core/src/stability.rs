@@ -0,0 +1,153 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::models::StabilityFilter;
+
+/// Decides which reading of a region is stable enough to publish
+///
+/// Readings are fed in capture order, including repeats of the last
+/// reading for captures whose image didn't change.
+#[derive(Debug, Clone)]
+pub struct Stabilizer {
+    /// Rule a reading must pass
+    filter: StabilityFilter,
+
+    /// Reading that passed the rule last
+    stable: Option<String>,
+
+    /// Latest reading and how often and since when it was seen in a row
+    candidate: Option<(String, u32, Instant)>,
+
+    /// Latest readings, oldest first (majority mode only)
+    window: VecDeque<String>,
+}
+
+impl Stabilizer {
+    pub fn new(filter: &StabilityFilter) -> Self {
+        Self {
+            filter: filter.clone(),
+            stable: None,
+            candidate: None,
+            window: VecDeque::new(),
+        }
+    }
+
+    /// Rule the stabilizer applies
+    pub fn filter(&self) -> &StabilityFilter {
+        &self.filter
+    }
+
+    /// Feed a reading and get the stable text, if any reading is stable yet
+    pub fn observe(&mut self, text: &str, now: Instant) -> Option<&str> {
+        match self.filter {
+            StabilityFilter::Consecutive { count } => {
+                let (_, seen, _) = self.track_candidate(text, now);
+                if *seen >= count {
+                    self.stable = Some(text.to_string());
+                }
+            }
+            StabilityFilter::Duration { min_ms } => {
+                let (_, _, since) = self.track_candidate(text, now);
+                if now.saturating_duration_since(*since) >= Duration::from_millis(min_ms) {
+                    self.stable = Some(text.to_string());
+                }
+            }
+            StabilityFilter::Majority { window } => {
+                self.window.push_back(text.to_string());
+                while self.window.len() > window as usize {
+                    self.window.pop_front();
+                }
+
+                let votes = self
+                    .window
+                    .iter()
+                    .filter(|reading| *reading == text)
+                    .count();
+                if votes * 2 > window as usize {
+                    self.stable = Some(text.to_string());
+                }
+            }
+        }
+
+        self.stable.as_deref()
+    }
+
+    /// Count a reading as the latest in a row of equal readings
+    fn track_candidate(&mut self, text: &str, now: Instant) -> &mut (String, u32, Instant) {
+        if self
+            .candidate
+            .as_ref()
+            .is_some_and(|(candidate, _, _)| candidate != text)
+        {
+            self.candidate = None;
+        }
+
+        let candidate = self
+            .candidate
+            .get_or_insert_with(|| (text.to_string(), 0, now));
+        candidate.1 += 1;
+        candidate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observe_all(stabilizer: &mut Stabilizer, readings: &[&str]) -> Vec<Option<String>> {
+        let now = Instant::now();
+        readings
+            .iter()
+            .map(|reading| stabilizer.observe(reading, now).map(str::to_string))
+            .collect()
+    }
+
+    #[test]
+    fn test_consecutive_readings() {
+        let mut stabilizer = Stabilizer::new(&StabilityFilter::Consecutive { count: 2 });
+        let stable = observe_all(&mut stabilizer, &["10", "1O", "10", "10", "1O"]);
+
+        assert_eq!(
+            stable,
+            vec![None, None, None, Some("10".into()), Some("10".into())]
+        );
+    }
+
+    #[test]
+    fn test_duration() {
+        let mut stabilizer = Stabilizer::new(&StabilityFilter::Duration { min_ms: 500 });
+        let start = Instant::now();
+
+        assert_eq!(stabilizer.observe("a", start), None);
+        assert_eq!(
+            stabilizer.observe("a", start + Duration::from_millis(499)),
+            None
+        );
+        assert_eq!(
+            stabilizer.observe("a", start + Duration::from_millis(500)),
+            Some("a")
+        );
+        assert_eq!(
+            stabilizer.observe("b", start + Duration::from_millis(600)),
+            Some("a")
+        );
+    }
+
+    #[test]
+    fn test_majority_vote() {
+        let mut stabilizer = Stabilizer::new(&StabilityFilter::Majority { window: 3 });
+        let stable = observe_all(&mut stabilizer, &["10", "10", "1O", "10", "1O", "1O"]);
+
+        assert_eq!(
+            stable,
+            vec![
+                None,
+                Some("10".into()),
+                Some("10".into()),
+                Some("10".into()),
+                Some("1O".into()),
+                Some("1O".into())
+            ]
+        );
+    }
+}
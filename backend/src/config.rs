@@ -1,5 +1,17 @@
-use log::info;
+use log::{info, warn};
+use std::collections::HashMap;
 use std::env;
+use std::path::PathBuf;
+
+use crate::services::capture_session::DEFAULT_STREAM_FPS;
+use crate::services::engine::{EngineConfig, EngineKind};
+use crate::services::geometry;
+use crate::services::jobs::{
+    DEFAULT_JOB_QUEUE_BYTES, DEFAULT_JOB_QUEUE_LIMIT, DEFAULT_JOB_WORKERS,
+};
+use crate::services::recorder::{
+    DEFAULT_MAX_RECORDING_BYTES, DEFAULT_MAX_RECORDING_SESSIONS, DEFAULT_RECORDINGS_DIR,
+};
 
 /// Application configuration
 pub struct Config {
@@ -11,6 +23,36 @@ pub struct Config {
 
     /// Path to static files directory
     pub static_dir: String,
+
+    /// Frame rate of the persistent screen capture streams
+    pub capture_fps: u32,
+
+    /// Number of threads processing OCR jobs
+    pub job_workers: usize,
+
+    /// Maximum number of OCR jobs waiting for a worker
+    pub job_queue_limit: usize,
+
+    /// Maximum bytes of uploaded images held by unfinished OCR jobs
+    pub job_queue_bytes: usize,
+
+    /// Run Tesseract in child processes instead of the server process
+    pub ocr_worker: bool,
+
+    /// OCR engine and the directory of its models
+    pub ocr_engine: EngineConfig,
+
+    /// Scale factor overrides of displays, the platform's factor is used otherwise
+    pub display_scale_factors: HashMap<u32, f64>,
+
+    /// Directory recorded capture sessions are stored in
+    pub recordings_dir: PathBuf,
+
+    /// Size a session archive may grow to before its recording stops
+    pub recording_max_bytes: u64,
+
+    /// Number of recorded sessions kept on disk
+    pub recording_max_sessions: usize,
 }
 
 impl Config {
@@ -25,15 +67,105 @@ impl Config {
 
         let static_dir = env::var("STATIC_DIR").unwrap_or_else(|_| "./static".to_string());
 
+        let capture_fps = env::var("CAPTURE_FPS")
+            .ok()
+            .and_then(|fps| fps.parse().ok())
+            .filter(|fps| *fps > 0)
+            .unwrap_or(DEFAULT_STREAM_FPS);
+
+        let job_workers = env::var("JOB_WORKERS")
+            .ok()
+            .and_then(|workers| workers.parse().ok())
+            .filter(|workers| *workers > 0)
+            .unwrap_or(DEFAULT_JOB_WORKERS);
+
+        let job_queue_limit = env::var("JOB_QUEUE_LIMIT")
+            .ok()
+            .and_then(|limit| limit.parse().ok())
+            .filter(|limit| *limit > 0)
+            .unwrap_or(DEFAULT_JOB_QUEUE_LIMIT);
+
+        let job_queue_bytes = env::var("JOB_QUEUE_MAX_BYTES")
+            .ok()
+            .and_then(|bytes| bytes.parse().ok())
+            .filter(|bytes| *bytes > 0)
+            .unwrap_or(DEFAULT_JOB_QUEUE_BYTES);
+
+        let ocr_worker = env::var("OCR_WORKER")
+            .map(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+
+        let ocr_engine = EngineConfig {
+            kind: env::var("OCR_ENGINE")
+                .ok()
+                .and_then(|name| {
+                    name.parse::<EngineKind>()
+                        .inspect_err(|e| warn!("Ignoring OCR_ENGINE: {}", e))
+                        .ok()
+                })
+                .unwrap_or_default(),
+            model_dir: env::var("OCR_MODEL_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| EngineConfig::default().model_dir),
+        };
+
+        let display_scale_factors = env::var("DISPLAY_SCALE_FACTORS")
+            .ok()
+            .and_then(|spec| {
+                geometry::parse_scale_factors(&spec)
+                    .inspect_err(|e| warn!("Ignoring DISPLAY_SCALE_FACTORS: {}", e))
+                    .ok()
+            })
+            .unwrap_or_default();
+
+        let recordings_dir = env::var("RECORDINGS_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from(DEFAULT_RECORDINGS_DIR));
+
+        let recording_max_bytes = env::var("RECORDING_MAX_BYTES")
+            .ok()
+            .and_then(|bytes| bytes.parse().ok())
+            .filter(|bytes| *bytes > 0)
+            .unwrap_or(DEFAULT_MAX_RECORDING_BYTES);
+
+        let recording_max_sessions = env::var("RECORDING_MAX_SESSIONS")
+            .ok()
+            .and_then(|sessions| sessions.parse().ok())
+            .filter(|sessions| *sessions > 0)
+            .unwrap_or(DEFAULT_MAX_RECORDING_SESSIONS);
+
         let config = Self {
             server_addr,
             server_port,
             static_dir,
+            capture_fps,
+            job_workers,
+            job_queue_limit,
+            job_queue_bytes,
+            ocr_worker,
+            ocr_engine,
+            display_scale_factors,
+            recordings_dir,
+            recording_max_bytes,
+            recording_max_sessions,
         };
 
         info!(
-            "Loaded configuration: server={}:{}, static_dir={}",
-            config.server_addr, config.server_port, config.static_dir
+            "Loaded configuration: server={}:{}, static_dir={}, capture_fps={}, job_workers={}, job_queue_limit={}, job_queue_bytes={}, ocr_worker={}, ocr_engine={}, ocr_model_dir={}, display_scale_factors={:?}, recordings_dir={}, recording_max_bytes={}, recording_max_sessions={}",
+            config.server_addr,
+            config.server_port,
+            config.static_dir,
+            config.capture_fps,
+            config.job_workers,
+            config.job_queue_limit,
+            config.job_queue_bytes,
+            config.ocr_worker,
+            config.ocr_engine.kind.as_str(),
+            config.ocr_engine.model_dir.display(),
+            config.display_scale_factors,
+            config.recordings_dir.display(),
+            config.recording_max_bytes,
+            config.recording_max_sessions
         );
 
         config
@@ -0,0 +1,253 @@
+//! Error type shared by all API handlers
+
+use actix_web::{error::BlockingError, http::StatusCode, HttpRequest, HttpResponse, ResponseError};
+use log::{error, warn};
+use thiserror::Error;
+
+use crate::models::{ErrorDetail, ErrorResponse};
+use crate::services::jobs::JobError;
+use crate::services::recorder::RecordingError;
+use crate::services::screen_capture::CaptureError;
+
+/// Error returned by API handlers
+///
+/// Every variant has a stable machine readable code and is reported as
+/// `{"error": {"code": "...", "message": "..."}}` with a matching status.
+#[derive(Debug, Error)]
+pub enum ApiError {
+    /// The request is malformed or has invalid parameters
+    #[error("{0}")]
+    InvalidRequest(String),
+
+    /// A region has invalid dimensions or settings
+    #[error("{0}")]
+    InvalidRegion(String),
+
+    /// The operation needs a monitored region but none is set
+    #[error("No region selected")]
+    NoRegion,
+
+    /// The requested resource does not exist
+    #[error("{0}")]
+    NotFound(String),
+
+    /// The request conflicts with the current state
+    #[error("{0}")]
+    Conflict(String),
+
+    /// The upload exceeds the size limit
+    #[error("{0}")]
+    PayloadTooLarge(String),
+
+    /// The upload is not in a supported format
+    #[error("{0}")]
+    UnsupportedMediaType(String),
+
+    /// Too many jobs are waiting
+    #[error("{0}")]
+    QueueFull(String),
+
+    /// Screen capture is not available on this platform
+    #[error("Screen capture is not supported on this platform")]
+    CaptureUnsupported,
+
+    /// Screen capture permission was not granted
+    #[error("Screen capture permission denied")]
+    PermissionDenied,
+
+    /// The display did not deliver a frame in time
+    #[error("{0}")]
+    CaptureTimeout(String),
+
+    /// Capturing the screen failed
+    #[error("{0}")]
+    CaptureFailed(String),
+
+    /// Running OCR failed
+    #[error("{0}")]
+    OcrFailed(String),
+
+    /// An unexpected server side failure
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl ApiError {
+    /// Stable machine readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::InvalidRequest(_) => "invalid_request",
+            ApiError::InvalidRegion(_) => "invalid_region",
+            ApiError::NoRegion => "no_region",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::PayloadTooLarge(_) => "payload_too_large",
+            ApiError::UnsupportedMediaType(_) => "unsupported_media_type",
+            ApiError::QueueFull(_) => "queue_full",
+            ApiError::CaptureUnsupported => "capture_unsupported",
+            ApiError::PermissionDenied => "permission_denied",
+            ApiError::CaptureTimeout(_) => "capture_timeout",
+            ApiError::CaptureFailed(_) => "capture_failed",
+            ApiError::OcrFailed(_) => "ocr_failed",
+            ApiError::Internal(_) => "internal",
+        }
+    }
+
+    /// Error for a failed screen capture
+    pub fn capture(error: anyhow::Error) -> Self {
+        Self::from_capture_error(&error)
+            .unwrap_or_else(|| ApiError::CaptureFailed(format!("{:#}", error)))
+    }
+
+    /// Error for a failed OCR run, which may include a capture
+    pub fn ocr(error: anyhow::Error) -> Self {
+        Self::from_capture_error(&error)
+            .unwrap_or_else(|| ApiError::OcrFailed(format!("{:#}", error)))
+    }
+
+    /// Error for a poisoned lock of shared state
+    pub fn lock(name: &str) -> Self {
+        ApiError::Internal(format!("Failed to lock {}", name))
+    }
+
+    /// Map a typed capture error anywhere in the chain
+    fn from_capture_error(error: &anyhow::Error) -> Option<Self> {
+        error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<CaptureError>())
+            .map(|cause| match cause {
+                CaptureError::Unsupported => ApiError::CaptureUnsupported,
+                CaptureError::PermissionDenied => ApiError::PermissionDenied,
+                CaptureError::Timeout(_) => ApiError::CaptureTimeout(cause.to_string()),
+            })
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::InvalidRequest(_) | ApiError::InvalidRegion(_) | ApiError::NoRegion => {
+                StatusCode::BAD_REQUEST
+            }
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ApiError::QueueFull(_) => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::CaptureUnsupported => StatusCode::NOT_IMPLEMENTED,
+            ApiError::PermissionDenied => StatusCode::FORBIDDEN,
+            ApiError::CaptureTimeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            ApiError::CaptureFailed(_) | ApiError::OcrFailed(_) | ApiError::Internal(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        if status.is_server_error() {
+            error!("Request failed ({}): {}", self.code(), self);
+        } else {
+            warn!("Request rejected ({}): {}", self.code(), self);
+        }
+
+        HttpResponse::build(status).json(ErrorResponse {
+            error: ErrorDetail {
+                code: self.code().to_string(),
+                message: self.to_string(),
+            },
+        })
+    }
+}
+
+impl From<JobError> for ApiError {
+    fn from(error: JobError) -> Self {
+        match error {
+            JobError::QueueFull(_) | JobError::QueueBytesFull(_) => {
+                ApiError::QueueFull(error.to_string())
+            }
+            JobError::NotFound(_) => ApiError::NotFound(error.to_string()),
+            JobError::AlreadyFinished(_) => ApiError::Conflict(error.to_string()),
+        }
+    }
+}
+
+impl From<RecordingError> for ApiError {
+    fn from(error: RecordingError) -> Self {
+        match error {
+            RecordingError::NotFound(_) => ApiError::NotFound(error.to_string()),
+            RecordingError::AlreadyRecording(_)
+            | RecordingError::NotRecording
+            | RecordingError::Active(_) => ApiError::Conflict(error.to_string()),
+            RecordingError::Storage(_) => ApiError::Internal(error.to_string()),
+        }
+    }
+}
+
+impl From<BlockingError> for ApiError {
+    fn from(error: BlockingError) -> Self {
+        ApiError::Internal(format!("Background task failed: {}", error))
+    }
+}
+
+/// Report JSON body extractor failures in the error envelope
+pub fn json_error_handler(
+    error: actix_web::error::JsonPayloadError,
+    _req: &HttpRequest,
+) -> actix_web::Error {
+    match error {
+        actix_web::error::JsonPayloadError::OverflowKnownLength { .. }
+        | actix_web::error::JsonPayloadError::Overflow { .. } => {
+            ApiError::PayloadTooLarge(error.to_string()).into()
+        }
+        actix_web::error::JsonPayloadError::ContentType => {
+            ApiError::UnsupportedMediaType(error.to_string()).into()
+        }
+        _ => ApiError::InvalidRequest(format!("Invalid JSON body: {}", error)).into(),
+    }
+}
+
+/// Report query string extractor failures in the error envelope
+pub fn query_error_handler(
+    error: actix_web::error::QueryPayloadError,
+    _req: &HttpRequest,
+) -> actix_web::Error {
+    ApiError::InvalidRequest(format!("Invalid query parameters: {}", error)).into()
+}
+
+/// Report path extractor failures in the error envelope
+pub fn path_error_handler(
+    error: actix_web::error::PathError,
+    _req: &HttpRequest,
+) -> actix_web::Error {
+    ApiError::InvalidRequest(format!("Invalid path: {}", error)).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_capture_errors_are_typed() {
+        let error = Err::<(), _>(CaptureError::Timeout(2))
+            .context("Failed to capture region")
+            .unwrap_err();
+        let api_error = ApiError::ocr(error);
+
+        assert_eq!(api_error.code(), "capture_timeout");
+        assert_eq!(api_error.status_code(), StatusCode::GATEWAY_TIMEOUT);
+
+        let api_error = ApiError::ocr(anyhow::anyhow!("engine crashed"));
+        assert_eq!(api_error.code(), "ocr_failed");
+    }
+
+    #[test]
+    fn test_job_errors_map_to_status() {
+        let api_error = ApiError::from(JobError::QueueFull(3));
+        assert_eq!(api_error.status_code(), StatusCode::TOO_MANY_REQUESTS);
+
+        let api_error = ApiError::from(JobError::AlreadyFinished("job-1".into()));
+        assert_eq!(api_error.code(), "conflict");
+    }
+}
@@ -0,0 +1,142 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use log::debug;
+
+use crate::error::ApiError;
+use crate::models::{
+    Capabilities, DiagnosticsQuery, DiagnosticsResponse, ErrorResponse, HealthChecks,
+    HealthResponse, PlatformInfo,
+};
+use crate::services::{diagnostics, engine, ScreenCaptureService};
+use crate::state::AppState;
+
+/// Report liveness and readiness
+///
+/// Answers 200 when every component needed to read the screen works,
+/// and 503 with the failing checks otherwise.
+#[utoipa::path(
+    tag = "diagnostics",
+    responses(
+        (status = 200, description = "Ready to read the screen", body = HealthResponse),
+        (status = 503, description = "Not ready, see the failing checks", body = HealthResponse),
+    )
+)]
+#[get("/api/health")]
+pub async fn get_health(state: web::Data<AppState>) -> impl Responder {
+    let health = health(&state);
+
+    if health.ready {
+        HttpResponse::Ok().json(health)
+    } else {
+        debug!("Health check failed: {:?}", health.checks);
+        HttpResponse::ServiceUnavailable().json(health)
+    }
+}
+
+/// Report the environment, component errors and an OCR self-test
+///
+/// Pass `self_test=false` to skip the self-test.
+#[utoipa::path(
+    tag = "diagnostics",
+    params(DiagnosticsQuery),
+    responses(
+        (status = 200, description = "Environment, component errors and self-test", body = DiagnosticsResponse),
+        (status = 500, description = "Collecting diagnostics failed", body = ErrorResponse),
+    )
+)]
+#[get("/api/diagnostics")]
+pub async fn get_diagnostics(
+    query: web::Query<DiagnosticsQuery>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    debug!("Request for diagnostics: {:?}", query);
+
+    let run_self_test = query.self_test;
+
+    // Initializing Tesseract and the self-test block, keep them off the async workers
+    let (tesseract, self_test) = web::block(move || {
+        let self_test = run_self_test.then(diagnostics::run_self_test);
+        (diagnostics::tesseract_details(), self_test)
+    })
+    .await?;
+
+    Ok(HttpResponse::Ok().json(DiagnosticsResponse {
+        platform: PlatformInfo {
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        },
+        health: health(&state),
+        ocr_engine: engine::config().kind.as_str().to_string(),
+        tesseract_version: tesseract.version,
+        tesseract_languages: tesseract.languages,
+        tesseract_datapath: tesseract.datapath,
+        tesseract_source: tesseract.source,
+        monitor: diagnostics::monitor_liveness(),
+        supervisor: state.supervisor.status(),
+        last_errors: diagnostics::last_errors(),
+        jobs_queued: state.jobs.queued(),
+        jobs_running: state.jobs.running(),
+        self_test,
+    }))
+}
+
+/// Run the readiness checks
+fn health(state: &AppState) -> HealthResponse {
+    let capture_supported = ScreenCaptureService::is_supported();
+    let monitor = diagnostics::monitor_liveness();
+    let checks = HealthChecks {
+        capture_supported,
+        capture_permission: capture_supported && ScreenCaptureService::has_permission(),
+        ocr_ready: state.ocr_ready.lock().map(|ready| *ready).unwrap_or(false),
+        monitor_alive: monitor.alive,
+    };
+
+    // Builds without capture only read uploaded images, the monitor never runs
+    let ready = !cfg!(feature = "capture")
+        || (checks.capture_supported
+            && checks.capture_permission
+            && checks.ocr_ready
+            && checks.monitor_alive);
+
+    HealthResponse {
+        live: diagnostics::is_live(&state.supervisor.status(), &monitor),
+        ready,
+        checks,
+    }
+}
+
+/// Report what this build of the server can do
+///
+/// Lists the cargo features it was built with, whether screens can be
+/// captured on this host and which OCR engines are available.
+#[utoipa::path(
+    tag = "diagnostics",
+    responses(
+        (status = 200, description = "Features, engines and capture support of this build", body = Capabilities),
+    )
+)]
+#[get("/api/capabilities")]
+pub async fn get_capabilities() -> impl Responder {
+    let features = [
+        ("capture", cfg!(feature = "capture")),
+        ("tesseract", cfg!(feature = "tesseract")),
+        ("ocrs", cfg!(feature = "ocrs")),
+    ];
+
+    HttpResponse::Ok().json(Capabilities {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        features: features
+            .into_iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(feature, _)| feature.to_string())
+            .collect(),
+        capture: cfg!(feature = "capture"),
+        capture_supported: ScreenCaptureService::is_supported(),
+        engines: engine::available_engines()
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+        ocr_engine: engine::config().kind.as_str().to_string(),
+        ocr_worker: engine::uses_worker(),
+    })
+}
@@ -0,0 +1,72 @@
+use actix_web::{get, web, HttpResponse};
+use futures_util::stream;
+use log::{debug, warn};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::models::{EventsLagged, EventsQuery, TextChange};
+use crate::state::AppState;
+
+/// Stream text changes of the monitored regions as server-sent events
+///
+/// Every event carries the new text and its diff to the previous text.
+/// Pass `region` to only receive changes of one region. A client too slow
+/// to keep up gets a `lagged` event and should resync from the history.
+#[utoipa::path(
+    tag = "monitoring",
+    params(EventsQuery),
+    responses(
+        (status = 200, description = "Stream of `text` events, each carrying a text change as JSON, \
+            and `lagged` events carrying the number of skipped changes",
+            body = TextChange, content_type = "text/event-stream"),
+    )
+)]
+#[get("/api/events")]
+pub async fn text_events(
+    query: web::Query<EventsQuery>,
+    state: web::Data<AppState>,
+) -> HttpResponse {
+    debug!("Client subscribed to text events: {:?}", query);
+
+    let region = query.into_inner().region;
+    let receiver = state.text_events.subscribe();
+
+    let events = stream::unfold(receiver, move |mut receiver| {
+        let region = region.clone();
+        async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(change) if region.as_ref().is_none_or(|id| id == &change.region_id) => {
+                        return Some((sse_event(&change), receiver));
+                    }
+                    Ok(_) => continue,
+                    // The client can catch up through the history endpoint
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Text event stream skipped {} changes", skipped);
+                        return Some((lagged_event(skipped), receiver));
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(events)
+}
+
+/// Encode a text change as a server-sent event
+fn sse_event(change: &TextChange) -> Result<web::Bytes, actix_web::Error> {
+    let data = serde_json::to_string(change)?;
+    Ok(web::Bytes::from(format!("event: text\ndata: {}\n\n", data)))
+}
+
+/// Encode the notice that changes were skipped as a server-sent event
+fn lagged_event(skipped: u64) -> Result<web::Bytes, actix_web::Error> {
+    let data = serde_json::to_string(&EventsLagged { skipped })?;
+    Ok(web::Bytes::from(format!(
+        "event: lagged\ndata: {}\n\n",
+        data
+    )))
+}
@@ -0,0 +1,292 @@
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
+use futures_util::{stream, Stream};
+use log::{debug, info, warn};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use super::upload::read_images;
+use crate::error::ApiError;
+use crate::models::{ErrorResponse, Job, OcrSettings, RegionJobRequest};
+use crate::openapi::{Image, ImageUpload};
+use crate::services::jobs::{JobError, JobInput, JobQueue};
+use crate::state::AppState;
+
+/// Largest number of images accepted in one batch job
+const MAX_BATCH_IMAGES: usize = 100;
+
+/// Submit a job capturing and reading a live screen region
+#[utoipa::path(
+    tag = "jobs",
+    request_body = RegionJobRequest,
+    responses(
+        (status = 202, description = "The job was queued", body = Job),
+        (status = 400, description = "Invalid region or settings", body = ErrorResponse),
+        (status = 429, description = "Too many jobs are waiting", body = ErrorResponse),
+    )
+)]
+#[post("/api/jobs/region")]
+pub async fn submit_region_job(
+    req: web::Json<RegionJobRequest>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let RegionJobRequest { region, settings } = req.into_inner();
+
+    if !region.is_valid() {
+        debug!("Rejecting job for invalid region: {:?}", region);
+        return Err(ApiError::InvalidRegion(
+            "Invalid region: dimensions must be positive".to_string(),
+        ));
+    }
+    settings.validate().map_err(ApiError::InvalidRequest)?;
+
+    submit(&state.jobs, JobInput::Region(region), settings)
+}
+
+/// Submit a job reading one or more uploaded images
+///
+/// Accepts the same uploads as `/api/ocr`, with any number of `image`
+/// form fields up to the batch limit.
+#[utoipa::path(
+    tag = "jobs",
+    params(OcrSettings),
+    request_body(
+        description = "The image as raw body, or any number of images as multipart form",
+        content(
+            (ImageUpload = "multipart/form-data"),
+            (Image = "image/png"),
+            (Image = "image/jpeg"),
+            (Image = "image/tiff"),
+        )
+    ),
+    responses(
+        (status = 202, description = "The job was queued", body = Job),
+        (status = 400, description = "No images, too many images or invalid settings", body = ErrorResponse),
+        (status = 413, description = "The upload is too large", body = ErrorResponse),
+        (status = 415, description = "An image is not PNG, JPEG or TIFF", body = ErrorResponse),
+        (status = 429, description = "Too many jobs or too much image data are waiting", body = ErrorResponse),
+    )
+)]
+#[post("/api/jobs/images")]
+pub async fn submit_image_job(
+    req: HttpRequest,
+    payload: web::Payload,
+    query: web::Query<OcrSettings>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let upload = read_images(&req, payload, query.into_inner(), MAX_BATCH_IMAGES).await?;
+
+    submit(
+        &state.jobs,
+        JobInput::Images(upload.images),
+        upload.settings,
+    )
+}
+
+/// List all known jobs without their results
+#[utoipa::path(
+    tag = "jobs",
+    responses(
+        (status = 200, description = "All known jobs without their items", body = Vec<Job>),
+    )
+)]
+#[get("/api/jobs")]
+pub async fn list_jobs(state: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(state.jobs.list())
+}
+
+/// Get the status and results of a job
+#[utoipa::path(
+    tag = "jobs",
+    params(("id" = String, Path, description = "Identifier of the job")),
+    responses(
+        (status = 200, description = "The job with its results", body = Job),
+        (status = 404, description = "No job has this id", body = ErrorResponse),
+    )
+)]
+#[get("/api/jobs/{id}")]
+pub async fn get_job(
+    path: web::Path<String>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let job = state.jobs.get(&id).ok_or(JobError::NotFound(id))?;
+
+    Ok(HttpResponse::Ok().json(job))
+}
+
+/// Cancel a queued or running job
+#[utoipa::path(
+    tag = "jobs",
+    params(("id" = String, Path, description = "Identifier of the job")),
+    responses(
+        (status = 200, description = "The cancelled job", body = Job),
+        (status = 404, description = "No job has this id", body = ErrorResponse),
+        (status = 409, description = "The job already finished", body = ErrorResponse),
+    )
+)]
+#[delete("/api/jobs/{id}")]
+pub async fn cancel_job(
+    path: web::Path<String>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let job = state.jobs.cancel(&path.into_inner())?;
+
+    Ok(HttpResponse::Ok().json(job))
+}
+
+/// Stream updates of a job as server-sent events
+///
+/// Every update carries the full job. The stream ends once the job
+/// reaches a final state.
+#[utoipa::path(
+    tag = "jobs",
+    params(("id" = String, Path, description = "Identifier of the job")),
+    responses(
+        (status = 200, description = "Stream of `job` events, each carrying the full job as JSON",
+            body = Job, content_type = "text/event-stream"),
+        (status = 404, description = "No job has this id", body = ErrorResponse),
+    )
+)]
+#[get("/api/jobs/{id}/events")]
+pub async fn job_events(
+    path: web::Path<String>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+
+    // Subscribe before reading the current state so no update is missed
+    let receiver = state.jobs.subscribe();
+    let current = state
+        .jobs
+        .get(&id)
+        .ok_or_else(|| JobError::NotFound(id.clone()))?;
+
+    let events = job_event_stream(state.jobs.clone(), id, receiver, current);
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(events))
+}
+
+/// Stream the given state of a job and every later update until it finishes
+fn job_event_stream(
+    queue: JobQueue,
+    id: String,
+    receiver: broadcast::Receiver<Job>,
+    current: Job,
+) -> impl Stream<Item = Result<web::Bytes, actix_web::Error>> {
+    stream::unfold(
+        (receiver, Some(current), false),
+        move |(mut receiver, pending, finished)| {
+            let queue = queue.clone();
+            let id = id.clone();
+            async move {
+                if finished {
+                    return None;
+                }
+
+                let job = match pending {
+                    Some(job) => job,
+                    None => loop {
+                        match receiver.recv().await {
+                            Ok(job) if job.id == id => break job,
+                            Ok(_) => continue,
+                            // Updates were dropped, catch up with the current state
+                            Err(RecvError::Lagged(skipped)) => {
+                                warn!("Event stream of job {} skipped {} updates", id, skipped);
+                                break queue.get(&id)?;
+                            }
+                            Err(RecvError::Closed) => return None,
+                        }
+                    },
+                };
+
+                let finished = job.status.is_finished();
+                Some((sse_event(&job), (receiver, None, finished)))
+            }
+        },
+    )
+}
+
+/// Queue a job and report it to the client
+fn submit(
+    jobs: &JobQueue,
+    input: JobInput,
+    settings: OcrSettings,
+) -> Result<HttpResponse, ApiError> {
+    let job = jobs.submit(input, settings)?;
+    info!("Accepted job {}", job.id);
+
+    Ok(HttpResponse::Accepted().json(job))
+}
+
+/// Encode a job update as a server-sent event
+fn sse_event(job: &Job) -> Result<web::Bytes, actix_web::Error> {
+    let data = serde_json::to_string(job)?;
+    Ok(web::Bytes::from(format!("event: job\ndata: {}\n\n", data)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::JobStatus;
+    use crate::services::engine::FakeEngine;
+    use crate::services::{OcrService, ScreenCaptureService};
+    use futures_util::StreamExt;
+
+    #[actix_web::test]
+    async fn test_event_stream_follows_job_until_finished() {
+        let queue = JobQueue::new(10, 1 << 20);
+        let png_data = ScreenCaptureService::to_png(&image::RgbaImage::new(2, 2)).unwrap();
+        let other = queue
+            .submit(
+                JobInput::Images(vec![png_data.clone()]),
+                OcrSettings::default(),
+            )
+            .unwrap();
+        let job = queue
+            .submit(JobInput::Images(vec![png_data; 2]), OcrSettings::default())
+            .unwrap();
+
+        let receiver = queue.subscribe();
+        let current = queue.get(&job.id).unwrap();
+        let events = job_event_stream(queue.clone(), job.id.clone(), receiver, current);
+
+        let worker = queue.clone();
+        std::thread::spawn(move || {
+            let mut ocr_service = OcrService::with_engine(Box::new(FakeEngine::with_text("42")));
+            worker.run_next(&mut ocr_service);
+            worker.run_next(&mut ocr_service);
+        })
+        .join()
+        .unwrap();
+
+        let events: Vec<String> = events
+            .map(|event| String::from_utf8(event.unwrap().to_vec()).unwrap())
+            .collect()
+            .await;
+        let updates: Vec<(JobStatus, usize)> = events
+            .iter()
+            .map(|event| {
+                let data = event
+                    .strip_prefix("event: job\ndata: ")
+                    .and_then(|event| event.strip_suffix("\n\n"))
+                    .unwrap();
+                let update: Job = serde_json::from_str(data).unwrap();
+                assert_eq!(update.id, job.id, "update of job {} leaked", other.id);
+                (update.status, update.completed)
+            })
+            .collect();
+
+        assert_eq!(
+            updates,
+            vec![
+                (JobStatus::Queued, 0),
+                (JobStatus::Running, 0),
+                (JobStatus::Running, 1),
+                (JobStatus::Running, 2),
+                (JobStatus::Completed, 2),
+            ]
+        );
+    }
+}
@@ -0,0 +1,26 @@
+use actix_web::{get, web, HttpResponse};
+
+use crate::error::ApiError;
+use crate::models::ErrorResponse;
+use crate::services::metrics;
+use crate::state::AppState;
+
+/// Export metrics in the Prometheus text format
+#[utoipa::path(
+    tag = "diagnostics",
+    responses(
+        (status = 200, description = "Metrics in the Prometheus text format", body = String,
+            content_type = "text/plain; version=0.0.4"),
+        (status = 500, description = "Rendering the metrics failed", body = ErrorResponse),
+    )
+)]
+#[get("/metrics")]
+pub async fn get_metrics(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    state.update_metrics();
+
+    let text = metrics::render().map_err(|e| ApiError::Internal(format!("{:#}", e)))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(text))
+}
@@ -1,9 +1,25 @@
 //! HTTP API handlers
 
+pub mod diagnostics;
+pub mod events;
+pub mod jobs;
+pub mod metrics;
 pub mod monitoring;
+pub mod ocr;
+pub mod recordings;
 pub mod region;
 pub mod screenshot;
+mod upload;
 
-pub use monitoring::{get_status, start_monitoring, stop_monitoring};
-pub use region::set_region;
-pub use screenshot::{get_latest_screenshot, get_screens, take_screenshot};
+pub use diagnostics::{get_capabilities, get_diagnostics, get_health};
+pub use events::text_events;
+pub use jobs::{cancel_job, get_job, job_events, list_jobs, submit_image_job, submit_region_job};
+pub use metrics::get_metrics;
+pub use monitoring::{get_status, reinit_monitor, start_monitoring, stop_monitoring};
+pub use ocr::ocr_image;
+pub use recordings::{
+    delete_recording, download_recording, get_recording, list_recordings, start_recording,
+    stop_recording,
+};
+pub use region::{add_region, delete_region, get_region_history, list_regions, set_region};
+pub use screenshot::{get_latest_screenshot, get_screens, take_screenshot, take_thumbnail};
@@ -1,33 +1,93 @@
-use crate::models::StatusResponse;
-use crate::services::ocr::OcrService;
+use crate::error::ApiError;
+use crate::models::{
+    ErrorResponse, MonitorState, MonitorSupervision, OcrResult, RegionStatus, StatusResponse,
+    TextDiff,
+};
 use crate::state::AppState;
-use actix_web::{get, post, web, HttpResponse, Responder};
+use actix_web::{get, post, web, HttpResponse};
 use log::{debug, error, info};
+use std::collections::HashMap;
 
 /// Get the current monitoring status
+#[utoipa::path(
+    tag = "monitoring",
+    responses(
+        (status = 200, description = "Status of the monitor and every region", body = StatusResponse),
+    )
+)]
 #[get("/api/status")]
-pub async fn get_status(state: web::Data<AppState>) -> impl Responder {
+pub async fn get_status(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
     debug!("Request for current status");
 
-    // Create a macro to reduce boilerplate for mutex locking with error handling
-    macro_rules! safe_lock {
-        ($mutex:expr, $err_msg:expr) => {
-            match $mutex.lock() {
-                Ok(guard) => guard,
-                Err(e) => {
-                    error!("{}: {}", $err_msg, e);
-                    return HttpResponse::InternalServerError()
-                        .json(format!("Internal server error: {}", $err_msg));
-                }
+    let regions = state
+        .regions
+        .lock()
+        .map_err(|_| ApiError::lock("regions"))?
+        .clone();
+    let ocr_ready = *state
+        .ocr_ready
+        .lock()
+        .map_err(|_| ApiError::lock("OCR ready flag"))?;
+    let ocr_results = state
+        .ocr_results
+        .lock()
+        .map_err(|_| ApiError::lock("OCR results"))?
+        .clone();
+    let capture_rates = state
+        .capture_rates
+        .lock()
+        .map_err(|_| ApiError::lock("capture rates"))?
+        .clone();
+    let is_monitoring = *state
+        .is_monitoring
+        .lock()
+        .map_err(|_| ApiError::lock("monitoring status"))?;
+    let region_health = state
+        .region_health
+        .lock()
+        .map_err(|_| ApiError::lock("region health"))?
+        .clone();
+    let last_diffs: HashMap<String, TextDiff> = state
+        .text_history
+        .lock()
+        .map_err(|_| ApiError::lock("text history"))?
+        .iter()
+        .filter_map(|(id, changes)| Some((id.clone(), changes.back()?.diff.clone())))
+        .collect();
+
+    // Top-level fields describe the primary region for single-region clients
+    let primary = AppState::primary_region_of(&regions);
+    let region = primary.map(|monitored| monitored.region.clone());
+    let ocr_result = primary
+        .and_then(|monitored| ocr_results.get(&monitored.id).cloned())
+        .unwrap_or_else(OcrResult::empty);
+
+    let now = chrono::Utc::now();
+    let region_statuses = regions
+        .iter()
+        .map(|monitored| {
+            let result = ocr_results
+                .get(&monitored.id)
+                .cloned()
+                .unwrap_or_else(OcrResult::empty);
+            let health = region_health
+                .get(&monitored.id)
+                .cloned()
+                .unwrap_or_default();
+            let stale = !is_monitoring || health.is_stale(&monitored.schedule, now);
+
+            RegionStatus {
+                id: monitored.id.clone(),
+                region: monitored.region.clone(),
+                last_text: result.text,
+                last_update: result.timestamp,
+                capture_rate: capture_rates.get(&monitored.id).copied(),
+                health,
+                stale,
+                last_diff: last_diffs.get(&monitored.id).cloned(),
             }
-        };
-    }
-
-    // Safely acquire all the locks we need
-    let region = safe_lock!(state.region, "region lock").clone();
-    let ocr_ready = *safe_lock!(state.ocr_ready, "OCR ready flag lock");
-    let ocr_result = safe_lock!(state.ocr_result, "OCR result lock").clone();
-    let is_monitoring = *safe_lock!(state.is_monitoring, "monitoring status lock");
+        })
+        .collect();
 
     // Check if screenshot is available
     let has_screenshot = match state.latest_screenshot.lock() {
@@ -56,123 +116,118 @@ pub async fn get_status(state: web::Data<AppState>) -> impl Responder {
         last_update: ocr_result.timestamp,
         ocr_ready,
         has_screenshot,
+        regions: region_statuses,
     };
 
     debug!("Serializing status response");
-    HttpResponse::Ok().json(status)
+    Ok(HttpResponse::Ok().json(status))
 }
 
 /// Start monitoring the selected region
+#[utoipa::path(
+    tag = "monitoring",
+    responses(
+        (status = 200, description = "Monitoring started", body = String, content_type = "text/plain"),
+        (status = 400, description = "No region is selected", body = ErrorResponse),
+        (status = 409, description = "Already monitoring", body = ErrorResponse),
+    )
+)]
 #[post("/api/monitor/start")]
-pub async fn start_monitoring(state: web::Data<AppState>) -> impl Responder {
+pub async fn start_monitoring(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
     debug!("Request to start monitoring");
 
     // Check if already monitoring
-    if let Ok(is_monitoring) = state.is_monitoring.lock() {
-        if *is_monitoring {
-            debug!("Monitoring already active");
-            return HttpResponse::BadRequest().body("Already monitoring");
-        }
-    } else {
-        error!("Failed to lock monitoring status");
-        return HttpResponse::InternalServerError().body("Internal server error");
+    if *state
+        .is_monitoring
+        .lock()
+        .map_err(|_| ApiError::lock("monitoring status"))?
+    {
+        debug!("Monitoring already active");
+        return Err(ApiError::Conflict("Already monitoring".to_string()));
     }
 
     // Check if a region is selected
-    let selected_region = match state.region.lock() {
-        Ok(region) => {
-            if region.is_none() {
-                debug!("No region selected for monitoring");
-                return HttpResponse::BadRequest().body("No region selected");
-            }
-            region.clone()
-        }
-        Err(_) => {
-            error!("Failed to lock region");
-            return HttpResponse::InternalServerError().body("Internal server error");
-        }
-    };
-
-    // Set monitoring flag first
-    if let Ok(mut is_monitoring) = state.is_monitoring.lock() {
-        *is_monitoring = true;
-        info!("Monitoring flag set to active");
-    } else {
-        error!("Failed to lock monitoring status for activation");
-        return HttpResponse::InternalServerError().body("Failed to start monitoring");
-    }
-
-    // Trigger an immediate OCR processing in a separate thread
-    if let Some(region) = selected_region {
-        let state_clone = state.clone();
-        tokio::spawn(async move {
-            // Perform an immediate scan without waiting for the monitor loop
-            match OcrService::new() {
-                Ok(mut ocr_service) => {
-                    info!("Performing initial OCR scan");
-                    match ocr_service.extract_text_from_region(&region) {
-                        Ok(result) => {
-                            // Only update if we're still monitoring (in case it was stopped immediately)
-                            if let Ok(is_monitoring) = state_clone.is_monitoring.lock() {
-                                if *is_monitoring {
-                                    info!(
-                                        "Initial OCR scan detected: {} characters",
-                                        result.text.len()
-                                    );
-                                    if let Ok(mut ocr_result) = state_clone.ocr_result.lock() {
-                                        *ocr_result = result;
-                                    }
-                                } else {
-                                    info!("Monitoring stopped before initial scan completed - discarding results");
-                                }
-                            }
-                        }
-                        Err(e) => error!("Failed to perform initial OCR scan: {}", e),
-                    }
-                }
-                Err(e) => error!("Failed to initialize OCR service for initial scan: {}", e),
-            }
-        });
+    if state
+        .regions
+        .lock()
+        .map_err(|_| ApiError::lock("regions"))?
+        .is_empty()
+    {
+        debug!("No region selected for monitoring");
+        return Err(ApiError::NoRegion);
     }
 
+    // Set monitoring flag first
+    *state
+        .is_monitoring
+        .lock()
+        .map_err(|_| ApiError::lock("monitoring status"))? = true;
+    info!("Monitoring flag set to active");
+
+    // The monitor reads every region on its next poll and publishes the
+    // initial results like any later ones
     info!("Monitoring started successfully");
-    HttpResponse::Ok().body("Monitoring started")
+    Ok(HttpResponse::Ok().body("Monitoring started"))
 }
 
 /// Stop monitoring
+#[utoipa::path(
+    tag = "monitoring",
+    responses(
+        (status = 200, description = "Monitoring stopped or was already inactive", body = String, content_type = "text/plain"),
+    )
+)]
 #[post("/api/monitor/stop")]
-pub async fn stop_monitoring(state: web::Data<AppState>) -> impl Responder {
+pub async fn stop_monitoring(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
     debug!("Request to stop monitoring");
 
-    // Check if we're actually monitoring first
-    let was_monitoring = match state.is_monitoring.lock() {
-        Ok(guard) => *guard,
-        Err(e) => {
-            error!("Failed to check monitoring status: {}", e);
-            return HttpResponse::InternalServerError().body("Failed to stop monitoring");
-        }
-    };
-
-    // Set monitoring flag to false
-    if let Ok(mut is_monitoring) = state.is_monitoring.lock() {
-        *is_monitoring = false;
-
-        if was_monitoring {
-            info!("Monitoring stopped successfully");
+    // Clear the monitoring flag, remembering whether it was set
+    let was_monitoring = std::mem::replace(
+        &mut *state
+            .is_monitoring
+            .lock()
+            .map_err(|_| ApiError::lock("monitoring status"))?,
+        false,
+    );
 
-            // Clear the OCR text but keep record of when it was stopped
-            if let Err(e) = state.clear_current_result() {
-                error!("Failed to clear OCR result: {}", e);
-                // Still continue, this isn't fatal
-            }
+    if was_monitoring {
+        info!("Monitoring stopped successfully");
 
-            HttpResponse::Ok().body("Monitoring stopped")
-        } else {
-            debug!("Monitoring was already inactive");
-            HttpResponse::Ok().body("Monitoring was already inactive")
+        // Clear the OCR text but keep record of when it was stopped
+        if let Err(e) = state.clear_current_result() {
+            error!("Failed to clear OCR result: {}", e);
+            // Still continue, this isn't fatal
         }
+
+        Ok(HttpResponse::Ok().body("Monitoring stopped"))
     } else {
-        error!("Failed to lock monitoring status");
-        HttpResponse::InternalServerError().body("Failed to stop monitoring")
+        debug!("Monitoring was already inactive");
+        Ok(HttpResponse::Ok().body("Monitoring was already inactive"))
     }
 }
+
+/// Re-initialize the monitor's OCR engine
+///
+/// Restarts the monitor right away, also while it waits to restart after
+/// a failure, e.g. after installing missing tessdata. Answers 202 with
+/// the supervision status; poll `/api/diagnostics` to follow progress.
+#[utoipa::path(
+    tag = "monitoring",
+    responses(
+        (status = 202, description = "Re-initialization requested", body = MonitorSupervision),
+        (status = 501, description = "The monitor does not run in this build or on this platform", body = ErrorResponse),
+    )
+)]
+#[post("/api/monitor/reinit")]
+pub async fn reinit_monitor(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    debug!("Request to re-initialize the monitor");
+
+    if state.supervisor.status().state == MonitorState::Stopped {
+        return Err(ApiError::CaptureUnsupported);
+    }
+
+    state.supervisor.request_reinit();
+    info!("Monitor re-initialization requested");
+
+    Ok(HttpResponse::Accepted().json(state.supervisor.status()))
+}
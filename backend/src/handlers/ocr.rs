@@ -0,0 +1,70 @@
+use actix_web::{post, web, HttpRequest, HttpResponse};
+use log::{debug, info};
+
+use super::upload::read_images;
+use crate::error::ApiError;
+use crate::models::{ErrorResponse, OcrResult, OcrSettings};
+use crate::openapi::{Image, ImageUpload};
+use crate::state::AppState;
+
+/// Run OCR on an uploaded image
+///
+/// The image (PNG, JPEG or TIFF) is either the raw request body or the
+/// `image` field of a multipart form. OCR settings can be given as query
+/// parameters or as a JSON `settings` form field, which takes precedence.
+/// Use `/api/jobs/images` to OCR several images in the background.
+#[utoipa::path(
+    tag = "ocr",
+    params(OcrSettings),
+    request_body(
+        description = "The image as raw body or multipart form",
+        content(
+            (Image = "image/png"),
+            (Image = "image/jpeg"),
+            (Image = "image/tiff"),
+            (ImageUpload = "multipart/form-data"),
+        )
+    ),
+    responses(
+        (status = 200, description = "Recognized text, words and confidence", body = OcrResult),
+        (status = 400, description = "No image, several images or invalid settings", body = ErrorResponse),
+        (status = 413, description = "The upload is too large", body = ErrorResponse),
+        (status = 415, description = "The image is not PNG, JPEG or TIFF", body = ErrorResponse),
+        (status = 500, description = "OCR failed", body = ErrorResponse),
+    )
+)]
+#[post("/api/ocr")]
+pub async fn ocr_image(
+    req: HttpRequest,
+    payload: web::Payload,
+    query: web::Query<OcrSettings>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let upload = read_images(&req, payload, query.into_inner(), 1).await?;
+
+    let settings = upload.settings;
+    let [data] = <[Vec<u8>; 1]>::try_from(upload.images).map_err(|_| {
+        ApiError::InvalidRequest(
+            "Expected a single image, submit batches to /api/jobs/images".to_string(),
+        )
+    })?;
+
+    info!(
+        "Running OCR on uploaded image ({} bytes, language={}, psm={})",
+        data.len(),
+        settings.language,
+        settings.psm
+    );
+
+    // Tesseract is CPU bound, keep it off the async workers
+    let result = web::block(move || {
+        state.ocr_pool.run(&settings, |ocr_service| {
+            ocr_service.extract_text_from_bytes(&data)
+        })
+    })
+    .await?
+    .map_err(ApiError::ocr)?;
+
+    debug!("OCR of uploaded image found {} words", result.words.len());
+    Ok(HttpResponse::Ok().json(result))
+}
@@ -0,0 +1,162 @@
+use actix_files::NamedFile;
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse};
+use log::debug;
+
+use crate::error::ApiError;
+use crate::models::{ErrorResponse, RecordingSession, StartRecordingRequest};
+use crate::openapi::Image;
+use crate::state::AppState;
+
+/// Start recording the monitor's captures into a new session archive
+///
+/// Frames are only recorded while monitoring is running.
+#[utoipa::path(
+    tag = "recordings",
+    request_body = StartRecordingRequest,
+    responses(
+        (status = 201, description = "The recording started", body = RecordingSession),
+        (status = 400, description = "The region list is empty", body = ErrorResponse),
+        (status = 409, description = "A recording is already running", body = ErrorResponse),
+    )
+)]
+#[post("/api/recordings/start")]
+pub async fn start_recording(
+    req: web::Json<StartRecordingRequest>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let request = req.into_inner();
+
+    if request.regions.as_ref().is_some_and(Vec::is_empty) {
+        debug!("Rejecting recording of an empty region list");
+        return Err(ApiError::InvalidRequest(
+            "Invalid recording: regions must not be empty, omit it to record all regions"
+                .to_string(),
+        ));
+    }
+
+    // Keep the settings of the recorded regions as they were at the start
+    let regions = state
+        .regions
+        .lock()
+        .map_err(|_| ApiError::lock("regions"))?
+        .iter()
+        .filter(|monitored| {
+            request
+                .regions
+                .as_ref()
+                .is_none_or(|ids| ids.contains(&monitored.id))
+        })
+        .cloned()
+        .collect();
+
+    let session = web::block(move || state.recorder.start(request, regions)).await??;
+
+    Ok(HttpResponse::Created().json(session))
+}
+
+/// Stop the running recording and finish its archive
+#[utoipa::path(
+    tag = "recordings",
+    responses(
+        (status = 200, description = "The finished session", body = RecordingSession),
+        (status = 409, description = "No recording is running", body = ErrorResponse),
+    )
+)]
+#[post("/api/recordings/stop")]
+pub async fn stop_recording(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let session = web::block(move || state.recorder.stop()).await??;
+
+    Ok(HttpResponse::Ok().json(session))
+}
+
+/// List all recorded sessions, oldest first
+#[utoipa::path(
+    tag = "recordings",
+    responses(
+        (status = 200, description = "All sessions, including a running one", body = Vec<RecordingSession>),
+    )
+)]
+#[get("/api/recordings")]
+pub async fn list_recordings(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let sessions = web::block(move || state.recorder.list()).await??;
+
+    Ok(HttpResponse::Ok().json(sessions))
+}
+
+/// Get the summary of a recorded session
+#[utoipa::path(
+    tag = "recordings",
+    params(("id" = String, Path, description = "Identifier of the session")),
+    responses(
+        (status = 200, description = "The session", body = RecordingSession),
+        (status = 404, description = "No session has this id", body = ErrorResponse),
+    )
+)]
+#[get("/api/recordings/{id}")]
+pub async fn get_recording(
+    path: web::Path<String>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let session = web::block(move || state.recorder.get(&id)).await??;
+
+    Ok(HttpResponse::Ok().json(session))
+}
+
+/// Download the tar archive of a finished session
+///
+/// The archive holds every recorded frame as `frames/<sequence>-<region>.png`
+/// with its metadata in a `.json` file of the same name, and the session
+/// summary as `session.json`.
+#[utoipa::path(
+    tag = "recordings",
+    params(("id" = String, Path, description = "Identifier of the session")),
+    responses(
+        (status = 200, description = "The session archive", body = Image, content_type = "application/x-tar"),
+        (status = 404, description = "No session has this id", body = ErrorResponse),
+        (status = 409, description = "The session is still being recorded", body = ErrorResponse),
+    )
+)]
+#[get("/api/recordings/{id}/archive")]
+pub async fn download_recording(
+    req: HttpRequest,
+    path: web::Path<String>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let archive_id = id.clone();
+    let archive = web::block(move || state.recorder.archive(&archive_id)).await??;
+
+    let file = NamedFile::open_async(&archive)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to open recording '{}': {}", id, e)))?
+        .set_content_type("application/x-tar".parse().expect("valid MIME type"))
+        .set_content_disposition(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(format!("{}.tar", id))],
+        });
+
+    Ok(file.into_response(&req))
+}
+
+/// Delete a finished session and its archive
+#[utoipa::path(
+    tag = "recordings",
+    params(("id" = String, Path, description = "Identifier of the session")),
+    responses(
+        (status = 204, description = "The session was deleted"),
+        (status = 404, description = "No session has this id", body = ErrorResponse),
+        (status = 409, description = "The session is still being recorded", body = ErrorResponse),
+    )
+)]
+#[delete("/api/recordings/{id}")]
+pub async fn delete_recording(
+    path: web::Path<String>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    web::block(move || state.recorder.delete(&id)).await??;
+
+    Ok(HttpResponse::NoContent().finish())
+}
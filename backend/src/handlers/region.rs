@@ -1,29 +1,253 @@
-use actix_web::{post, web, HttpResponse, Responder};
+use actix_web::{delete, get, post, web, HttpResponse};
 use log::{debug, info};
 
-use crate::models::SetRegionRequest;
+use crate::error::ApiError;
+use crate::models::{
+    CaptureSchedule, ErrorResponse, HistoryQuery, MonitoredRegion, NumericExport, Region,
+    SetRegionRequest, StabilityFilter, TextChange, TextNormalization,
+};
+use crate::services::{geometry, ScreenCaptureService};
 use crate::state::AppState;
 
+/// Validate a region and check it fits its display
+///
+/// The bounds check is skipped when the display's size is unknown; the
+/// monitor reports regions that don't fit once it captures them.
+async fn check_region(region: &Region) -> Result<(), ApiError> {
+    region.validate().map_err(|e| {
+        debug!("Rejecting invalid region: {:?}", region);
+        ApiError::InvalidRegion(format!("Invalid region: {}", e))
+    })?;
+
+    let display_id = region.display_id;
+    match web::block(move || ScreenCaptureService::display_size(display_id)).await? {
+        Some((width, height)) => {
+            geometry::map_region(region, width, height).map_err(|e| {
+                debug!(
+                    "Rejecting region outside display {}: {:?}",
+                    display_id, region
+                );
+                ApiError::InvalidRegion(format!("Invalid region: {}", e))
+            })?;
+        }
+        None => debug!(
+            "Skipping bounds check, size of display {} is unknown",
+            display_id
+        ),
+    }
+
+    Ok(())
+}
+
 /// Set the region to monitor
+///
+/// This replaces the default region and leaves other monitored regions untouched.
+#[utoipa::path(
+    tag = "regions",
+    request_body = SetRegionRequest,
+    responses(
+        (status = 200, description = "The region now monitored", body = Region),
+        (status = 400, description = "Invalid region", body = ErrorResponse),
+    )
+)]
 #[post("/api/region")]
 pub async fn set_region(
     req: web::Json<SetRegionRequest>,
     state: web::Data<AppState>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let region = req.region.clone();
 
-    if !region.is_valid() {
-        debug!("Rejecting invalid region: {:?}", region);
-        return HttpResponse::BadRequest().json("Invalid region: dimensions must be positive");
-    }
+    check_region(&region).await?;
 
     info!(
         "Setting monitoring region: x={}, y={}, width={}, height={}",
         region.x, region.y, region.width, region.height
     );
 
-    let mut app_region = state.region.lock().unwrap();
-    *app_region = Some(region.clone());
+    state
+        .upsert_region(MonitoredRegion::new(
+            MonitoredRegion::DEFAULT_ID,
+            region.clone(),
+        ))
+        .map_err(ApiError::Internal)?;
+
+    Ok(HttpResponse::Ok().json(region))
+}
+
+/// List all monitored regions
+#[utoipa::path(
+    tag = "regions",
+    responses(
+        (status = 200, description = "All monitored regions", body = Vec<MonitoredRegion>),
+    )
+)]
+#[get("/api/regions")]
+pub async fn list_regions(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    debug!("Request to list monitored regions");
+
+    let regions = state
+        .regions
+        .lock()
+        .map_err(|_| ApiError::lock("regions"))?
+        .clone();
+
+    Ok(HttpResponse::Ok().json(regions))
+}
+
+/// Add a monitored region, replacing any region with the same id
+#[utoipa::path(
+    tag = "regions",
+    request_body = MonitoredRegion,
+    responses(
+        (status = 200, description = "The region now monitored", body = MonitoredRegion),
+        (status = 400, description = "Invalid region or region settings", body = ErrorResponse),
+    )
+)]
+#[post("/api/regions")]
+pub async fn add_region(
+    req: web::Json<MonitoredRegion>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let monitored = req.into_inner();
+
+    if monitored.id.trim().is_empty() {
+        debug!("Rejecting region without id");
+        return Err(ApiError::InvalidRegion(
+            "Invalid region: id must not be empty".to_string(),
+        ));
+    }
+
+    check_region(&monitored.region).await?;
+
+    if !monitored.schedule.is_valid() {
+        debug!("Rejecting invalid schedule: {:?}", monitored.schedule);
+        return Err(ApiError::InvalidRegion(format!(
+            "Invalid schedule: interval must be at least {}ms and not exceed max_interval_ms, which must not exceed {}ms",
+            CaptureSchedule::MIN_INTERVAL_MS,
+            CaptureSchedule::MAX_INTERVAL_MS
+        )));
+    }
+
+    if let Some(numeric) = monitored
+        .numeric
+        .as_ref()
+        .filter(|numeric| !numeric.is_valid())
+    {
+        debug!("Rejecting invalid numeric export: {:?}", numeric);
+        return Err(ApiError::InvalidRegion(format!(
+            "Invalid numeric export: decimal_separator must be '.' or ',' and stale_after_ms at least {}ms",
+            NumericExport::MIN_STALE_AFTER_MS
+        )));
+    }
+
+    if let Some(stability) = monitored
+        .stability
+        .as_ref()
+        .filter(|stability| !stability.is_valid())
+    {
+        debug!("Rejecting invalid stability filter: {:?}", stability);
+        return Err(ApiError::InvalidRegion(format!(
+            "Invalid stability filter: count and window must be between 1 and {}, min_ms at most {}ms",
+            StabilityFilter::MAX_CAPTURES,
+            StabilityFilter::MAX_DURATION_MS
+        )));
+    }
+
+    if let Some(normalization) = monitored
+        .normalization
+        .as_ref()
+        .filter(|normalization| !normalization.is_valid())
+    {
+        debug!("Rejecting invalid text normalization: {:?}", normalization);
+        return Err(ApiError::InvalidRegion(format!(
+            "Invalid text normalization: at most {} non-empty dictionary words and max_edits at most {}",
+            TextNormalization::MAX_DICTIONARY_WORDS,
+            TextNormalization::MAX_EDITS
+        )));
+    }
+
+    info!(
+        "Adding monitored region '{}': display={}, x={}, y={}, width={}, height={}, interval={}ms, adaptive={}",
+        monitored.id,
+        monitored.region.display_id,
+        monitored.region.x,
+        monitored.region.y,
+        monitored.region.width,
+        monitored.region.height,
+        monitored.schedule.interval_ms,
+        monitored.schedule.adaptive
+    );
+
+    state
+        .upsert_region(monitored.clone())
+        .map_err(ApiError::Internal)?;
+
+    Ok(HttpResponse::Ok().json(monitored))
+}
+
+/// Stop monitoring a region
+#[utoipa::path(
+    tag = "regions",
+    params(("id" = String, Path, description = "Identifier of the region")),
+    responses(
+        (status = 204, description = "The region is no longer monitored"),
+        (status = 404, description = "No region has this id", body = ErrorResponse),
+    )
+)]
+#[delete("/api/regions/{id}")]
+pub async fn delete_region(
+    path: web::Path<String>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+
+    if !state.remove_region(&id).map_err(ApiError::Internal)? {
+        debug!("Region '{}' not found", id);
+        return Err(ApiError::NotFound(format!("Region not found: {}", id)));
+    }
+
+    info!("Removed monitored region '{}'", id);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Get the latest text changes of a region, oldest first
+///
+/// Every change carries the new text and its diff to the previous text.
+#[utoipa::path(
+    tag = "regions",
+    params(("id" = String, Path, description = "Identifier of the region"), HistoryQuery),
+    responses(
+        (status = 200, description = "Text changes, oldest first", body = Vec<TextChange>),
+        (status = 404, description = "No region has this id", body = ErrorResponse),
+    )
+)]
+#[get("/api/regions/{id}/history")]
+pub async fn get_region_history(
+    path: web::Path<String>,
+    query: web::Query<HistoryQuery>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    debug!("Request for history of region '{}'", id);
+
+    let known = state
+        .regions
+        .lock()
+        .map_err(|_| ApiError::lock("regions"))?
+        .iter()
+        .any(|region| region.id == id);
+    if !known {
+        return Err(ApiError::NotFound(format!("Region not found: {}", id)));
+    }
+
+    let history = state
+        .text_history
+        .lock()
+        .map_err(|_| ApiError::lock("text history"))?;
+    let changes: Vec<_> = history.get(&id).into_iter().flatten().collect();
+    let skip = query
+        .limit
+        .map_or(0, |limit| changes.len().saturating_sub(limit));
 
-    HttpResponse::Ok().json(region)
+    Ok(HttpResponse::Ok().json(&changes[skip..]))
 }
@@ -1,69 +1,144 @@
+use crate::error::ApiError;
 use crate::state::AppState;
-use actix_web::{get, web, HttpResponse, Responder};
+use actix_web::{get, web, HttpResponse};
 use log::{debug, error, info};
 use once_cell::sync::Lazy;
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
 
-use crate::services::ScreenCaptureService;
+use crate::models::{ErrorResponse, OutputFormat, ScreenshotQuery};
+use crate::openapi::Image;
+use crate::services::screen_capture::DisplayInfo;
+use crate::services::{image_output, ScreenCaptureService};
 
 /// Get information about all available screens
+#[utoipa::path(
+    tag = "screenshots",
+    responses(
+        (status = 200, description = "Available displays", body = Vec<DisplayInfo>),
+        (status = 403, description = "Screen capture permission denied", body = ErrorResponse),
+        (status = 501, description = "Screen capture is not supported", body = ErrorResponse),
+    )
+)]
 #[get("/api/screens")]
-pub async fn get_screens() -> impl Responder {
+pub async fn get_screens() -> Result<HttpResponse, ApiError> {
     debug!("Request to get screen information");
 
-    match ScreenCaptureService::get_display_info() {
-        Ok(screens) => {
-            debug!("Returning information for {} screens", screens.len());
-            HttpResponse::Ok().json(screens)
-        }
-        Err(e) => {
-            error!("Failed to get screen information: {}", e);
-            HttpResponse::InternalServerError().json(format!("Error: {}", e))
-        }
-    }
+    let screens = ScreenCaptureService::get_display_info().map_err(ApiError::capture)?;
+
+    debug!("Returning information for {} screens", screens.len());
+    Ok(HttpResponse::Ok().json(screens))
 }
 
-/// Take a full screenshot of the primary screen
+/// Default bounds of thumbnail screenshots
+const THUMBNAIL_MAX_WIDTH: u32 = 320;
+const THUMBNAIL_MAX_HEIGHT: u32 = 240;
+
+/// Take a screenshot of a display
+///
+/// Query parameters select the output format and quality, the display,
+/// an optional crop rectangle and a maximum size to scale down to.
+#[utoipa::path(
+    tag = "screenshots",
+    params(ScreenshotQuery),
+    responses(
+        (status = 200, description = "The encoded screenshot",
+            content((Image = "image/png"), (Image = "image/jpeg"), (Image = "image/webp")),
+            headers(
+                ("X-Coordinate-Space" = String, description = "Unit of the requested crop rectangle"),
+                ("X-Scale-Factor" = f64, description = "Physical pixels per logical pixel"),
+                ("X-Capture-Region" = String, description = "Captured area in frame pixels as `x,y,width,height`"),
+            )),
+        (status = 400, description = "Invalid crop rectangle or quality", body = ErrorResponse),
+        (status = 403, description = "Screen capture permission denied", body = ErrorResponse),
+        (status = 500, description = "Capturing or encoding failed", body = ErrorResponse),
+        (status = 501, description = "Screen capture is not supported", body = ErrorResponse),
+        (status = 504, description = "The display did not deliver a frame in time", body = ErrorResponse),
+    )
+)]
 #[get("/api/screenshot")]
-pub async fn take_screenshot() -> impl Responder {
-    debug!("Request to take a full screenshot");
-
-    let result = (|| {
-        // Check platform support
-        if !ScreenCaptureService::is_supported() {
-            return Err(anyhow::anyhow!(
-                "Screen capture not supported on this platform"
-            ));
-        }
+pub async fn take_screenshot(query: web::Query<ScreenshotQuery>) -> Result<HttpResponse, ApiError> {
+    debug!("Request to take a screenshot: {:?}", query);
 
-        // Check permission
-        if !ScreenCaptureService::has_permission() {
-            debug!("No screen capture permission. Requesting permission...");
-            if !ScreenCaptureService::request_permission() {
-                return Err(anyhow::anyhow!("Screen capture permission denied"));
-            }
-        }
-
-        // Capture full screenshot
-        let frame = ScreenCaptureService::capture_primary_display()?;
+    screenshot_response(query.into_inner(), false).await
+}
 
-        // Convert to PNG
-        let png_data = ScreenCaptureService::to_png(&frame)?;
+/// Take a small preview screenshot, JPEG encoded by default
+#[utoipa::path(
+    tag = "screenshots",
+    params(ScreenshotQuery),
+    responses(
+        (status = 200, description = "The encoded thumbnail, at most 320x240 unless given",
+            content((Image = "image/jpeg"), (Image = "image/png"), (Image = "image/webp"))),
+        (status = 400, description = "Invalid crop rectangle or quality", body = ErrorResponse),
+        (status = 403, description = "Screen capture permission denied", body = ErrorResponse),
+        (status = 500, description = "Capturing or encoding failed", body = ErrorResponse),
+        (status = 501, description = "Screen capture is not supported", body = ErrorResponse),
+        (status = 504, description = "The display did not deliver a frame in time", body = ErrorResponse),
+    )
+)]
+#[get("/api/screenshot/thumbnail")]
+pub async fn take_thumbnail(query: web::Query<ScreenshotQuery>) -> Result<HttpResponse, ApiError> {
+    debug!("Request to take a thumbnail screenshot: {:?}", query);
+
+    screenshot_response(query.into_inner(), true).await
+}
 
-        Ok::<_, anyhow::Error>(png_data)
-    })();
+/// Capture, scale and encode a screenshot according to the query
+async fn screenshot_response(
+    query: ScreenshotQuery,
+    thumbnail: bool,
+) -> Result<HttpResponse, ApiError> {
+    let crop = query.crop().map_err(ApiError::InvalidRequest)?;
+    let quality = query.quality().map_err(ApiError::InvalidRequest)?;
+    let format = query.format.unwrap_or(if thumbnail {
+        OutputFormat::Jpeg
+    } else {
+        OutputFormat::Png
+    });
+
+    // Capturing and encoding large images is CPU bound, keep it off the async workers
+    let (data, mapping) = web::block(move || {
+        ScreenCaptureService::ensure_access()?;
+
+        let (image, mapping) =
+            ScreenCaptureService::capture_display_image(query.display.unwrap_or(0), crop.as_ref())?;
+
+        let image = if thumbnail {
+            image_output::thumbnail(
+                &image,
+                query.max_width.unwrap_or(THUMBNAIL_MAX_WIDTH),
+                query.max_height.unwrap_or(THUMBNAIL_MAX_HEIGHT),
+            )
+        } else {
+            image_output::scale_to_fit(image, query.max_width, query.max_height)
+        };
 
-    match result {
-        Ok(png_data) => {
-            debug!("Returning screenshot ({} bytes)", png_data.len());
-            HttpResponse::Ok().content_type("image/png").body(png_data)
-        }
-        Err(e) => {
-            error!("Failed to take screenshot: {}", e);
-            HttpResponse::InternalServerError().body(format!("Error: {}", e))
-        }
-    }
+        Ok((image_output::encode(&image, format, quality)?, mapping))
+    })
+    .await?
+    .map_err(ApiError::capture)?;
+
+    debug!(
+        "Returning screenshot ({} bytes) of {:?}",
+        data.len(),
+        mapping.physical
+    );
+
+    // Report how the requested area was translated to frame pixels
+    let physical = &mapping.physical;
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header(("X-Coordinate-Space", mapping.space.as_str()))
+        .insert_header(("X-Scale-Factor", mapping.scale_factor.to_string()))
+        .insert_header((
+            "X-Capture-Region",
+            format!(
+                "{},{},{},{}",
+                physical.x, physical.y, physical.width, physical.height
+            ),
+        ))
+        .body(data))
 }
 
 // Static variables for throttling
@@ -71,8 +146,15 @@ static LAST_CAPTURE_TIME: Lazy<Mutex<Instant>> = Lazy::new(|| Mutex::new(Instant
 static CAPTURE_INTERVAL: Lazy<Duration> = Lazy::new(|| Duration::from_millis(500));
 
 /// Get the latest screenshot from the monitored region
+#[utoipa::path(
+    tag = "screenshots",
+    responses(
+        (status = 200, description = "PNG image of the monitored region", content((Image = "image/png"))),
+        (status = 204, description = "No screenshot available yet"),
+    )
+)]
 #[get("/api/latest-screenshot")]
-pub async fn get_latest_screenshot(state: web::Data<AppState>) -> impl Responder {
+pub async fn get_latest_screenshot(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
     debug!("Request for latest screenshot");
 
     // Check if we should capture a new screenshot based on elapsed time
@@ -101,23 +183,21 @@ pub async fn get_latest_screenshot(state: web::Data<AppState>) -> impl Responder
     };
 
     // Get the latest screenshot data
-    let screenshot_data = match state.latest_screenshot.lock() {
-        Ok(screenshot) => {
-            let data = screenshot.clone();
-            debug!("Screenshot data locked successfully: {:?}", data.is_some());
-            data
-        }
-        Err(e) => {
-            error!("Failed to lock latest screenshot: {}", e);
-            return HttpResponse::InternalServerError().body("Internal server error");
-        }
-    };
+    let screenshot_data = state
+        .latest_screenshot
+        .lock()
+        .map_err(|_| ApiError::lock("latest screenshot"))?
+        .clone();
+    debug!(
+        "Screenshot data locked successfully: {:?}",
+        screenshot_data.is_some()
+    );
 
     // If we have a screenshot and shouldn't capture a new one, return the existing one
     if let Some(data) = screenshot_data.clone() {
         if !should_capture {
             debug!("Using cached screenshot ({} bytes)", data.len());
-            return HttpResponse::Ok().content_type("image/png").body(data);
+            return Ok(HttpResponse::Ok().content_type("image/png").body(data));
         }
     }
 
@@ -131,34 +211,31 @@ pub async fn get_latest_screenshot(state: web::Data<AppState>) -> impl Responder
 
         if is_monitoring {
             // Get the current region
-            if let Ok(region_guard) = state.region.lock() {
-                if let Some(region) = &*region_guard {
-                    info!("Capturing new screenshot for region: {:?}", region);
-
-                    // Try to capture the region
-                    match ScreenCaptureService::capture_region(region) {
-                        Ok(frame) => {
-                            match ScreenCaptureService::to_png(&frame) {
-                                Ok(png_data) => {
-                                    // Store for future requests
-                                    if let Ok(mut latest_screenshot) =
-                                        state.latest_screenshot.lock()
-                                    {
-                                        *latest_screenshot = Some(png_data.clone());
-                                        info!("Captured new screenshot ({} bytes)", png_data.len());
-                                    }
-                                    return HttpResponse::Ok()
-                                        .content_type("image/png")
-                                        .body(png_data);
-                                }
-                                Err(e) => {
-                                    error!("Failed to convert capture to PNG: {}", e);
+            if let Ok(Some(monitored)) = state.primary_region() {
+                let region = &monitored.region;
+                info!("Capturing new screenshot for region: {:?}", region);
+
+                // Try to capture the region
+                match ScreenCaptureService::capture_region(region) {
+                    Ok(image) => {
+                        match ScreenCaptureService::to_png(&image) {
+                            Ok(png_data) => {
+                                // Store for future requests
+                                if let Ok(mut latest_screenshot) = state.latest_screenshot.lock() {
+                                    *latest_screenshot = Some(png_data.clone());
+                                    info!("Captured new screenshot ({} bytes)", png_data.len());
                                 }
+                                return Ok(HttpResponse::Ok()
+                                    .content_type("image/png")
+                                    .body(png_data));
+                            }
+                            Err(e) => {
+                                error!("Failed to convert capture to PNG: {}", e);
                             }
                         }
-                        Err(e) => {
-                            error!("Failed to capture region: {}", e);
-                        }
+                    }
+                    Err(e) => {
+                        error!("Failed to capture region: {}", e);
                     }
                 }
             }
@@ -168,10 +245,10 @@ pub async fn get_latest_screenshot(state: web::Data<AppState>) -> impl Responder
     // If we have an existing screenshot but failed to capture a new one
     if let Some(data) = screenshot_data {
         info!("Returning existing screenshot ({} bytes)", data.len());
-        return HttpResponse::Ok().content_type("image/png").body(data);
+        return Ok(HttpResponse::Ok().content_type("image/png").body(data));
     }
 
     // Fall back to no content response if we have no screenshot
     debug!("No screenshot available");
-    HttpResponse::NoContent().body("No screenshot available")
+    Ok(HttpResponse::NoContent().body("No screenshot available"))
 }
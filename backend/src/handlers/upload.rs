@@ -0,0 +1,167 @@
+//! Reading image uploads shared by the OCR and job endpoints
+
+use actix_multipart::Multipart;
+use actix_web::{http::header, web, HttpRequest};
+use futures_util::StreamExt;
+use image::ImageFormat;
+use log::debug;
+
+use crate::error::ApiError;
+use crate::models::OcrSettings;
+
+/// Largest single image accepted for OCR
+const MAX_IMAGE_BYTES: usize = 20 * 1024 * 1024;
+
+/// Largest total upload accepted in one request
+const MAX_UPLOAD_BYTES: usize = 256 * 1024 * 1024;
+
+/// Name of the multipart field(s) carrying images
+const IMAGE_FIELD: &str = "image";
+
+/// Name of the multipart field carrying OCR settings as JSON
+const SETTINGS_FIELD: &str = "settings";
+
+/// Images and settings read from an upload
+pub struct ImageUpload {
+    /// Encoded images in upload order
+    pub images: Vec<Vec<u8>>,
+
+    /// OCR settings for the images
+    pub settings: OcrSettings,
+}
+
+/// Read the images of an upload
+///
+/// Images are either the raw request body or the `image` field(s) of a
+/// multipart form. OCR settings come from the query, or from a JSON
+/// `settings` form field, which takes precedence. An upload with more than
+/// `max_images` images is rejected as soon as the extra image starts.
+pub async fn read_images(
+    req: &HttpRequest,
+    payload: web::Payload,
+    settings: OcrSettings,
+    max_images: usize,
+) -> Result<ImageUpload, ApiError> {
+    let is_multipart = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("multipart/form-data"));
+
+    let upload = if is_multipart {
+        read_multipart(Multipart::new(req.headers(), payload), settings, max_images).await?
+    } else {
+        ImageUpload {
+            images: vec![read_body(payload).await?],
+            settings,
+        }
+    };
+
+    upload
+        .settings
+        .validate()
+        .map_err(ApiError::InvalidRequest)?;
+
+    for (index, data) in upload.images.iter().enumerate() {
+        match image::guess_format(data) {
+            Ok(ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Tiff) => {}
+            _ => {
+                debug!("Rejecting upload of image {} in unsupported format", index);
+                return Err(ApiError::UnsupportedMediaType(format!(
+                    "Unsupported format of image {}: expected PNG, JPEG or TIFF",
+                    index
+                )));
+            }
+        }
+    }
+
+    Ok(upload)
+}
+
+/// Read a raw image request body, enforcing the upload limit
+async fn read_body(mut payload: web::Payload) -> Result<Vec<u8>, ApiError> {
+    let mut data = Vec::new();
+
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|e| {
+            debug!("Failed to read upload: {}", e);
+            ApiError::InvalidRequest(format!("Failed to read upload: {}", e))
+        })?;
+        append_limited(&mut data, &chunk, 0)?;
+    }
+
+    if data.is_empty() {
+        return Err(ApiError::InvalidRequest("No image uploaded".to_string()));
+    }
+
+    Ok(data)
+}
+
+/// Read the image and optional settings fields of a multipart upload
+async fn read_multipart(
+    mut multipart: Multipart,
+    mut settings: OcrSettings,
+    max_images: usize,
+) -> Result<ImageUpload, ApiError> {
+    let mut images: Vec<Vec<u8>> = Vec::new();
+    let mut total = 0;
+
+    while let Some(field) = multipart.next().await {
+        let mut field = field.map_err(|e| {
+            debug!("Failed to read multipart field: {}", e);
+            ApiError::InvalidRequest(format!("Invalid multipart upload: {}", e))
+        })?;
+
+        let name = field.name().unwrap_or_default().to_string();
+        if name == IMAGE_FIELD && images.len() >= max_images {
+            debug!("Rejecting upload with more than {} images", max_images);
+            return Err(ApiError::InvalidRequest(format!(
+                "Too many images: an upload holds at most {}",
+                max_images
+            )));
+        }
+
+        let mut data = Vec::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk
+                .map_err(|e| ApiError::InvalidRequest(format!("Failed to read upload: {}", e)))?;
+            append_limited(&mut data, &chunk, total)?;
+        }
+        total += data.len();
+
+        match name.as_str() {
+            IMAGE_FIELD if !data.is_empty() => images.push(data),
+            SETTINGS_FIELD => {
+                settings = serde_json::from_slice(&data).map_err(|e| {
+                    ApiError::InvalidRequest(format!("Invalid OCR settings: {}", e))
+                })?;
+            }
+            _ => debug!("Ignoring multipart field '{}'", name),
+        }
+    }
+
+    if images.is_empty() {
+        return Err(ApiError::InvalidRequest(format!(
+            "No image uploaded: expected a '{}' form field",
+            IMAGE_FIELD
+        )));
+    }
+
+    Ok(ImageUpload { images, settings })
+}
+
+/// Append a chunk to an image buffer unless it would exceed the limits
+///
+/// `previous` is the number of bytes already read for earlier fields.
+fn append_limited(data: &mut Vec<u8>, chunk: &[u8], previous: usize) -> Result<(), ApiError> {
+    let size = data.len() + chunk.len();
+    if size > MAX_IMAGE_BYTES || previous + size > MAX_UPLOAD_BYTES {
+        return Err(ApiError::PayloadTooLarge(format!(
+            "Upload exceeds the limit of {} bytes per image and {} bytes in total",
+            MAX_IMAGE_BYTES, MAX_UPLOAD_BYTES
+        )));
+    }
+
+    data.extend_from_slice(chunk);
+    Ok(())
+}
@@ -4,19 +4,27 @@
 //! and perform OCR to extract text from those regions.
 
 mod config;
+mod error;
 mod handlers;
-mod models;
+mod openapi;
 mod services;
 mod state;
 
-use std::sync::Arc;
-
 use actix_cors::Cors;
 use actix_web::{web, App, HttpServer};
 use log::{error, info, warn};
+use screen_text_core::{hooks, models};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 use crate::config::Config;
-use crate::services::ScreenCaptureService;
+use crate::services::jobs::JobQueue;
+#[cfg(feature = "tesseract")]
+use crate::services::ocr_worker;
+use crate::services::recorder::SessionRecorder;
+use crate::services::{
+    capture_session, diagnostics, engine, geometry, metrics, ScreenCaptureService,
+};
 use crate::state::AppState;
 
 #[actix_web::main]
@@ -24,11 +32,19 @@ async fn main() -> std::io::Result<()> {
     // Initialize logging
     Config::init_logging();
 
+    // Serve OCR requests of the parent process when started as a worker
+    #[cfg(feature = "tesseract")]
+    if std::env::args().any(|arg| arg == ocr_worker::OCR_WORKER_FLAG) {
+        return ocr_worker::run_worker();
+    }
+
     // Load configuration
     let config = Config::from_env();
 
     // Check if screen capture is supported
-    if !ScreenCaptureService::is_supported() {
+    if !cfg!(feature = "capture") {
+        info!("Built without screen capture, only uploaded images can be read");
+    } else if !ScreenCaptureService::is_supported() {
         warn!("Screen capture is not supported on this platform!");
         warn!("The application may not function correctly.");
     } else {
@@ -51,14 +67,58 @@ async fn main() -> std::io::Result<()> {
         }
     }
 
+    // Register metrics so every series is exported from the first scrape
+    metrics::init();
+
+    // Errors and OCR times of the capture/OCR library feed diagnostics and metrics
+    hooks::set_error_hook(|component, message| diagnostics::report_error(component, message));
+    hooks::set_ocr_time_hook(|duration| metrics::OCR_DURATION.observe(duration.as_secs_f64()));
+
+    // Decide which engine runs OCR and where before any OCR service is created
+    if !config.ocr_engine.kind.is_available() {
+        error!(
+            "OCR engine '{}' is not compiled into this binary",
+            config.ocr_engine.kind.as_str()
+        );
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Configured OCR engine is not available",
+        ));
+    }
+    engine::configure(config.ocr_engine.clone());
+    #[cfg(feature = "tesseract")]
+    ocr_worker::set_enabled(config.ocr_worker);
+    #[cfg(not(feature = "tesseract"))]
+    if config.ocr_worker {
+        warn!("Ignoring OCR_WORKER, this build has no Tesseract engine");
+    }
+
+    // Scale factors translate logical region coordinates to frame pixels
+    geometry::set_scale_factors(config.display_scale_factors.clone());
+
+    // Configure the persistent capture streams before anything captures
+    capture_session::set_stream_fps(config.capture_fps);
+
+    // Start the workers of the background OCR job queue
+    let jobs = JobQueue::new(config.job_queue_limit, config.job_queue_bytes);
+    jobs.start_workers(config.job_workers);
+
+    // Recordings are written by the monitor and served by the API
+    let recorder = SessionRecorder::new(
+        config.recordings_dir.clone(),
+        config.recording_max_bytes,
+        config.recording_max_sessions,
+    );
+
     // Initialize application state
-    let state = AppState::new();
+    let state = AppState::new(jobs, recorder);
 
-    // Start background monitoring task
-    AppState::start_monitoring_task(Arc::new(state.clone()));
     // Create a web::Data wrapper for the state
     let app_state = web::Data::new(state);
 
+    // Start background monitoring task on the same state the handlers use
+    AppState::start_monitoring_task(app_state.clone().into_inner());
+
     // Ensure static directory exists
     if let Err(e) = std::fs::create_dir_all(&config.static_dir) {
         error!("Failed to create static directory: {}", e);
@@ -73,12 +133,24 @@ async fn main() -> std::io::Result<()> {
     // Store static directory path for use in HttpServer
     let static_dir = config.static_dir.clone();
 
+    // Generate the API description once, every worker serves the same document
+    let api_doc = openapi::ApiDoc::openapi();
+
     // Start HTTP server
     info!("Starting server at http://{}", server_url);
+    info!(
+        "API documentation at http://{}{}",
+        server_url,
+        openapi::DOCS_PATH
+    );
 
     HttpServer::new(move || {
         App::new()
             .app_data(app_state.clone()) // Clone the wrapper, not the inner state
+            // Report extractor failures in the same error format as the handlers
+            .app_data(web::JsonConfig::default().error_handler(error::json_error_handler))
+            .app_data(web::QueryConfig::default().error_handler(error::query_error_handler))
+            .app_data(web::PathConfig::default().error_handler(error::path_error_handler))
             // Configure CORS
             .wrap(
                 Cors::default()
@@ -90,11 +162,40 @@ async fn main() -> std::io::Result<()> {
             // API routes
             .service(handlers::get_screens)
             .service(handlers::take_screenshot)
+            .service(handlers::take_thumbnail)
             .service(handlers::get_latest_screenshot)
             .service(handlers::set_region)
+            .service(handlers::list_regions)
+            .service(handlers::add_region)
+            .service(handlers::delete_region)
+            .service(handlers::get_region_history)
             .service(handlers::get_status)
             .service(handlers::start_monitoring)
             .service(handlers::stop_monitoring)
+            .service(handlers::reinit_monitor)
+            .service(handlers::text_events)
+            .service(handlers::ocr_image)
+            .service(handlers::submit_region_job)
+            .service(handlers::submit_image_job)
+            .service(handlers::list_jobs)
+            .service(handlers::get_job)
+            .service(handlers::cancel_job)
+            .service(handlers::job_events)
+            .service(handlers::get_metrics)
+            .service(handlers::get_health)
+            .service(handlers::get_diagnostics)
+            .service(handlers::get_capabilities)
+            .service(handlers::start_recording)
+            .service(handlers::stop_recording)
+            .service(handlers::list_recordings)
+            .service(handlers::get_recording)
+            .service(handlers::download_recording)
+            .service(handlers::delete_recording)
+            // OpenAPI document and interactive viewer
+            .service(
+                SwaggerUi::new(format!("{}{{_:.*}}", openapi::DOCS_PATH))
+                    .url(openapi::OPENAPI_PATH, api_doc.clone()),
+            )
     })
     .bind(server_url)?
     .run()
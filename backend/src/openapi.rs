@@ -0,0 +1,113 @@
+//! OpenAPI description of the HTTP API
+//!
+//! Paths come from the `#[utoipa::path]` attributes of the handlers and
+//! schemas from the models, so the document follows the code.
+
+use utoipa::{OpenApi, ToSchema};
+
+use crate::handlers;
+use crate::models::{ErrorResponse, EventsLagged, OcrSettings, OutputFormat};
+
+/// Path the OpenAPI document is served at
+pub const OPENAPI_PATH: &str = "/api/openapi.json";
+
+/// Path of the interactive API viewer
+pub const DOCS_PATH: &str = "/api/docs/";
+
+/// The OpenAPI document of the server
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Screen Text Reader API",
+        description = "Capture screen regions, read their text with OCR and follow changes."
+    ),
+    paths(
+        handlers::screenshot::get_screens,
+        handlers::screenshot::take_screenshot,
+        handlers::screenshot::take_thumbnail,
+        handlers::screenshot::get_latest_screenshot,
+        handlers::region::set_region,
+        handlers::region::list_regions,
+        handlers::region::add_region,
+        handlers::region::delete_region,
+        handlers::region::get_region_history,
+        handlers::monitoring::get_status,
+        handlers::monitoring::start_monitoring,
+        handlers::monitoring::stop_monitoring,
+        handlers::monitoring::reinit_monitor,
+        handlers::events::text_events,
+        handlers::ocr::ocr_image,
+        handlers::jobs::submit_region_job,
+        handlers::jobs::submit_image_job,
+        handlers::jobs::list_jobs,
+        handlers::jobs::get_job,
+        handlers::jobs::cancel_job,
+        handlers::jobs::job_events,
+        handlers::metrics::get_metrics,
+        handlers::diagnostics::get_health,
+        handlers::diagnostics::get_diagnostics,
+        handlers::diagnostics::get_capabilities,
+        handlers::recordings::start_recording,
+        handlers::recordings::stop_recording,
+        handlers::recordings::list_recordings,
+        handlers::recordings::get_recording,
+        handlers::recordings::download_recording,
+        handlers::recordings::delete_recording,
+    ),
+    components(schemas(ErrorResponse, EventsLagged, Image, ImageUpload, OutputFormat)),
+    tags(
+        (name = "screenshots", description = "Displays and screenshots"),
+        (name = "regions", description = "Monitored regions and their text history"),
+        (name = "monitoring", description = "Monitor status, control and text events"),
+        (name = "ocr", description = "OCR of uploaded images"),
+        (name = "jobs", description = "Background capture and OCR jobs"),
+        (name = "diagnostics", description = "Health, diagnostics and metrics"),
+        (name = "recordings", description = "Recorded capture sessions for replay"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Encoded image data
+#[derive(ToSchema)]
+#[schema(value_type = String, format = Binary)]
+#[allow(dead_code)]
+pub struct Image(Vec<u8>);
+
+/// Multipart form uploading images for OCR
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct ImageUpload {
+    /// Images to read, PNG, JPEG or TIFF; repeat the field for batches
+    image: Vec<Image>,
+
+    /// OCR settings as JSON, overriding the query parameters
+    settings: Option<OcrSettings>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_covers_handlers() {
+        let document = ApiDoc::openapi();
+
+        assert!(document.paths.paths.contains_key("/api/status"));
+        assert!(document
+            .paths
+            .paths
+            .contains_key("/api/regions/{id}/history"));
+
+        let json = document.to_json().unwrap();
+        let schemas = document.components.unwrap().schemas;
+        for schema in ["StatusResponse", "MonitoredRegion", "TextChange", "Job"] {
+            assert!(schemas.contains_key(schema), "Missing schema {}", schema);
+        }
+
+        // Schemas only used by query parameters must be listed explicitly
+        for reference in json.split("#/components/schemas/").skip(1) {
+            let name = reference.split('"').next().unwrap();
+            assert!(schemas.contains_key(name), "Unresolved schema {}", name);
+        }
+    }
+}
@@ -0,0 +1,326 @@
+#[cfg(not(feature = "tesseract"))]
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use std::collections::{BTreeMap, HashMap};
+#[cfg(not(feature = "tesseract"))]
+use std::process::Command;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "tesseract")]
+use super::engine;
+use super::metrics;
+use super::ocr::OcrService;
+#[cfg(feature = "tesseract")]
+use crate::models::OcrSettings;
+use crate::models::{
+    ComponentError, MonitorLiveness, MonitorState, MonitorSupervision, SelfTestResult,
+    TesseractSource,
+};
+
+/// Sample image read by the OCR self-test
+const SELF_TEST_IMAGE: &[u8] = include_bytes!("../../assets/self-test.png");
+
+/// Text printed on the sample image
+const SELF_TEST_TEXT: &str = "Screen Reader Self Test 12345";
+
+/// How long the monitor may go without reporting in before it counts as dead
+const MONITOR_STALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Last error of every component, keyed by component name
+static LAST_ERRORS: Lazy<Mutex<HashMap<&'static str, ComponentError>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Unix time in milliseconds of the last monitor heartbeat (0 if none)
+static MONITOR_HEARTBEAT: AtomicI64 = AtomicI64::new(0);
+
+/// Record an error of a component for the diagnostics report and metrics
+pub fn report_error(component: &'static str, error: impl std::fmt::Display) {
+    metrics::record_error(component);
+
+    let message = error.to_string();
+    match LAST_ERRORS.lock() {
+        Ok(mut errors) => {
+            let count = errors.get(component).map_or(0, |last| last.count) + 1;
+            errors.insert(
+                component,
+                ComponentError {
+                    message,
+                    timestamp: Utc::now(),
+                    count,
+                },
+            );
+        }
+        Err(e) => warn!("Failed to lock component errors: {}", e),
+    }
+}
+
+/// Get the last error of every component that failed since startup
+pub fn last_errors() -> BTreeMap<String, ComponentError> {
+    match LAST_ERRORS.lock() {
+        Ok(errors) => errors
+            .iter()
+            .map(|(component, error)| (component.to_string(), error.clone()))
+            .collect(),
+        Err(e) => {
+            warn!("Failed to lock component errors: {}", e);
+            BTreeMap::new()
+        }
+    }
+}
+
+/// Note that the monitor thread is alive
+pub fn monitor_heartbeat() {
+    MONITOR_HEARTBEAT.store(Utc::now().timestamp_millis(), Ordering::SeqCst);
+}
+
+/// Check whether the monitor thread reported in recently
+pub fn monitor_liveness() -> MonitorLiveness {
+    let last_heartbeat = match MONITOR_HEARTBEAT.load(Ordering::SeqCst) {
+        0 => None,
+        millis => Utc.timestamp_millis_opt(millis).single(),
+    };
+
+    MonitorLiveness {
+        alive: last_heartbeat.is_some_and(is_recent),
+        last_heartbeat,
+    }
+}
+
+/// Check whether the server is live, judged by its monitor thread
+///
+/// A failed monitor is restarted by the supervisor, but nothing recovers a
+/// monitor that hangs while it is starting or running. That happened when
+/// neither its start nor its last heartbeat is recent.
+pub fn is_live(supervision: &MonitorSupervision, liveness: &MonitorLiveness) -> bool {
+    match supervision.state {
+        MonitorState::Restarting | MonitorState::Stopped => true,
+        MonitorState::Starting | MonitorState::Running => liveness
+            .last_heartbeat
+            .max(supervision.started_at)
+            .is_none_or(is_recent),
+    }
+}
+
+/// Whether a sign of life is within the stall timeout
+fn is_recent(timestamp: DateTime<Utc>) -> bool {
+    (Utc::now() - timestamp).num_milliseconds() <= MONITOR_STALL_TIMEOUT.as_millis() as i64
+}
+
+/// Tesseract version and languages as reported by diagnostics
+#[derive(Debug, Default)]
+pub struct TesseractDetails {
+    /// Tesseract version
+    pub version: Option<String>,
+
+    /// Installed tessdata languages
+    pub languages: Vec<String>,
+
+    /// tessdata directory the languages were found in
+    pub datapath: Option<String>,
+
+    /// Where the details come from
+    pub source: Option<TesseractSource>,
+}
+
+/// Describe the Tesseract that OCR runs with
+///
+/// The linked library is initialized the way engines are, so its data path
+/// and languages are the ones requests can use.
+#[cfg(feature = "tesseract")]
+pub fn tesseract_details() -> TesseractDetails {
+    let installed = engine::tesseract_languages(&OcrSettings::default().language)
+        .inspect_err(|e| debug!("Failed to list Tesseract languages: {:#}", e))
+        .ok();
+
+    TesseractDetails {
+        version: Some(engine::tesseract_version()),
+        datapath: installed
+            .as_ref()
+            .map(|installed| installed.datapath.clone()),
+        languages: installed
+            .map(|installed| installed.languages)
+            .unwrap_or_default(),
+        source: Some(TesseractSource::Library),
+    }
+}
+
+/// Describe the `tesseract` binary on `PATH`
+///
+/// This build doesn't link the library, so the binary is the only source.
+/// It may differ from what another build would read text with.
+#[cfg(not(feature = "tesseract"))]
+pub fn tesseract_details() -> TesseractDetails {
+    let version = run_tesseract(&["--version"])
+        .inspect_err(|e| debug!("Failed to query Tesseract version: {:#}", e))
+        .ok()
+        .and_then(|output| {
+            output
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .map(|line| line.trim_start_matches("tesseract").trim().to_string())
+        });
+    let listing = run_tesseract(&["--list-langs"])
+        .inspect_err(|e| debug!("Failed to list Tesseract languages: {:#}", e))
+        .unwrap_or_default();
+    let (datapath, languages) = parse_language_list(&listing);
+
+    TesseractDetails {
+        source: version.is_some().then_some(TesseractSource::Cli),
+        version,
+        languages,
+        datapath,
+    }
+}
+
+/// Parse the output of `tesseract --list-langs`
+///
+/// The header names the tessdata directory, e.g.
+/// `List of available languages in "/usr/share/tessdata/" (2):`.
+#[cfg_attr(feature = "tesseract", allow(dead_code))]
+fn parse_language_list(output: &str) -> (Option<String>, Vec<String>) {
+    let mut lines = output
+        .lines()
+        .skip_while(|line| !line.starts_with("List of available languages"));
+
+    let datapath = lines
+        .next()
+        .and_then(|header| header.split('"').nth(1))
+        .map(str::to_string);
+    let languages = lines
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    (datapath, languages)
+}
+
+/// Run the tesseract binary and return what it printed
+///
+/// Some versions print to stderr instead of stdout, so both are returned.
+#[cfg(not(feature = "tesseract"))]
+fn run_tesseract(args: &[&str]) -> Result<String> {
+    let output = Command::new("tesseract")
+        .args(args)
+        .output()
+        .context("Failed to run tesseract")?;
+
+    if !output.status.success() {
+        return Err(anyhow!("tesseract exited with {}", output.status));
+    }
+
+    Ok(format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    ))
+}
+
+/// Read the bundled sample image and compare the result with its text
+pub fn run_self_test() -> SelfTestResult {
+    let started = Instant::now();
+
+    let outcome = OcrService::new()
+        .and_then(|mut ocr_service| ocr_service.extract_text_from_bytes(SELF_TEST_IMAGE));
+
+    let duration_ms = started.elapsed().as_millis() as u64;
+    match outcome {
+        Ok(result) => SelfTestResult {
+            passed: normalize(&result.text).contains(&normalize(SELF_TEST_TEXT)),
+            expected: SELF_TEST_TEXT.to_string(),
+            text: Some(result.text),
+            confidence: result.confidence,
+            duration_ms,
+            error: None,
+        },
+        Err(e) => {
+            report_error("self_test", format!("{:#}", e));
+            SelfTestResult {
+                passed: false,
+                expected: SELF_TEST_TEXT.to_string(),
+                text: None,
+                confidence: None,
+                duration_ms,
+                error: Some(format!("{:#}", e)),
+            }
+        }
+    }
+}
+
+/// Lowercase text and collapse whitespace for comparison
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_error_counts() {
+        report_error("diagnostics_test", "first");
+        report_error("diagnostics_test", "second");
+
+        let error = &last_errors()["diagnostics_test"];
+        assert_eq!(error.message, "second");
+        assert_eq!(error.count, 2);
+    }
+
+    #[test]
+    fn test_hung_monitor_is_not_live() {
+        let stale = Utc::now() - chrono::Duration::minutes(5);
+        let mut supervision = MonitorSupervision {
+            state: MonitorState::Running,
+            restarts: 0,
+            failures: 0,
+            last_failure: None,
+            started_at: Some(stale),
+            next_restart_at: None,
+        };
+        let liveness = |last_heartbeat| MonitorLiveness {
+            alive: false,
+            last_heartbeat,
+        };
+
+        assert!(!is_live(&supervision, &liveness(Some(stale))));
+        assert!(is_live(&supervision, &liveness(Some(Utc::now()))));
+
+        // Waiting to restart is the supervisor doing its job
+        supervision.state = MonitorState::Restarting;
+        assert!(is_live(&supervision, &liveness(Some(stale))));
+
+        // A monitor that only just started hasn't had a chance to report in
+        supervision.state = MonitorState::Starting;
+        supervision.started_at = Some(Utc::now());
+        assert!(is_live(&supervision, &liveness(None)));
+    }
+
+    #[test]
+    fn test_parse_language_list() {
+        let output = "List of available languages in \"/usr/share/tessdata/\" (2):\neng\nosd\n\n";
+        let (datapath, languages) = parse_language_list(output);
+
+        assert_eq!(datapath.as_deref(), Some("/usr/share/tessdata/"));
+        assert_eq!(languages, ["eng", "osd"]);
+    }
+
+    #[test]
+    fn test_self_test_image_is_png() {
+        assert_eq!(
+            image::guess_format(SELF_TEST_IMAGE).unwrap(),
+            image::ImageFormat::Png
+        );
+        assert_eq!(
+            normalize(" Screen  Reader\nSelf Test 12345 "),
+            normalize(SELF_TEST_TEXT)
+        );
+    }
+}
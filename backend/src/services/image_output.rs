@@ -0,0 +1,102 @@
+use anyhow::{Context, Result};
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::{ColorType, DynamicImage, ImageEncoder, RgbaImage};
+
+use crate::models::OutputFormat;
+
+/// Scale an image down so it fits within the given bounds
+///
+/// The aspect ratio is preserved and images are never scaled up.
+pub fn scale_to_fit(
+    image: RgbaImage,
+    max_width: Option<u32>,
+    max_height: Option<u32>,
+) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let max_width = max_width.unwrap_or(width).max(1);
+    let max_height = max_height.unwrap_or(height).max(1);
+
+    if width <= max_width && height <= max_height {
+        return image;
+    }
+
+    let scale = (max_width as f64 / width as f64).min(max_height as f64 / height as f64);
+    let target_width = ((width as f64 * scale).round() as u32).max(1);
+    let target_height = ((height as f64 * scale).round() as u32).max(1);
+
+    image::imageops::resize(&image, target_width, target_height, FilterType::Triangle)
+}
+
+/// Create a small preview image quickly
+pub fn thumbnail(image: &RgbaImage, max_width: u32, max_height: u32) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let scale = (max_width as f64 / width as f64)
+        .min(max_height as f64 / height as f64)
+        .min(1.0);
+    let target_width = ((width as f64 * scale).round() as u32).max(1);
+    let target_height = ((height as f64 * scale).round() as u32).max(1);
+
+    image::imageops::thumbnail(image, target_width, target_height)
+}
+
+/// Encode an image in the requested format
+///
+/// `quality` only applies to JPEG; PNG and WebP are encoded losslessly.
+pub fn encode(image: &RgbaImage, format: OutputFormat, quality: u8) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    let (width, height) = image.dimensions();
+
+    match format {
+        OutputFormat::Png => PngEncoder::new(&mut data)
+            .write_image(image.as_raw(), width, height, ColorType::Rgba8)
+            .context("Failed to encode image as PNG")?,
+        OutputFormat::Jpeg => {
+            // JPEG has no alpha channel
+            let rgb = DynamicImage::ImageRgba8(image.clone()).to_rgb8();
+            JpegEncoder::new_with_quality(&mut data, quality)
+                .write_image(rgb.as_raw(), width, height, ColorType::Rgb8)
+                .context("Failed to encode image as JPEG")?
+        }
+        OutputFormat::Webp => WebPEncoder::new_lossless(&mut data)
+            .write_image(image.as_raw(), width, height, ColorType::Rgba8)
+            .context("Failed to encode image as WebP")?,
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale_to_fit_preserves_aspect_ratio() {
+        let image = RgbaImage::new(400, 200);
+
+        let scaled = scale_to_fit(image.clone(), Some(100), None);
+        assert_eq!(scaled.dimensions(), (100, 50));
+
+        let scaled = scale_to_fit(image.clone(), Some(300), Some(50));
+        assert_eq!(scaled.dimensions(), (100, 50));
+
+        let unchanged = scale_to_fit(image, Some(1000), Some(1000));
+        assert_eq!(unchanged.dimensions(), (400, 200));
+    }
+
+    #[test]
+    fn test_encode_formats() {
+        let image = RgbaImage::new(8, 8);
+
+        for (format, magic) in [
+            (OutputFormat::Png, &b"\x89PNG"[..]),
+            (OutputFormat::Jpeg, &b"\xFF\xD8"[..]),
+            (OutputFormat::Webp, &b"RIFF"[..]),
+        ] {
+            let data = encode(&image, format, 80).unwrap();
+            assert!(data.starts_with(magic), "{:?}", format);
+        }
+    }
+}
@@ -0,0 +1,695 @@
+use anyhow::Result;
+use chrono::Utc;
+use log::{debug, error, info};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use thiserror::Error;
+use tokio::sync::broadcast;
+
+use super::diagnostics;
+use super::ocr::OcrService;
+use super::screen_capture::ScreenCaptureService;
+use crate::models::{Job, JobItem, JobKind, JobStatus, OcrResult, OcrSettings, Region};
+
+/// Number of worker threads unless configured otherwise
+pub const DEFAULT_JOB_WORKERS: usize = 1;
+
+/// Number of jobs that may wait for a worker unless configured otherwise
+pub const DEFAULT_JOB_QUEUE_LIMIT: usize = 32;
+
+/// Image data unfinished jobs may hold unless configured otherwise
+pub const DEFAULT_JOB_QUEUE_BYTES: usize = 512 * 1024 * 1024;
+
+/// Number of finished jobs kept around for polling
+const MAX_RETAINED_JOBS: usize = 100;
+
+/// Number of job updates buffered for slow subscribers
+const EVENT_CAPACITY: usize = 256;
+
+/// Errors reported by the job queue
+#[derive(Debug, Error)]
+pub enum JobError {
+    /// Too many jobs are already waiting
+    #[error("Job queue is full ({0} jobs waiting)")]
+    QueueFull(usize),
+
+    /// Unfinished jobs already hold too much image data
+    #[error("Job queue is full ({0} bytes of images waiting)")]
+    QueueBytesFull(usize),
+
+    /// No job with the given id exists
+    #[error("Job '{0}' not found")]
+    NotFound(String),
+
+    /// The job already reached a final state
+    #[error("Job '{0}' has already finished")]
+    AlreadyFinished(String),
+}
+
+/// Work to be done by a job
+pub enum JobInput {
+    /// Capture and OCR a live screen region
+    Region(Region),
+
+    /// OCR encoded images
+    Images(Vec<Vec<u8>>),
+}
+
+impl JobInput {
+    /// Kind of job created for this input
+    fn kind(&self) -> JobKind {
+        match self {
+            JobInput::Region(_) => JobKind::Region,
+            JobInput::Images(images) if images.len() == 1 => JobKind::Image,
+            JobInput::Images(_) => JobKind::Batch,
+        }
+    }
+
+    /// Number of items processed for this input
+    fn len(&self) -> usize {
+        match self {
+            JobInput::Region(_) => 1,
+            JobInput::Images(images) => images.len(),
+        }
+    }
+
+    /// Bytes of image data held by this input
+    fn size(&self) -> usize {
+        match self {
+            JobInput::Region(_) => 0,
+            JobInput::Images(images) => images.iter().map(Vec::len).sum(),
+        }
+    }
+}
+
+/// A job and the bookkeeping needed to run it
+struct JobEntry {
+    /// Public view of the job
+    job: Job,
+
+    /// Work still to be picked up by a worker
+    input: Option<JobInput>,
+
+    /// Set to ask the worker to stop processing the job
+    cancel: Arc<AtomicBool>,
+
+    /// Bytes of image data counted against the queue until the job finishes
+    size: usize,
+}
+
+/// Jobs known to the queue
+#[derive(Default)]
+struct JobStore {
+    /// All retained jobs, keyed by id
+    entries: HashMap<String, JobEntry>,
+
+    /// Ids of jobs waiting for a worker, oldest first
+    pending: VecDeque<String>,
+
+    /// Ids of all retained jobs, oldest first
+    order: VecDeque<String>,
+
+    /// Bytes of image data held by unfinished jobs
+    size: usize,
+}
+
+impl JobStore {
+    /// Stop counting the image data of a job once it finished
+    fn release(&mut self, id: &str) {
+        if let Some(entry) = self.entries.get_mut(id) {
+            if entry.job.status.is_finished() {
+                self.size -= std::mem::take(&mut entry.size);
+            }
+        }
+    }
+}
+
+/// State shared between queue handles and workers
+struct QueueShared {
+    /// Jobs known to the queue
+    store: Mutex<JobStore>,
+
+    /// Signalled whenever a job is queued
+    work_ready: Condvar,
+
+    /// Broadcasts every job update
+    events: broadcast::Sender<Job>,
+
+    /// Source of job ids
+    next_id: AtomicU64,
+
+    /// Maximum number of jobs waiting for a worker
+    queue_limit: usize,
+
+    /// Maximum bytes of image data held by unfinished jobs
+    byte_limit: usize,
+}
+
+/// Background queue of capture/OCR jobs
+///
+/// Jobs are processed in submission order by a fixed pool of worker
+/// threads. Clients poll a job by id or subscribe to its updates, and
+/// can cancel it until it finishes.
+#[derive(Clone)]
+pub struct JobQueue {
+    shared: Arc<QueueShared>,
+}
+
+impl JobQueue {
+    /// Create an empty queue accepting up to `queue_limit` waiting jobs
+    ///
+    /// Jobs are also rejected while the unfinished ones hold more than
+    /// `byte_limit` bytes of images, so queued uploads can't exhaust memory.
+    pub fn new(queue_limit: usize, byte_limit: usize) -> Self {
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+
+        Self {
+            shared: Arc::new(QueueShared {
+                store: Mutex::new(JobStore::default()),
+                work_ready: Condvar::new(),
+                events,
+                next_id: AtomicU64::new(1),
+                queue_limit: queue_limit.max(1),
+                byte_limit,
+            }),
+        }
+    }
+
+    /// Start the worker threads processing the queue
+    pub fn start_workers(&self, count: usize) {
+        let count = count.max(1);
+        info!("Starting {} OCR job worker(s)", count);
+
+        for index in 0..count {
+            let queue = self.clone();
+            std::thread::Builder::new()
+                .name(format!("ocr-job-worker-{}", index))
+                .spawn(move || queue.worker_loop())
+                .expect("Failed to spawn OCR job worker thread");
+        }
+    }
+
+    /// Queue a new job
+    pub fn submit(&self, input: JobInput, settings: OcrSettings) -> Result<Job, JobError> {
+        let mut store = self.lock_store();
+
+        if store.pending.len() >= self.shared.queue_limit {
+            return Err(JobError::QueueFull(store.pending.len()));
+        }
+        let size = input.size();
+        if store.size + size > self.shared.byte_limit {
+            return Err(JobError::QueueBytesFull(store.size));
+        }
+
+        let id = format!("job-{}", self.shared.next_id.fetch_add(1, Ordering::SeqCst));
+        let job = Job {
+            id: id.clone(),
+            kind: input.kind(),
+            status: JobStatus::Queued,
+            settings,
+            created_at: Utc::now(),
+            started_at: None,
+            finished_at: None,
+            total: input.len(),
+            completed: 0,
+            items: Vec::new(),
+            error: None,
+        };
+
+        store.entries.insert(
+            id.clone(),
+            JobEntry {
+                job: job.clone(),
+                input: Some(input),
+                cancel: Arc::new(AtomicBool::new(false)),
+                size,
+            },
+        );
+        store.size += size;
+        store.pending.push_back(id.clone());
+        store.order.push_back(id);
+        Self::prune(&mut store);
+        drop(store);
+
+        info!(
+            "Queued {:?} job {} with {} item(s)",
+            job.kind, job.id, job.total
+        );
+        self.shared.work_ready.notify_one();
+        self.publish(&job);
+
+        Ok(job)
+    }
+
+    /// Get a job by id
+    pub fn get(&self, id: &str) -> Option<Job> {
+        self.lock_store()
+            .entries
+            .get(id)
+            .map(|entry| entry.job.clone())
+    }
+
+    /// List all retained jobs, oldest first, without their item results
+    pub fn list(&self) -> Vec<Job> {
+        let store = self.lock_store();
+        store
+            .order
+            .iter()
+            .filter_map(|id| store.entries.get(id))
+            .map(|entry| Job {
+                items: Vec::new(),
+                ..entry.job.clone()
+            })
+            .collect()
+    }
+
+    /// Cancel a job
+    ///
+    /// A queued job is cancelled immediately. A running job stops after
+    /// the item it is currently processing.
+    pub fn cancel(&self, id: &str) -> Result<Job, JobError> {
+        let mut store = self.lock_store();
+        let entry = store
+            .entries
+            .get_mut(id)
+            .ok_or_else(|| JobError::NotFound(id.to_string()))?;
+
+        if entry.job.status.is_finished() {
+            return Err(JobError::AlreadyFinished(id.to_string()));
+        }
+
+        entry.cancel.store(true, Ordering::SeqCst);
+        if entry.job.status == JobStatus::Queued {
+            entry.input = None;
+            entry.job.status = JobStatus::Cancelled;
+            entry.job.finished_at = Some(Utc::now());
+        }
+        let job = entry.job.clone();
+        store.pending.retain(|pending| pending != id);
+        store.release(id);
+        drop(store);
+
+        info!("Cancelled job {}", id);
+        self.publish(&job);
+
+        Ok(job)
+    }
+
+    /// Number of jobs waiting for a worker
+    pub fn queued(&self) -> usize {
+        self.lock_store().pending.len()
+    }
+
+    /// Number of jobs being processed by a worker
+    pub fn running(&self) -> usize {
+        self.lock_store()
+            .entries
+            .values()
+            .filter(|entry| entry.job.status == JobStatus::Running)
+            .count()
+    }
+
+    /// Subscribe to updates of all jobs
+    pub fn subscribe(&self) -> broadcast::Receiver<Job> {
+        self.shared.events.subscribe()
+    }
+
+    /// Lock the job store, recovering from a poisoned lock
+    ///
+    /// The store is only mutated in small steps that leave it consistent,
+    /// so a panicking worker must not take the whole queue down.
+    fn lock_store(&self) -> MutexGuard<'_, JobStore> {
+        self.shared
+            .store
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Send a job update to subscribers
+    fn publish(&self, job: &Job) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.shared.events.send(job.clone());
+    }
+
+    /// Drop the oldest finished jobs beyond the retention limit
+    fn prune(store: &mut JobStore) {
+        while store.order.len() > MAX_RETAINED_JOBS {
+            let finished = store.order.iter().position(|id| {
+                store
+                    .entries
+                    .get(id)
+                    .is_none_or(|entry| entry.job.status.is_finished())
+            });
+
+            match finished.and_then(|index| store.order.remove(index)) {
+                Some(id) => {
+                    store.entries.remove(&id);
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Apply a change to a job and publish the result
+    fn update(&self, id: &str, change: impl FnOnce(&mut Job)) {
+        let job = {
+            let mut store = self.lock_store();
+            let Some(entry) = store.entries.get_mut(id) else {
+                return;
+            };
+            change(&mut entry.job);
+            let job = entry.job.clone();
+            store.release(id);
+            job
+        };
+
+        self.publish(&job);
+    }
+
+    /// Wait for the next queued job and mark it as running
+    fn next_job(&self) -> (String, JobInput, OcrSettings, Arc<AtomicBool>) {
+        let mut store = self.lock_store();
+
+        loop {
+            while let Some(id) = store.pending.pop_front() {
+                let Some(entry) = store.entries.get_mut(&id) else {
+                    continue;
+                };
+                let Some(input) = entry.input.take() else {
+                    continue;
+                };
+
+                entry.job.status = JobStatus::Running;
+                entry.job.started_at = Some(Utc::now());
+                let job = entry.job.clone();
+                let claimed = (id, input, job.settings.clone(), entry.cancel.clone());
+                drop(store);
+
+                self.publish(&job);
+                return claimed;
+            }
+
+            store = self
+                .shared
+                .work_ready
+                .wait(store)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    /// Process jobs until the process exits
+    fn worker_loop(&self) {
+        // Engine of the previous job, reused while the settings match
+        let mut engine: Option<(OcrSettings, OcrService)> = None;
+
+        loop {
+            let (id, input, settings, cancel) = self.next_job();
+            debug!("Worker picked up job {}", id);
+
+            if engine
+                .as_ref()
+                .is_none_or(|(current, _)| current != &settings)
+            {
+                engine = match OcrService::with_settings(&settings) {
+                    Ok(service) => Some((settings.clone(), service)),
+                    Err(e) => {
+                        error!("Failed to initialize OCR for job {}: {:#}", id, e);
+                        diagnostics::report_error("ocr_init", format!("{:#}", e));
+                        self.update(&id, |job| {
+                            job.status = JobStatus::Failed;
+                            job.error = Some(format!("{:#}", e));
+                            job.finished_at = Some(Utc::now());
+                        });
+                        continue;
+                    }
+                };
+            }
+            let Some((_, ocr_service)) = engine.as_mut() else {
+                continue;
+            };
+
+            let status = self.run_job(&id, input, ocr_service, &cancel);
+            info!("Job {} finished: {:?}", id, status);
+        }
+    }
+
+    /// Run the next queued job with the given service, as a worker would
+    #[cfg(test)]
+    pub(crate) fn run_next(&self, ocr_service: &mut OcrService) -> JobStatus {
+        let (id, input, _, cancel) = self.next_job();
+        self.run_job(&id, input, ocr_service, &cancel)
+    }
+
+    /// Process every item of a job, recording results as they complete
+    fn run_job(
+        &self,
+        id: &str,
+        input: JobInput,
+        ocr_service: &mut OcrService,
+        cancel: &AtomicBool,
+    ) -> JobStatus {
+        let mut failure = None;
+        let mut processed = 0;
+
+        for index in 0..input.len() {
+            if cancel.load(Ordering::SeqCst) {
+                break;
+            }
+
+            let item = match Self::process_item(&input, index, ocr_service) {
+                Ok(result) => JobItem {
+                    index,
+                    result: Some(result),
+                    error: None,
+                },
+                Err(e) => {
+                    debug!("Item {} of job {} failed: {:#}", index, id, e);
+                    diagnostics::report_error("job", format!("Job {}: {:#}", id, e));
+                    let message = format!("{:#}", e);
+                    failure = Some(message.clone());
+                    JobItem {
+                        index,
+                        result: None,
+                        error: Some(message),
+                    }
+                }
+            };
+
+            self.update(id, |job| {
+                job.items.push(item);
+                job.completed += 1;
+            });
+            processed += 1;
+        }
+
+        // A cancel request arriving during the last item comes too late to
+        // stop anything. A single-item job fails with its item, a batch
+        // reports failures per item.
+        let status = if processed < input.len() {
+            JobStatus::Cancelled
+        } else if input.len() == 1 && failure.is_some() {
+            JobStatus::Failed
+        } else {
+            JobStatus::Completed
+        };
+
+        self.update(id, |job| {
+            job.status = status;
+            if status == JobStatus::Failed {
+                job.error = failure;
+            }
+            job.finished_at = Some(Utc::now());
+        });
+
+        status
+    }
+
+    /// Run OCR on one item of a job
+    fn process_item(
+        input: &JobInput,
+        index: usize,
+        ocr_service: &mut OcrService,
+    ) -> Result<OcrResult> {
+        match input {
+            JobInput::Region(region) => {
+                let image = ScreenCaptureService::capture_region(region)?;
+                ocr_service.extract_text_from_image(&image)
+            }
+            JobInput::Images(images) => ocr_service.extract_text_from_bytes(&images[index]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::engine::{EngineKind, FakeEngine, OcrEngine};
+    use std::sync::mpsc;
+
+    fn images(count: usize) -> JobInput {
+        JobInput::Images(vec![Vec::new(); count])
+    }
+
+    /// Engine that reports every item it starts and waits to be let go
+    struct GatedEngine {
+        started: mpsc::Sender<()>,
+        proceed: mpsc::Receiver<()>,
+    }
+
+    impl OcrEngine for GatedEngine {
+        fn kind(&self) -> EngineKind {
+            EngineKind::Fake
+        }
+
+        fn recognize_bytes(&mut self, _data: &[u8]) -> Result<OcrResult> {
+            self.started.send(()).unwrap();
+            self.proceed.recv().unwrap();
+            Ok(OcrResult::new("item".to_string()))
+        }
+    }
+
+    /// Run the next job on a worker thread that waits before every item
+    fn spawn_gated_worker(
+        queue: &JobQueue,
+    ) -> (
+        mpsc::Receiver<()>,
+        mpsc::Sender<()>,
+        std::thread::JoinHandle<JobStatus>,
+    ) {
+        let (started, started_rx) = mpsc::channel();
+        let (proceed_tx, proceed) = mpsc::channel();
+        let queue = queue.clone();
+        let worker = std::thread::spawn(move || {
+            let engine = GatedEngine { started, proceed };
+            queue.run_next(&mut OcrService::with_engine(Box::new(engine)))
+        });
+        (started_rx, proceed_tx, worker)
+    }
+
+    #[test]
+    fn test_submit_assigns_ids_and_kinds() {
+        let queue = JobQueue::new(10, DEFAULT_JOB_QUEUE_BYTES);
+
+        let single = queue.submit(images(1), OcrSettings::default()).unwrap();
+        let batch = queue.submit(images(3), OcrSettings::default()).unwrap();
+
+        assert_ne!(single.id, batch.id);
+        assert_eq!(single.kind, JobKind::Image);
+        assert_eq!(batch.kind, JobKind::Batch);
+        assert_eq!(batch.total, 3);
+        assert_eq!(queue.get(&batch.id).unwrap().status, JobStatus::Queued);
+    }
+
+    #[test]
+    fn test_queue_limit() {
+        let queue = JobQueue::new(2, DEFAULT_JOB_QUEUE_BYTES);
+        queue.submit(images(1), OcrSettings::default()).unwrap();
+        queue.submit(images(1), OcrSettings::default()).unwrap();
+
+        assert!(matches!(
+            queue.submit(images(1), OcrSettings::default()),
+            Err(JobError::QueueFull(2))
+        ));
+    }
+
+    #[test]
+    fn test_cancel_queued_job() {
+        let queue = JobQueue::new(1, DEFAULT_JOB_QUEUE_BYTES);
+        let job = queue.submit(images(1), OcrSettings::default()).unwrap();
+
+        let cancelled = queue.cancel(&job.id).unwrap();
+        assert_eq!(cancelled.status, JobStatus::Cancelled);
+        assert!(matches!(
+            queue.cancel(&job.id),
+            Err(JobError::AlreadyFinished(_))
+        ));
+        assert!(matches!(
+            queue.cancel("missing"),
+            Err(JobError::NotFound(_))
+        ));
+
+        // The cancelled job no longer counts against the limit
+        assert!(queue.submit(images(1), OcrSettings::default()).is_ok());
+    }
+
+    #[test]
+    fn test_byte_limit_counts_unfinished_jobs() {
+        let queue = JobQueue::new(10, 10);
+        let job = queue
+            .submit(JobInput::Images(vec![vec![0; 6]]), OcrSettings::default())
+            .unwrap();
+
+        assert!(matches!(
+            queue.submit(JobInput::Images(vec![vec![0; 6]]), OcrSettings::default()),
+            Err(JobError::QueueBytesFull(6))
+        ));
+
+        queue.cancel(&job.id).unwrap();
+        assert!(queue
+            .submit(JobInput::Images(vec![vec![0; 6]]), OcrSettings::default())
+            .is_ok());
+    }
+
+    #[test]
+    fn test_worker_runs_job_and_publishes_updates() {
+        let queue = JobQueue::new(10, DEFAULT_JOB_QUEUE_BYTES);
+        let mut events = queue.subscribe();
+        let png_data = ScreenCaptureService::to_png(&image::RgbaImage::new(2, 2)).unwrap();
+        let job = queue
+            .submit(JobInput::Images(vec![png_data; 2]), OcrSettings::default())
+            .unwrap();
+
+        let mut ocr_service = OcrService::with_engine(Box::new(FakeEngine::with_text("42")));
+        assert_eq!(queue.run_next(&mut ocr_service), JobStatus::Completed);
+
+        let finished = queue.get(&job.id).unwrap();
+        assert_eq!(finished.completed, 2);
+        assert_eq!(finished.items[1].result.as_ref().unwrap().text, "42");
+
+        let mut updates = Vec::new();
+        while let Ok(update) = events.try_recv() {
+            updates.push((update.status, update.completed));
+        }
+        assert_eq!(
+            updates,
+            vec![
+                (JobStatus::Queued, 0),
+                (JobStatus::Running, 0),
+                (JobStatus::Running, 1),
+                (JobStatus::Running, 2),
+                (JobStatus::Completed, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cancel_running_job_between_items() {
+        let queue = JobQueue::new(10, DEFAULT_JOB_QUEUE_BYTES);
+        let job = queue.submit(images(3), OcrSettings::default()).unwrap();
+        let (started, proceed, worker) = spawn_gated_worker(&queue);
+
+        started.recv().unwrap();
+        assert_eq!(queue.cancel(&job.id).unwrap().status, JobStatus::Running);
+        proceed.send(()).unwrap();
+
+        assert_eq!(worker.join().unwrap(), JobStatus::Cancelled);
+        let cancelled = queue.get(&job.id).unwrap();
+        assert_eq!(cancelled.completed, 1);
+        assert!(cancelled.finished_at.is_some());
+    }
+
+    #[test]
+    fn test_cancel_during_last_item_completes_job() {
+        let queue = JobQueue::new(10, DEFAULT_JOB_QUEUE_BYTES);
+        let job = queue.submit(images(1), OcrSettings::default()).unwrap();
+        let (started, proceed, worker) = spawn_gated_worker(&queue);
+
+        started.recv().unwrap();
+        queue.cancel(&job.id).unwrap();
+        proceed.send(()).unwrap();
+
+        assert_eq!(worker.join().unwrap(), JobStatus::Completed);
+        assert_eq!(queue.get(&job.id).unwrap().completed, 1);
+    }
+}
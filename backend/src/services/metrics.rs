@@ -0,0 +1,258 @@
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use prometheus::core::Collector;
+use prometheus::{
+    exponential_buckets, Encoder, GaugeVec, Histogram, HistogramOpts, IntCounter, IntCounterVec,
+    IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+
+/// Prefix of every exported metric
+const NAMESPACE: &str = "screen_reader";
+
+/// Registry holding all application metrics
+static REGISTRY: Lazy<Registry> = Lazy::new(Registry::new);
+
+/// Time to obtain a display frame from the capture stream
+pub static CAPTURE_DURATION: Lazy<Histogram> = Lazy::new(|| {
+    histogram(
+        "capture_duration_seconds",
+        "Time to obtain a display frame from the capture stream",
+        exponential_buckets(0.0005, 2.0, 14),
+    )
+});
+
+/// Time to encode a region image as PNG
+pub static PNG_ENCODE_DURATION: Lazy<Histogram> = Lazy::new(|| {
+    histogram(
+        "png_encode_duration_seconds",
+        "Time to encode a region image as PNG",
+        exponential_buckets(0.0005, 2.0, 14),
+    )
+});
+
+/// Time the OCR engine spends recognizing one image
+pub static OCR_DURATION: Lazy<Histogram> = Lazy::new(|| {
+    histogram(
+        "ocr_duration_seconds",
+        "Time the OCR engine spends recognizing one image",
+        exponential_buckets(0.005, 2.0, 13),
+    )
+});
+
+/// Duration of a full monitor cycle over all due regions
+pub static MONITOR_CYCLE_DURATION: Lazy<Histogram> = Lazy::new(|| {
+    histogram(
+        "monitor_cycle_duration_seconds",
+        "Duration of a full monitor cycle over all due regions",
+        exponential_buckets(0.005, 2.0, 13),
+    )
+});
+
+/// Region captures skipped because the image did not change
+pub static FRAMES_UNCHANGED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register(
+        IntCounterVec::new(
+            opts(
+                "frames_unchanged_total",
+                "Region captures skipped by change detection",
+            ),
+            &["region"],
+        )
+        .expect("Invalid frames_unchanged_total metric"),
+    )
+});
+
+/// Errors by the component they occurred in
+pub static ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register(
+        IntCounterVec::new(
+            opts(
+                "errors_total",
+                "Errors by the kind of operation that failed",
+            ),
+            &["kind"],
+        )
+        .expect("Invalid errors_total metric"),
+    )
+});
+
+/// Restarts of the supervised monitor thread
+pub static MONITOR_RESTARTS: Lazy<IntCounter> = Lazy::new(|| {
+    register(
+        IntCounter::with_opts(opts(
+            "monitor_restarts_total",
+            "Restarts of the monitor thread after a failure or re-initialization",
+        ))
+        .expect("Invalid monitor_restarts_total metric"),
+    )
+});
+
+/// Jobs waiting for a worker
+pub static JOBS_QUEUED: Lazy<IntGauge> = Lazy::new(|| {
+    register(
+        IntGauge::with_opts(opts("jobs_queued", "OCR jobs waiting for a worker"))
+            .expect("Invalid jobs_queued metric"),
+    )
+});
+
+/// Jobs being processed by a worker
+pub static JOBS_RUNNING: Lazy<IntGauge> = Lazy::new(|| {
+    register(
+        IntGauge::with_opts(opts("jobs_running", "OCR jobs being processed"))
+            .expect("Invalid jobs_running metric"),
+    )
+});
+
+/// Regions configured for monitoring
+pub static ACTIVE_REGIONS: Lazy<IntGauge> = Lazy::new(|| {
+    register(
+        IntGauge::with_opts(opts("active_regions", "Regions configured for monitoring"))
+            .expect("Invalid active_regions metric"),
+    )
+});
+
+/// Whether the monitor is running (1) or paused (0)
+pub static MONITORING: Lazy<IntGauge> = Lazy::new(|| {
+    register(
+        IntGauge::with_opts(opts("monitoring", "Whether the monitor is running"))
+            .expect("Invalid monitoring metric"),
+    )
+});
+
+/// Target and measured capture interval of every region
+pub static CAPTURE_INTERVAL: Lazy<GaugeVec> = Lazy::new(|| {
+    register(
+        GaugeVec::new(
+            opts(
+                "capture_interval_seconds",
+                "Capture interval of a region, as scheduled (target) and observed (measured)",
+            ),
+            &["region", "kind"],
+        )
+        .expect("Invalid capture_interval_seconds metric"),
+    )
+});
+
+/// Latest value read from each numeric region
+pub static REGION_VALUE: Lazy<GaugeVec> = Lazy::new(|| {
+    register(
+        GaugeVec::new(
+            opts("region_value", "Latest number read from a numeric region"),
+            &["region"],
+        )
+        .expect("Invalid region_value metric"),
+    )
+});
+
+/// Whether the value of a numeric region is stale
+pub static REGION_VALUE_STALE: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register(
+        IntGaugeVec::new(
+            opts(
+                "region_value_stale",
+                "Whether no capture confirmed the value of a numeric region recently",
+            ),
+            &["region"],
+        )
+        .expect("Invalid region_value_stale metric"),
+    )
+});
+
+/// When the value of a numeric region was last confirmed
+pub static REGION_VALUE_TIMESTAMP: Lazy<GaugeVec> = Lazy::new(|| {
+    register(
+        GaugeVec::new(
+            opts(
+                "region_value_timestamp_seconds",
+                "Unix time a capture last confirmed the value of a numeric region",
+            ),
+            &["region"],
+        )
+        .expect("Invalid region_value_timestamp_seconds metric"),
+    )
+});
+
+/// Number of times the value of a numeric region changed
+pub static REGION_VALUE_CHANGES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register(
+        IntCounterVec::new(
+            opts(
+                "region_value_changes_total",
+                "Number of times the value of a numeric region changed",
+            ),
+            &["region"],
+        )
+        .expect("Invalid region_value_changes_total metric"),
+    )
+});
+
+/// Register every metric so it is exported before its first observation
+pub fn init() {
+    Lazy::force(&CAPTURE_DURATION);
+    Lazy::force(&PNG_ENCODE_DURATION);
+    Lazy::force(&OCR_DURATION);
+    Lazy::force(&MONITOR_CYCLE_DURATION);
+    Lazy::force(&FRAMES_UNCHANGED);
+    Lazy::force(&ERRORS);
+    Lazy::force(&MONITOR_RESTARTS);
+    Lazy::force(&JOBS_QUEUED);
+    Lazy::force(&JOBS_RUNNING);
+    Lazy::force(&ACTIVE_REGIONS);
+    Lazy::force(&MONITORING);
+    Lazy::force(&CAPTURE_INTERVAL);
+    Lazy::force(&REGION_VALUE);
+    Lazy::force(&REGION_VALUE_STALE);
+    Lazy::force(&REGION_VALUE_TIMESTAMP);
+    Lazy::force(&REGION_VALUE_CHANGES);
+}
+
+/// Count an error of the given kind
+pub fn record_error(kind: &str) {
+    ERRORS.with_label_values(&[kind]).inc();
+}
+
+/// Render all metrics in the Prometheus text exposition format
+pub fn render() -> Result<String> {
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(&REGISTRY.gather(), &mut buffer)
+        .context("Failed to encode metrics")?;
+
+    String::from_utf8(buffer).context("Metrics are not valid UTF-8")
+}
+
+/// Build the options of a metric in the application namespace
+fn opts(name: &str, help: &str) -> Opts {
+    Opts::new(name, help).namespace(NAMESPACE)
+}
+
+/// Create and register a histogram
+fn histogram(name: &str, help: &str, buckets: prometheus::Result<Vec<f64>>) -> Histogram {
+    let opts =
+        HistogramOpts::from(opts(name, help)).buckets(buckets.expect("Invalid histogram buckets"));
+
+    register(Histogram::with_opts(opts).expect("Invalid histogram metric"))
+}
+
+/// Register a metric with the application registry
+fn register<M: Collector + Clone + 'static>(metric: M) -> M {
+    REGISTRY
+        .register(Box::new(metric.clone()))
+        .expect("Metric registered twice");
+    metric
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_includes_namespace() {
+        record_error("test");
+        CAPTURE_DURATION.observe(0.01);
+
+        let text = render().unwrap();
+        assert!(text.contains("screen_reader_errors_total{kind=\"test\"} 1"));
+        assert!(text.contains("screen_reader_capture_duration_seconds_bucket"));
+    }
+}
@@ -1,20 +1,15 @@
-pub mod ocr;
-pub mod screen_capture;
-
-pub use ocr::OcrService;
-pub use screen_capture::ScreenCaptureService;
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct DisplayInfo {
-    /// Screen identifier
-    pub id: u32,
-
-    /// Display name
-    pub name: String,
-
-    /// Screen width in pixels
-    pub width: u32,
-
-    /// Screen height in pixels
-    pub height: u32,
-}
+pub mod diagnostics;
+pub mod image_output;
+pub mod jobs;
+pub mod metrics;
+pub mod ocr_pool;
+pub mod recorder;
+pub mod supervisor;
+
+// Capture, OCR and the monitor pipeline live in the library crate
+#[cfg(feature = "tesseract")]
+pub use screen_text_core::ocr_worker;
+pub use screen_text_core::{
+    capture_session, engine, geometry, monitor, numeric, ocr, screen_capture,
+};
+pub use screen_text_core::{OcrService, ScreenCaptureService};
@@ -0,0 +1,137 @@
+//! Reuse of OCR engines across requests
+//!
+//! Initializing an engine loads its language models, which takes far
+//! longer than reading a small image. Idle services are kept per settings
+//! and handed to the next request with the same settings.
+
+use anyhow::Result;
+use log::debug;
+use std::sync::{Mutex, MutexGuard};
+
+use super::ocr::OcrService;
+use crate::models::OcrSettings;
+
+/// Number of idle services kept unless configured otherwise
+pub const DEFAULT_MAX_IDLE_ENGINES: usize = 4;
+
+/// Idle OCR services, reused while their settings match
+pub struct OcrPool {
+    /// Idle services with their settings, least recently used first
+    idle: Mutex<Vec<(OcrSettings, OcrService)>>,
+
+    /// Maximum number of idle services
+    max_idle: usize,
+}
+
+impl OcrPool {
+    /// Create an empty pool keeping up to `max_idle` idle services
+    pub fn new(max_idle: usize) -> Self {
+        Self {
+            idle: Mutex::new(Vec::new()),
+            max_idle,
+        }
+    }
+
+    /// Run OCR with a service for the given settings
+    ///
+    /// An idle service is reused if one matches, otherwise a new one is
+    /// created. The service goes back to the pool unless OCR failed, in
+    /// which case the engine may be in a bad state and is dropped.
+    pub fn run<T>(
+        &self,
+        settings: &OcrSettings,
+        ocr: impl FnOnce(&mut OcrService) -> Result<T>,
+    ) -> Result<T> {
+        let idle = self.take(settings);
+        let mut service = match idle {
+            Some(service) => service,
+            None => {
+                debug!("Creating OCR engine for language '{}'", settings.language);
+                OcrService::with_settings(settings)?
+            }
+        };
+
+        let result = ocr(&mut service)?;
+        self.put(settings.clone(), service);
+
+        Ok(result)
+    }
+
+    /// Take the most recently used idle service with the given settings
+    fn take(&self, settings: &OcrSettings) -> Option<OcrService> {
+        let mut idle = self.lock_idle();
+        let index = idle.iter().rposition(|(current, _)| current == settings)?;
+        Some(idle.remove(index).1)
+    }
+
+    /// Return a service, dropping the least recently used one when full
+    fn put(&self, settings: OcrSettings, service: OcrService) {
+        let mut idle = self.lock_idle();
+        if self.max_idle == 0 {
+            return;
+        }
+        if idle.len() == self.max_idle {
+            idle.remove(0);
+        }
+        idle.push((settings, service));
+    }
+
+    fn lock_idle(&self) -> MutexGuard<'_, Vec<(OcrSettings, OcrService)>> {
+        self.idle
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::engine::FakeEngine;
+
+    fn settings(language: &str) -> OcrSettings {
+        OcrSettings {
+            language: language.to_string(),
+            ..OcrSettings::default()
+        }
+    }
+
+    #[test]
+    fn test_reuses_matching_services() {
+        let pool = OcrPool::new(1);
+        pool.put(
+            settings("eng"),
+            OcrService::with_engine(Box::new(FakeEngine::with_text("first"))),
+        );
+
+        let text = pool
+            .run(&settings("eng"), |service| {
+                service.extract_text_from_image(&image::RgbaImage::new(4, 4))
+            })
+            .unwrap()
+            .text;
+        assert_eq!(text, "first");
+        assert_eq!(pool.lock_idle().len(), 1);
+
+        // A failed read drops the service
+        assert!(pool
+            .run(&settings("eng"), |_| Err::<(), _>(anyhow::anyhow!("boom")))
+            .is_err());
+        assert_eq!(pool.lock_idle().len(), 0);
+    }
+
+    #[test]
+    fn test_drops_least_recently_used() {
+        let pool = OcrPool::new(1);
+        pool.put(
+            settings("eng"),
+            OcrService::with_engine(Box::new(FakeEngine::with_text("eng"))),
+        );
+        pool.put(
+            settings("deu"),
+            OcrService::with_engine(Box::new(FakeEngine::with_text("deu"))),
+        );
+
+        assert!(pool.take(&settings("eng")).is_none());
+        assert!(pool.take(&settings("deu")).is_some());
+    }
+}
@@ -0,0 +1,687 @@
+//! Recording of monitor captures into session archives
+//!
+//! While a recording is active, the monitor hands every captured region
+//! frame to the recorder, which appends the PNG image and its metadata to
+//! a tar archive. Every session is stored as `<id>.tar` next to a
+//! `<id>.json` summary in the recordings directory, so misreads can be
+//! replayed frame by frame later on.
+//!
+//! Archive layout:
+//! - `frames/<sequence>-<region>.png`: the region image that was captured
+//! - `frames/<sequence>-<region>.json`: a `RecordedFrame` describing it
+//! - `session.json`: the final `RecordingSession`, written when it stops
+//!
+//! Only the newest sessions are kept. Starting a recording deletes the
+//! oldest finished ones beyond the configured number.
+
+use chrono::{DateTime, Utc};
+use log::{error, info, warn};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use thiserror::Error;
+
+use super::diagnostics;
+use super::monitor::{FrameOutcome, RegionFrame};
+use crate::models::{
+    MonitoredRegion, RecordedFrame, RecordingMode, RecordingSession, StartRecordingRequest,
+};
+
+/// Directory sessions are stored in unless configured otherwise
+pub const DEFAULT_RECORDINGS_DIR: &str = "./recordings";
+
+/// Largest archive size unless configured otherwise, 1 GiB
+pub const DEFAULT_MAX_RECORDING_BYTES: u64 = 1 << 30;
+
+/// Number of sessions kept on disk unless configured otherwise
+pub const DEFAULT_MAX_RECORDING_SESSIONS: usize = 20;
+
+/// Number of frames between updates of an active session's summary file
+const SUMMARY_SYNC_FRAMES: u64 = 100;
+
+/// Size of a tar header and the unit entries are padded to
+const TAR_BLOCK: u64 = 512;
+
+/// Errors reported by the recorder
+#[derive(Debug, Error)]
+pub enum RecordingError {
+    /// A recording is already running
+    #[error("Recording '{0}' is already running")]
+    AlreadyRecording(String),
+
+    /// No recording is running
+    #[error("No recording is running")]
+    NotRecording,
+
+    /// No session with the given id exists
+    #[error("Recording '{0}' not found")]
+    NotFound(String),
+
+    /// The session is still being recorded
+    #[error("Recording '{0}' is still running")]
+    Active(String),
+
+    /// Reading or writing the recordings directory failed
+    #[error("Recording storage failed: {0}")]
+    Storage(#[from] io::Error),
+}
+
+/// The session being recorded and its open archive
+struct ActiveRecording {
+    /// Summary of the session so far
+    session: RecordingSession,
+
+    /// Ids of the recorded regions, all regions when absent
+    region_filter: Option<HashSet<String>>,
+
+    /// Archive the frames are appended to
+    archive: tar::Builder<BufWriter<File>>,
+}
+
+/// Records monitor captures into session archives on disk
+pub struct SessionRecorder {
+    /// Directory holding the archives and their summaries
+    dir: PathBuf,
+
+    /// Size an archive may grow to before the recording stops
+    max_bytes: u64,
+
+    /// Number of sessions kept, including the running one
+    max_sessions: usize,
+
+    /// The running recording, if any
+    active: Mutex<Option<ActiveRecording>>,
+}
+
+impl SessionRecorder {
+    /// Create a recorder storing up to `max_sessions` sessions in `dir`
+    pub fn new(dir: impl Into<PathBuf>, max_bytes: u64, max_sessions: usize) -> Self {
+        Self {
+            dir: dir.into(),
+            max_bytes,
+            max_sessions,
+            active: Mutex::new(None),
+        }
+    }
+
+    /// Start a new recording of the given regions
+    pub fn start(
+        &self,
+        request: StartRecordingRequest,
+        regions: Vec<MonitoredRegion>,
+    ) -> Result<RecordingSession, RecordingError> {
+        let mut active = self.lock_active();
+        if let Some(recording) = active.as_ref() {
+            return Err(RecordingError::AlreadyRecording(
+                recording.session.id.clone(),
+            ));
+        }
+
+        fs::create_dir_all(&self.dir)?;
+
+        let started_at = Utc::now();
+        let id = format!("rec-{}", started_at.format("%Y%m%d-%H%M%S-%3f"));
+        let file = File::options()
+            .write(true)
+            .create_new(true)
+            .open(self.archive_path(&id))?;
+
+        let session = RecordingSession {
+            id,
+            label: request.label,
+            mode: request.mode,
+            region_filter: request.regions.clone(),
+            regions,
+            started_at,
+            stopped_at: None,
+            stop_reason: None,
+            frame_count: 0,
+            size_bytes: 0,
+            active: true,
+        };
+        self.write_summary(&session)?;
+
+        info!(
+            "Started recording '{}' ({:?} frames) to {}",
+            session.id,
+            session.mode,
+            self.dir.display()
+        );
+
+        *active = Some(ActiveRecording {
+            session: session.clone(),
+            region_filter: request.regions.map(|ids| ids.into_iter().collect()),
+            archive: tar::Builder::new(BufWriter::new(file)),
+        });
+        drop(active);
+
+        if let Err(e) = self.prune() {
+            warn!("Failed to delete old recordings: {}", e);
+        }
+
+        Ok(session)
+    }
+
+    /// Stop the running recording and finish its archive
+    pub fn stop(&self) -> Result<RecordingSession, RecordingError> {
+        let recording = self
+            .lock_active()
+            .take()
+            .ok_or(RecordingError::NotRecording)?;
+
+        self.finish(recording, None)
+    }
+
+    /// Summary of the running recording, if any
+    pub fn current(&self) -> Option<RecordingSession> {
+        self.lock_active()
+            .as_ref()
+            .map(|recording| recording.session.clone())
+    }
+
+    /// Append a captured region frame to the running recording
+    ///
+    /// Frames the recording doesn't keep are ignored. A failure to write
+    /// stops the recording, since its archive can't be trusted anymore.
+    pub fn record(&self, frame: &RegionFrame<'_>) {
+        let region = frame.region;
+        let mut active = self.lock_active();
+        let Some(recording) = active.as_mut() else {
+            return;
+        };
+
+        let changed = !matches!(frame.outcome, FrameOutcome::Unchanged);
+        let wanted = recording
+            .region_filter
+            .as_ref()
+            .is_none_or(|ids| ids.contains(&region.id))
+            && (changed || recording.session.mode == RecordingMode::All);
+        if !wanted {
+            return;
+        }
+
+        let sequence = recording.session.frame_count + 1;
+        let name = format!("frames/{:06}-{}", sequence, file_name_part(&region.id));
+        let (raw_result, result, error) = match &frame.outcome {
+            FrameOutcome::Unchanged => (None, None, None),
+            FrameOutcome::Read { raw, result } => {
+                (Some((*raw).clone()), Some((*result).clone()), None)
+            }
+            FrameOutcome::Failed(error) => (None, None, Some(error.clone())),
+        };
+        let timestamp = frame.timestamp;
+        let png_data = frame.png_data;
+        let frame = RecordedFrame {
+            sequence,
+            region_id: region.id.clone(),
+            region: region.region.clone(),
+            crop: frame.mapping.physical.clone(),
+            scale_factor: frame.mapping.scale_factor,
+            timestamp,
+            changed,
+            image: format!("{}.png", name),
+            raw_result,
+            result,
+            error,
+        };
+
+        let metadata = match serde_json::to_vec_pretty(&frame) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                error!("Failed to serialize recorded frame: {}", e);
+                return;
+            }
+        };
+
+        let size = entry_size(png_data.len()) + entry_size(metadata.len());
+        if recording.session.size_bytes + size > self.max_bytes {
+            let reason = format!("Size limit of {} bytes reached", self.max_bytes);
+            warn!("Stopping recording '{}': {}", recording.session.id, reason);
+            if let Some(recording) = active.take() {
+                let _ = self.finish(recording, Some(reason));
+            }
+            return;
+        }
+
+        let written =
+            append(&mut recording.archive, &frame.image, png_data, timestamp).and_then(|_| {
+                append(
+                    &mut recording.archive,
+                    &format!("{}.json", name),
+                    &metadata,
+                    timestamp,
+                )
+            });
+        if let Err(e) = written {
+            error!(
+                "Failed to write frame to recording '{}': {}",
+                recording.session.id, e
+            );
+            diagnostics::report_error(
+                "recording",
+                format!("Recording '{}': {}", recording.session.id, e),
+            );
+            if let Some(recording) = active.take() {
+                let _ = self.finish(recording, Some(format!("Failed to write frame: {}", e)));
+            }
+            return;
+        }
+
+        recording.session.frame_count = sequence;
+        recording.session.size_bytes += size;
+
+        // Keep the summary close to the archive in case the server dies
+        if sequence % SUMMARY_SYNC_FRAMES == 0 {
+            if let Err(e) = self.write_summary(&recording.session) {
+                warn!(
+                    "Failed to update summary of recording '{}': {}",
+                    recording.session.id, e
+                );
+            }
+        }
+    }
+
+    /// List all sessions, oldest first
+    pub fn list(&self) -> Result<Vec<RecordingSession>, RecordingError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut sessions = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            let Some(id) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".json"))
+                .filter(|id| is_valid_id(id))
+            else {
+                continue;
+            };
+
+            match self.get(id) {
+                Ok(session) => sessions.push(session),
+                Err(e) => warn!("Skipping recording '{}': {}", id, e),
+            }
+        }
+
+        sessions.sort_by_key(|session| session.started_at);
+        Ok(sessions)
+    }
+
+    /// Get the summary of a session
+    pub fn get(&self, id: &str) -> Result<RecordingSession, RecordingError> {
+        if let Some(session) = self.current().filter(|session| session.id == id) {
+            return Ok(session);
+        }
+        if !is_valid_id(id) {
+            return Err(RecordingError::NotFound(id.to_string()));
+        }
+
+        let summary = match fs::read(self.summary_path(id)) {
+            Ok(summary) => summary,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(RecordingError::NotFound(id.to_string()))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let mut session: RecordingSession =
+            serde_json::from_slice(&summary).map_err(io::Error::from)?;
+
+        // A session left behind by a server that died is not running anymore
+        session.active = false;
+        if let Ok(metadata) = fs::metadata(self.archive_path(id)) {
+            session.size_bytes = metadata.len();
+        }
+
+        Ok(session)
+    }
+
+    /// Get the path of a finished session's archive
+    pub fn archive(&self, id: &str) -> Result<PathBuf, RecordingError> {
+        let session = self.get(id)?;
+        if session.active {
+            return Err(RecordingError::Active(session.id));
+        }
+
+        let path = self.archive_path(id);
+        if !path.exists() {
+            return Err(RecordingError::NotFound(id.to_string()));
+        }
+        Ok(path)
+    }
+
+    /// Delete a finished session and its archive
+    pub fn delete(&self, id: &str) -> Result<(), RecordingError> {
+        let session = self.get(id)?;
+        if session.active {
+            return Err(RecordingError::Active(session.id));
+        }
+
+        remove_if_exists(&self.archive_path(id))?;
+        remove_if_exists(&self.summary_path(id))?;
+
+        info!("Deleted recording '{}'", id);
+        Ok(())
+    }
+
+    /// Delete the oldest finished sessions beyond the number kept
+    fn prune(&self) -> Result<(), RecordingError> {
+        let sessions = self.list()?;
+        let excess = sessions.len().saturating_sub(self.max_sessions);
+
+        for session in sessions
+            .into_iter()
+            .filter(|session| !session.active)
+            .take(excess)
+        {
+            self.delete(&session.id)?;
+        }
+        Ok(())
+    }
+
+    /// Close the archive of a recording and store its final summary
+    fn finish(
+        &self,
+        mut recording: ActiveRecording,
+        stop_reason: Option<String>,
+    ) -> Result<RecordingSession, RecordingError> {
+        let session = &mut recording.session;
+        session.stopped_at = Some(Utc::now());
+        session.stop_reason = stop_reason;
+        session.active = false;
+
+        let summary = serde_json::to_vec_pretty(&*session).map_err(io::Error::from)?;
+        let finished = append(&mut recording.archive, "session.json", &summary, Utc::now())
+            .and_then(|_| recording.archive.into_inner())
+            .and_then(|writer| writer.into_inner().map_err(|e| e.into_error()))
+            .and_then(|file| file.sync_all());
+
+        let mut session = recording.session;
+        if let Err(e) = finished {
+            error!("Failed to finish recording '{}': {}", session.id, e);
+            diagnostics::report_error("recording", format!("Recording '{}': {}", session.id, e));
+            session
+                .stop_reason
+                .get_or_insert_with(|| format!("Failed to finish archive: {}", e));
+        }
+        if let Ok(metadata) = fs::metadata(self.archive_path(&session.id)) {
+            session.size_bytes = metadata.len();
+        }
+        self.write_summary(&session)?;
+
+        info!(
+            "Stopped recording '{}' with {} frame(s), {} bytes",
+            session.id, session.frame_count, session.size_bytes
+        );
+        Ok(session)
+    }
+
+    /// Write the summary file of a session
+    fn write_summary(&self, session: &RecordingSession) -> io::Result<()> {
+        let summary = serde_json::to_vec_pretty(session)?;
+        fs::write(self.summary_path(&session.id), summary)
+    }
+
+    fn archive_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.tar", id))
+    }
+
+    fn summary_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    fn lock_active(&self) -> MutexGuard<'_, Option<ActiveRecording>> {
+        self.active
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Append a file to an archive
+fn append(
+    archive: &mut tar::Builder<BufWriter<File>>,
+    path: &str,
+    data: &[u8],
+    timestamp: DateTime<Utc>,
+) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(timestamp.timestamp().max(0) as u64);
+    archive.append_data(&mut header, path, data)
+}
+
+/// Space an entry of `len` bytes takes up in a tar archive
+fn entry_size(len: usize) -> u64 {
+    TAR_BLOCK + (len as u64).div_ceil(TAR_BLOCK) * TAR_BLOCK
+}
+
+/// Make a region id safe to use in an archive file name
+fn file_name_part(id: &str) -> String {
+    id.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Check that a session id can't point outside the recordings directory
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::OcrResult;
+    use crate::models::Region;
+    use crate::services::geometry::{self, RegionMapping};
+    use once_cell::sync::Lazy;
+    use std::io::Read;
+    use std::time::Duration;
+
+    fn region(id: &str) -> MonitoredRegion {
+        MonitoredRegion::new(id, Region::new(0, 0, 10, 10))
+    }
+
+    fn frame<'a>(
+        region: &'a MonitoredRegion,
+        timestamp: DateTime<Utc>,
+        outcome: FrameOutcome<'a>,
+    ) -> RegionFrame<'a> {
+        static MAPPING: Lazy<RegionMapping> =
+            Lazy::new(|| geometry::map_region(&Region::new(0, 0, 10, 10), 100, 100).unwrap());
+
+        RegionFrame {
+            region,
+            mapping: &MAPPING,
+            timestamp,
+            png_data: b"png",
+            encode_time: Duration::ZERO,
+            outcome,
+        }
+    }
+
+    #[test]
+    fn test_records_changed_frames_into_archive() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let recorder = SessionRecorder::new(
+            dir.path(),
+            DEFAULT_MAX_RECORDING_BYTES,
+            DEFAULT_MAX_RECORDING_SESSIONS,
+        );
+
+        let session = recorder
+            .start(StartRecordingRequest::default(), vec![region("a")])
+            .unwrap();
+        assert!(matches!(
+            recorder.start(StartRecordingRequest::default(), Vec::new()),
+            Err(RecordingError::AlreadyRecording(_))
+        ));
+        assert!(matches!(
+            recorder.archive(&session.id),
+            Err(RecordingError::Active(_))
+        ));
+
+        let now = Utc::now();
+        recorder.record(&frame(
+            &region("a"),
+            now,
+            FrameOutcome::Read {
+                raw: &OcrResult::new("4 2".to_string()),
+                result: &OcrResult::new("42".to_string()),
+            },
+        ));
+        recorder.record(&frame(&region("a"), now, FrameOutcome::Unchanged));
+        recorder.record(&frame(
+            &region("a/b"),
+            now,
+            FrameOutcome::Failed("boom".into()),
+        ));
+
+        let stopped = recorder.stop().unwrap();
+        assert_eq!(stopped.frame_count, 2);
+        assert!(!stopped.active);
+        assert!(matches!(recorder.stop(), Err(RecordingError::NotRecording)));
+
+        let mut archive =
+            tar::Archive::new(File::open(recorder.archive(&session.id).unwrap()).unwrap());
+        let mut names = Vec::new();
+        let mut first_frame = None;
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let name = entry.path().unwrap().to_string_lossy().into_owned();
+            if name == "frames/000001-a.json" {
+                let mut json = String::new();
+                entry.read_to_string(&mut json).unwrap();
+                first_frame = Some(serde_json::from_str::<RecordedFrame>(&json).unwrap());
+            }
+            names.push(name);
+        }
+        assert_eq!(
+            names,
+            [
+                "frames/000001-a.png",
+                "frames/000001-a.json",
+                "frames/000002-a_b.png",
+                "frames/000002-a_b.json",
+                "session.json",
+            ]
+        );
+        let first_frame = first_frame.unwrap();
+        assert_eq!(first_frame.raw_result.unwrap().text, "4 2");
+        assert_eq!(first_frame.result.unwrap().text, "42");
+        let crop = first_frame.crop;
+        assert_eq!((crop.x, crop.y, crop.width, crop.height), (0, 0, 10, 10));
+
+        let listed = recorder.list().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].frame_count, 2);
+
+        recorder.delete(&session.id).unwrap();
+        assert!(recorder.list().unwrap().is_empty());
+        assert!(matches!(
+            recorder.get(&session.id),
+            Err(RecordingError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_filters_regions_and_stops_at_size_limit() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let recorder =
+            SessionRecorder::new(dir.path(), 7 * TAR_BLOCK, DEFAULT_MAX_RECORDING_SESSIONS);
+
+        recorder
+            .start(
+                StartRecordingRequest {
+                    mode: RecordingMode::All,
+                    regions: Some(vec!["a".to_string()]),
+                    label: None,
+                },
+                Vec::new(),
+            )
+            .unwrap();
+
+        let now = Utc::now();
+        recorder.record(&frame(&region("b"), now, FrameOutcome::Unchanged));
+        recorder.record(&frame(&region("a"), now, FrameOutcome::Unchanged));
+        assert_eq!(recorder.current().unwrap().frame_count, 1);
+
+        // The second frame would exceed the limit
+        recorder.record(&frame(&region("a"), now, FrameOutcome::Unchanged));
+        assert!(recorder.current().is_none());
+
+        let sessions = recorder.list().unwrap();
+        assert_eq!(sessions[0].frame_count, 1);
+        assert!(sessions[0].stop_reason.is_some());
+    }
+
+    #[test]
+    fn test_keeps_newest_sessions() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let recorder = SessionRecorder::new(dir.path(), DEFAULT_MAX_RECORDING_BYTES, 2);
+
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            ids.push(
+                recorder
+                    .start(StartRecordingRequest::default(), Vec::new())
+                    .unwrap()
+                    .id,
+            );
+            recorder.stop().unwrap();
+            // Session ids have millisecond resolution
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+
+        let kept: Vec<_> = recorder
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|session| session.id)
+            .collect();
+        assert_eq!(kept, ids[1..]);
+        assert!(!dir.path().join(format!("{}.tar", ids[0])).exists());
+
+        // The running session counts towards the limit but is never deleted
+        let running = SessionRecorder::new(dir.path(), DEFAULT_MAX_RECORDING_BYTES, 0);
+        let session = running
+            .start(StartRecordingRequest::default(), Vec::new())
+            .unwrap();
+        let listed = running.list().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, session.id);
+    }
+
+    #[test]
+    fn test_rejects_ids_outside_directory() {
+        let recorder = SessionRecorder::new(
+            "/nonexistent",
+            DEFAULT_MAX_RECORDING_BYTES,
+            DEFAULT_MAX_RECORDING_SESSIONS,
+        );
+
+        assert!(matches!(
+            recorder.get("../etc/passwd"),
+            Err(RecordingError::NotFound(_))
+        ));
+        assert!(recorder.list().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,219 @@
+use chrono::Utc;
+use log::warn;
+use std::any::Any;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::models::{MonitorFailure, MonitorState, MonitorSupervision};
+
+/// Delay before the first restart after a failure
+pub const INITIAL_RESTART_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest delay between restarts
+pub const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+
+/// How long the monitor must run before a failure restarts with the initial delay
+pub const STABLE_RUN: Duration = Duration::from_secs(60);
+
+/// Exponential backoff between monitor restarts
+#[derive(Debug, Clone)]
+pub struct RestartBackoff {
+    /// Delay of the next restart
+    next: Duration,
+}
+
+impl RestartBackoff {
+    pub fn new() -> Self {
+        Self {
+            next: INITIAL_RESTART_DELAY,
+        }
+    }
+
+    /// Delay before restarting a monitor that ran for `uptime`
+    ///
+    /// The delay doubles with every failure in a row, up to
+    /// `MAX_RESTART_DELAY`, and starts over once a run lasted `STABLE_RUN`.
+    pub fn next_delay(&mut self, uptime: Duration) -> Duration {
+        if uptime >= STABLE_RUN {
+            self.reset();
+        }
+
+        let delay = self.next;
+        self.next = (self.next * 2).min(MAX_RESTART_DELAY);
+        delay
+    }
+
+    /// Restart with the initial delay next time
+    pub fn reset(&mut self) {
+        self.next = INITIAL_RESTART_DELAY;
+    }
+}
+
+impl Default for RestartBackoff {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Shared status of the supervised monitor thread
+///
+/// The supervisor records the lifecycle here, API handlers read it and
+/// can ask the monitor to re-initialize.
+pub struct MonitorSupervisor {
+    /// Status reported by the API
+    status: Mutex<MonitorSupervision>,
+
+    /// A re-initialization was requested and not yet handled
+    reinit_requested: Mutex<bool>,
+
+    /// Wakes the supervisor while it waits to restart
+    wakeup: Condvar,
+}
+
+impl MonitorSupervisor {
+    pub fn new() -> Self {
+        Self {
+            status: Mutex::new(MonitorSupervision {
+                state: MonitorState::Starting,
+                restarts: 0,
+                failures: 0,
+                last_failure: None,
+                started_at: None,
+                next_restart_at: None,
+            }),
+            reinit_requested: Mutex::new(false),
+            wakeup: Condvar::new(),
+        }
+    }
+
+    /// Current supervision status
+    pub fn status(&self) -> MonitorSupervision {
+        self.lock_status().clone()
+    }
+
+    /// Note that the monitor is (re)starting
+    pub fn record_start(&self, restart: bool) {
+        let mut status = self.lock_status();
+        status.state = MonitorState::Starting;
+        status.started_at = Some(Utc::now());
+        status.next_restart_at = None;
+        if restart {
+            status.restarts += 1;
+        }
+    }
+
+    /// Note that the monitor finished initializing
+    pub fn record_running(&self) {
+        self.lock_status().state = MonitorState::Running;
+    }
+
+    /// Note that the monitor failed and will restart after `delay`
+    pub fn record_failure(&self, message: String, panicked: bool, delay: Duration) {
+        let now = Utc::now();
+        let mut status = self.lock_status();
+        status.state = MonitorState::Restarting;
+        status.failures += 1;
+        status.last_failure = Some(MonitorFailure {
+            message,
+            panicked,
+            timestamp: now,
+        });
+        status.next_restart_at =
+            Some(now + chrono::Duration::from_std(delay).unwrap_or(chrono::Duration::zero()));
+    }
+
+    /// Note that the monitor exited for good
+    pub fn record_stopped(&self) {
+        let mut status = self.lock_status();
+        status.state = MonitorState::Stopped;
+        status.next_restart_at = None;
+    }
+
+    /// Ask the monitor to drop its OCR engine and initialize again
+    pub fn request_reinit(&self) {
+        *self.lock_reinit() = true;
+        self.wakeup.notify_all();
+    }
+
+    /// Check for and clear a pending re-initialization request
+    pub fn take_reinit_request(&self) -> bool {
+        std::mem::take(&mut *self.lock_reinit())
+    }
+
+    /// Wait before restarting, returning early when re-initialization is requested
+    ///
+    /// Returns true when the wait was cut short by a request.
+    pub fn wait_for_restart(&self, delay: Duration) -> bool {
+        let requested = self.lock_reinit();
+        let (mut requested, _) = self
+            .wakeup
+            .wait_timeout_while(requested, delay, |requested| !*requested)
+            .unwrap_or_else(|e| e.into_inner());
+        std::mem::take(&mut *requested)
+    }
+
+    fn lock_status(&self) -> MutexGuard<'_, MonitorSupervision> {
+        self.status.lock().unwrap_or_else(|e| {
+            warn!("Monitor supervision status was poisoned");
+            e.into_inner()
+        })
+    }
+
+    fn lock_reinit(&self) -> MutexGuard<'_, bool> {
+        self.reinit_requested
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for MonitorSupervisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Message of a caught panic
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Unknown panic".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let mut backoff = RestartBackoff::new();
+        let delays: Vec<u64> = (0..8)
+            .map(|_| backoff.next_delay(Duration::ZERO).as_secs())
+            .collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+    }
+
+    #[test]
+    fn test_backoff_resets_after_stable_run() {
+        let mut backoff = RestartBackoff::new();
+        backoff.next_delay(Duration::ZERO);
+        backoff.next_delay(Duration::ZERO);
+        assert_eq!(backoff.next_delay(STABLE_RUN), INITIAL_RESTART_DELAY);
+    }
+
+    #[test]
+    fn test_reinit_request_wakes_wait() {
+        let supervisor = MonitorSupervisor::new();
+        supervisor.request_reinit();
+        assert!(supervisor.wait_for_restart(Duration::from_secs(5)));
+        assert!(!supervisor.take_reinit_request());
+        assert!(!supervisor.wait_for_restart(Duration::from_millis(1)));
+    }
+
+    #[test]
+    fn test_panic_message() {
+        let payload = std::panic::catch_unwind(|| panic!("boom {}", 1)).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "boom 1");
+    }
+}
@@ -1,17 +1,35 @@
-use log::{debug, error, info, warn};
+use log::{error, info, warn};
+use std::collections::{HashMap, VecDeque};
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
-
-use crate::models::{OcrResult, Region};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use crate::models::{
+    CaptureRate, MonitoredRegion, OcrResult, RegionErrorKind, RegionHealth, TextChange,
+};
+use crate::services::jobs::JobQueue;
+use crate::services::monitor::{FrameOutcome, Monitor, MonitorSink, Reading, RegionFrame};
+use crate::services::numeric::NumericReading;
+use crate::services::ocr_pool::{OcrPool, DEFAULT_MAX_IDLE_ENGINES};
+use crate::services::recorder::SessionRecorder;
+use crate::services::supervisor::{self, MonitorSupervisor, RestartBackoff};
+use crate::services::{diagnostics, metrics};
 use crate::services::{OcrService, ScreenCaptureService};
 
+/// Number of text changes kept per region
+const MAX_HISTORY_PER_REGION: usize = 100;
+
+/// Number of text changes buffered for slow event stream subscribers
+const TEXT_EVENT_CAPACITY: usize = 256;
+
 /// Application state shared between API handlers and background tasks
 pub struct AppState {
-    /// Currently selected region (if any)
-    pub region: Mutex<Option<Region>>,
+    /// Regions watched by the monitor, in the order they were added
+    pub regions: Mutex<Vec<MonitoredRegion>>,
 
-    /// Most recent OCR result
-    pub ocr_result: Mutex<OcrResult>,
+    /// Most recent OCR result of each region, keyed by region id
+    pub ocr_results: Mutex<HashMap<String, OcrResult>>,
 
     /// Flag to control monitoring
     pub is_monitoring: Mutex<bool>,
@@ -19,16 +37,49 @@ pub struct AppState {
     /// Flag to indicate if OCR service is properly initialized
     pub ocr_ready: Mutex<bool>,
 
-    /// Latest screenshot data in PNG format for the UI to display
+    /// Latest screenshot of the primary region in PNG format for the UI to display
     pub latest_screenshot: Mutex<Option<Vec<u8>>>,
 
-    /// Last time a screenshot was captured
-    pub last_screenshot_time: Mutex<Instant>,
+    /// Rate each region is currently captured at, keyed by region id
+    pub capture_rates: Mutex<HashMap<String, CaptureRate>>,
+
+    /// Latest text changes of each region, oldest first, keyed by region id
+    pub text_history: Mutex<HashMap<String, VecDeque<TextChange>>>,
+
+    /// Publishes every text change to event stream subscribers
+    pub text_events: broadcast::Sender<TextChange>,
+
+    /// Capture and OCR outcomes of each region, keyed by region id
+    pub region_health: Mutex<HashMap<String, RegionHealth>>,
+
+    /// Last number read from each numeric region, keyed by region id
+    pub numeric_values: Mutex<HashMap<String, NumericReading>>,
+
+    /// Background capture/OCR jobs submitted through the API
+    pub jobs: JobQueue,
+
+    /// Records the monitor's captures into session archives
+    pub recorder: SessionRecorder,
+
+    /// OCR engines reused by requests reading uploaded images
+    pub ocr_pool: OcrPool,
+
+    /// Restarts, failures and re-initialization requests of the monitor thread
+    pub supervisor: MonitorSupervisor,
+}
+
+/// Why the monitor task returned without an error
+enum MonitorExit {
+    /// Screen capture is not supported, there is nothing to monitor
+    Unsupported,
+
+    /// Re-initialization was requested through the API
+    Reinit,
 }
 
 impl AppState {
-    /// Create a new application state
-    pub fn new() -> Self {
+    /// Create a new application state around the given job queue and recorder
+    pub fn new(jobs: JobQueue, recorder: SessionRecorder) -> Self {
         info!("Initializing application state");
 
         // Check if screen capture is supported
@@ -51,229 +102,461 @@ impl AppState {
         }
 
         Self {
-            region: Mutex::new(None),
-            ocr_result: Mutex::new(OcrResult::empty()),
+            regions: Mutex::new(Vec::new()),
+            ocr_results: Mutex::new(HashMap::new()),
             is_monitoring: Mutex::new(false),
             ocr_ready: Mutex::new(false), // Initially set to false until OCR is initialized
             latest_screenshot: Mutex::new(None),
-            last_screenshot_time: Mutex::new(Instant::now()),
+            capture_rates: Mutex::new(HashMap::new()),
+            text_history: Mutex::new(HashMap::new()),
+            text_events: broadcast::channel(TEXT_EVENT_CAPACITY).0,
+            region_health: Mutex::new(HashMap::new()),
+            numeric_values: Mutex::new(HashMap::new()),
+            jobs,
+            recorder,
+            ocr_pool: OcrPool::new(DEFAULT_MAX_IDLE_ENGINES),
+            supervisor: MonitorSupervisor::new(),
         }
     }
 
     /// Start the background OCR monitoring task
+    ///
+    /// The task is supervised: when it fails or panics it is restarted
+    /// with exponential backoff, and it can be asked to re-initialize.
     pub fn start_monitoring_task(state: Arc<Self>) {
         info!("Starting background OCR monitoring task");
 
         std::thread::Builder::new()
             .name("ocr-monitor".into())
-            .spawn(move || {
-                if let Err(e) = Self::monitor_task(state.clone()) {
-                    error!("OCR monitoring task failed: {}", e);
+            .spawn(move || Self::supervise_monitor(state))
+            .expect("Failed to spawn OCR monitoring thread");
+    }
 
-                    // Make sure to set ocr_ready to false if initialization fails
-                    if let Ok(mut ocr_ready) = state.ocr_ready.lock() {
-                        *ocr_ready = false;
-                    }
+    /// Run the monitor task until it exits for good, restarting it on failure
+    fn supervise_monitor(state: Arc<Self>) {
+        let mut backoff = RestartBackoff::new();
+        let mut restart = false;
+
+        loop {
+            state.supervisor.record_start(restart);
+            if restart {
+                metrics::MONITOR_RESTARTS.inc();
+            }
+            restart = true;
+
+            let started = std::time::Instant::now();
+            let outcome =
+                panic::catch_unwind(AssertUnwindSafe(|| Self::monitor_task(state.clone())));
+
+            // Whatever happened, the OCR engine of this run is gone
+            if let Ok(mut ocr_ready) = state.ocr_ready.lock() {
+                *ocr_ready = false;
+            }
+
+            let (message, panicked) = match outcome {
+                Ok(Ok(MonitorExit::Unsupported)) => {
+                    state.supervisor.record_stopped();
+                    return;
                 }
-            })
-            .expect("Failed to spawn OCR monitoring thread");
+                Ok(Ok(MonitorExit::Reinit)) => {
+                    info!("Re-initializing OCR monitoring task");
+                    backoff.reset();
+                    continue;
+                }
+                Ok(Err(e)) => (format!("{:#}", e), false),
+                Err(payload) => {
+                    state.clear_poison();
+                    (supervisor::panic_message(payload.as_ref()), true)
+                }
+            };
+
+            let delay = backoff.next_delay(started.elapsed());
+            error!(
+                "OCR monitoring task {}: {}, restarting in {}s",
+                if panicked { "panicked" } else { "failed" },
+                message,
+                delay.as_secs()
+            );
+            diagnostics::report_error("monitor", &message);
+            state.supervisor.record_failure(message, panicked, delay);
+
+            if state.supervisor.wait_for_restart(delay) {
+                info!("Re-initialization requested, restarting OCR monitoring task now");
+                backoff.reset();
+            }
+        }
+    }
+
+    /// Clear poisoned locks left behind by a panicking monitor
+    fn clear_poison(&self) {
+        self.regions.clear_poison();
+        self.ocr_results.clear_poison();
+        self.is_monitoring.clear_poison();
+        self.ocr_ready.clear_poison();
+        self.latest_screenshot.clear_poison();
+        self.capture_rates.clear_poison();
+        self.region_health.clear_poison();
+        self.text_history.clear_poison();
+        self.numeric_values.clear_poison();
     }
 
     /// Background task for monitoring and OCR processing
-    fn monitor_task(state: Arc<Self>) -> anyhow::Result<()> {
+    fn monitor_task(state: Arc<Self>) -> anyhow::Result<MonitorExit> {
         // Skip monitoring if screen capture is not supported
         if !ScreenCaptureService::is_supported() {
             warn!("Screen capture is not supported on this platform. Monitoring task will exit.");
-            return Ok(());
+            return Ok(MonitorExit::Unsupported);
         }
 
         // Initialize OCR service
-        let ocr_result = OcrService::new();
+        let mut ocr_service = match OcrService::new() {
+            Ok(ocr_service) => ocr_service,
+            Err(e) => {
+                error!("Failed to initialize OCR service: {}", e);
+                diagnostics::report_error("ocr_init", format!("{:#}", e));
 
-        match ocr_result {
-            Ok(mut ocr_service) => {
-                // Mark OCR as ready once initialized successfully
+                // Make sure OCR ready flag is set to false
                 if let Ok(mut ocr_ready) = state.ocr_ready.lock() {
-                    *ocr_ready = true;
-                    info!("OCR service initialized successfully");
-                } else {
-                    error!("Failed to update OCR ready status");
+                    *ocr_ready = false;
                 }
 
-                // Last processed text for comparison
-                let mut last_text = String::new();
-
-                // Store last captured screenshot data for change detection
-                let mut last_capture: Option<Vec<u8>> = None;
-
-                // Timestamp for tracking performance
-                let mut last_capture_time = std::time::Instant::now();
-
-                loop {
-                    // Check if monitoring is active
-                    let is_monitoring = match state.is_monitoring.lock() {
-                        Ok(guard) => *guard,
-                        Err(e) => {
-                            error!("Failed to lock monitoring state: {}", e);
-                            std::thread::sleep(std::time::Duration::from_secs(1));
-                            continue;
-                        }
-                    };
-
-                    if !is_monitoring {
-                        // Sleep and check again
-                        std::thread::sleep(std::time::Duration::from_millis(500));
-                        continue;
-                    }
+                return Err(e);
+            }
+        };
+
+        // Mark OCR as ready once initialized successfully
+        if let Ok(mut ocr_ready) = state.ocr_ready.lock() {
+            *ocr_ready = true;
+            info!(
+                "OCR service initialized successfully (engine: {})",
+                ocr_service.engine_kind().as_str()
+            );
+        } else {
+            error!("Failed to update OCR ready status");
+        }
+        state.supervisor.record_running();
 
-                    // Get the current region
-                    let current_region = match state.region.lock() {
-                        Ok(guard) => match guard.clone() {
-                            Some(region) => region,
-                            None => {
-                                // No region selected, sleep and check again
-                                std::thread::sleep(std::time::Duration::from_millis(500));
-                                continue;
-                            }
-                        },
-                        Err(e) => {
-                            error!("Failed to lock region: {}", e);
-                            std::thread::sleep(std::time::Duration::from_secs(1));
-                            continue;
-                        }
-                    };
-
-                    // Calculate time elapsed since last capture
-                    let elapsed = last_capture_time.elapsed();
-
-                    // Throttle capture frequency based on previous capture performance
-                    // If last capture took more than 200ms, we'll add some delay to prevent overloading
-                    if elapsed.as_millis() < 200 {
-                        let delay = std::time::Duration::from_millis(200) - elapsed;
-                        std::thread::sleep(delay);
-                    }
+        // Capture and OCR state of every region
+        let mut monitor = Monitor::new();
+
+        loop {
+            diagnostics::monitor_heartbeat();
+
+            // Drop the OCR engine and start over when asked to
+            if state.supervisor.take_reinit_request() {
+                return Ok(MonitorExit::Reinit);
+            }
 
-                    // Record start time of this capture
-                    last_capture_time = std::time::Instant::now();
+            // Check if monitoring is active
+            let is_monitoring = match state.is_monitoring.lock() {
+                Ok(guard) => *guard,
+                Err(e) => {
+                    error!("Failed to lock monitoring state: {}", e);
+                    std::thread::sleep(Duration::from_secs(1));
+                    continue;
+                }
+            };
 
-                    // Capture the screen region
-                    info!(
-                        "Capturing region: x={}, y={}, width={}, height={}",
-                        current_region.x,
-                        current_region.y,
-                        current_region.width,
-                        current_region.height
-                    );
+            if !is_monitoring {
+                // Start over when monitoring resumes, so every region is due
+                // at once and its first reading is published again
+                monitor = Monitor::new();
 
-                    let frame_result = ScreenCaptureService::capture_region(&current_region);
+                // Sleep and check again
+                std::thread::sleep(Duration::from_millis(500));
+                continue;
+            }
 
-                    if let Err(e) = frame_result {
-                        error!("Error capturing screen region: {}", e);
-                        std::thread::sleep(std::time::Duration::from_secs(1));
-                        continue;
-                    }
+            // Get the current regions
+            let regions = match state.regions.lock() {
+                Ok(guard) => guard.clone(),
+                Err(e) => {
+                    error!("Failed to lock regions: {}", e);
+                    std::thread::sleep(Duration::from_secs(1));
+                    continue;
+                }
+            };
 
-                    let frame = frame_result.unwrap();
-                    info!("Successfully captured frame");
+            // Sleep until the next region is due, but keep reacting to API changes
+            let wait = monitor.poll(&mut ocr_service, &regions, state.as_ref());
+            if !wait.is_zero() {
+                std::thread::sleep(wait);
+            }
+        }
+    }
+
+    /// Store a text change in the history and publish it
+    fn record_text_change(&self, change: TextChange) {
+        match self.text_history.lock() {
+            Ok(mut history) => {
+                let changes = history.entry(change.region_id.clone()).or_default();
+                if changes.len() == MAX_HISTORY_PER_REGION {
+                    changes.pop_front();
+                }
+                changes.push_back(change.clone());
+            }
+            Err(e) => error!("Failed to lock text history: {}", e),
+        }
 
-                    // Convert frame to PNG data for storage and comparison
-                    let png_data_result = ScreenCaptureService::to_png(&frame);
+        // Sending only fails when nobody is subscribed
+        let _ = self.text_events.send(change);
+    }
 
-                    if let Err(e) = png_data_result {
-                        error!("Error converting frame to PNG: {}", e);
-                        std::thread::sleep(std::time::Duration::from_secs(1));
-                        continue;
-                    }
+    /// Update the capture and OCR outcomes of a region
+    fn update_region_health(&self, id: &str, update: impl FnOnce(&mut RegionHealth)) {
+        match self.region_health.lock() {
+            Ok(mut health) => update(health.entry(id.to_string()).or_default()),
+            Err(e) => error!("Failed to lock region health: {}", e),
+        }
+    }
 
-                    let png_data = png_data_result.unwrap();
-                    info!("Converted frame to PNG: {} bytes", png_data.len());
+    /// Check whether a region is the one shown by the UI preview
+    fn is_primary_region(&self, id: &str) -> bool {
+        match self.regions.lock() {
+            Ok(regions) => Self::primary_region_of(&regions).is_some_and(|region| region.id == id),
+            Err(e) => {
+                error!("Failed to lock regions: {}", e);
+                false
+            }
+        }
+    }
 
-                    // Always store the latest screenshot regardless of changes
-                    // This ensures we always have screenshot data available for the frontend
-                    if let Ok(mut latest_screenshot) = state.latest_screenshot.lock() {
-                        *latest_screenshot = Some(png_data.clone());
-                        info!("Updated latest screenshot in state");
-                    } else {
-                        error!("Failed to update latest screenshot in state");
-                    }
+    /// Store a number read from a numeric region
+    fn record_numeric_value(
+        &self,
+        monitored: &MonitoredRegion,
+        value: f64,
+        timestamp: chrono::DateTime<chrono::Utc>,
+    ) {
+        let reading = NumericReading {
+            value,
+            confirmed_at: timestamp,
+        };
+
+        match self.numeric_values.lock() {
+            Ok(mut values) => {
+                let previous = values.insert(monitored.id.clone(), reading);
+                let changed = previous.is_some_and(|previous| previous.value != value);
+                let count_changes = monitored
+                    .numeric
+                    .as_ref()
+                    .is_some_and(|numeric| numeric.count_changes);
+
+                if changed && count_changes {
+                    metrics::REGION_VALUE_CHANGES
+                        .with_label_values(&[monitored.id.as_str()])
+                        .inc();
+                }
+            }
+            Err(e) => error!("Failed to lock numeric values: {}", e),
+        }
+    }
+
+    /// Drop the number of a region whose text no longer holds one
+    fn forget_numeric_value(&self, id: &str) {
+        match self.numeric_values.lock() {
+            Ok(mut values) => {
+                values.remove(id);
+            }
+            Err(e) => error!("Failed to lock numeric values: {}", e),
+        }
+    }
+
+    /// Mark the number of a region as still current
+    fn confirm_numeric_value(&self, id: &str, timestamp: chrono::DateTime<chrono::Utc>) {
+        match self.numeric_values.lock() {
+            Ok(mut values) => {
+                if let Some(reading) = values.get_mut(id) {
+                    reading.confirmed_at = timestamp;
+                }
+            }
+            Err(e) => error!("Failed to lock numeric values: {}", e),
+        }
+    }
+
+    /// Refresh the metrics that mirror application state
+    pub fn update_metrics(&self) {
+        match self.regions.lock() {
+            Ok(regions) => {
+                metrics::ACTIVE_REGIONS.set(regions.len() as i64);
+                self.update_numeric_metrics(&regions);
+            }
+            Err(e) => error!("Failed to lock regions: {}", e),
+        }
 
-                    // Check if the image has changed significantly
-                    let image_changed = match &last_capture {
-                        Some(last_data) => {
-                            // Simple hash comparison for quick change detection
-                            let current_hash = calculate_simple_hash(&png_data);
-                            let last_hash = calculate_simple_hash(last_data);
-                            current_hash != last_hash
-                        }
-                        None => true, // First capture
-                    };
-
-                    // If the image has changed or we need to extract text
-                    if image_changed {
-                        debug!("Screen region changed, performing OCR");
-
-                        // Update the last capture
-                        last_capture = Some(png_data.clone());
-
-                        // Extract text from the region
-                        let result = match ocr_service.extract_text_from_region(&current_region) {
-                            Ok(result) => result,
-                            Err(e) => {
-                                error!("Error extracting text: {}", e);
-                                std::thread::sleep(std::time::Duration::from_secs(1));
-                                continue;
-                            }
-                        };
-
-                        // Update state if text has changed
-                        if result.text != last_text {
-                            info!(
-                                "New text detected ({} characters): {}",
-                                result.text.len(),
-                                result.text
-                            );
-                            last_text = result.text.clone();
-
-                            match state.ocr_result.lock() {
-                                Ok(mut ocr_result) => {
-                                    *ocr_result = result;
-                                }
-                                Err(e) => {
-                                    error!("Failed to update OCR result: {}", e);
-                                }
-                            }
-                        }
-                    } else {
-                        debug!("No visual change detected in the monitored region");
+        match self.is_monitoring.lock() {
+            Ok(is_monitoring) => metrics::MONITORING.set(i64::from(*is_monitoring)),
+            Err(e) => error!("Failed to lock monitoring state: {}", e),
+        }
+
+        match self.capture_rates.lock() {
+            Ok(rates) => {
+                // Rebuild the series so removed regions disappear
+                metrics::CAPTURE_INTERVAL.reset();
+                for (id, rate) in rates.iter() {
+                    metrics::CAPTURE_INTERVAL
+                        .with_label_values(&[id.as_str(), "target"])
+                        .set(rate.interval_ms as f64 / 1000.0);
+                    if let Some(measured_ms) = rate.measured_interval_ms {
+                        metrics::CAPTURE_INTERVAL
+                            .with_label_values(&[id.as_str(), "measured"])
+                            .set(measured_ms as f64 / 1000.0);
                     }
+                }
+            }
+            Err(e) => error!("Failed to lock capture rates: {}", e),
+        }
 
-                    // Log performance metrics
-                    debug!(
-                        "OCR cycle completed in {}ms",
-                        last_capture_time.elapsed().as_millis()
-                    );
+        metrics::JOBS_QUEUED.set(self.jobs.queued() as i64);
+        metrics::JOBS_RUNNING.set(self.jobs.running() as i64);
+    }
+
+    /// Export the numbers of all numeric regions, dropping stale values
+    fn update_numeric_metrics(&self, regions: &[MonitoredRegion]) {
+        let values = match self.numeric_values.lock() {
+            Ok(values) => values,
+            Err(e) => {
+                error!("Failed to lock numeric values: {}", e);
+                return;
+            }
+        };
+
+        // Rebuild the series so removed regions and stale values disappear
+        metrics::REGION_VALUE.reset();
+        metrics::REGION_VALUE_STALE.reset();
+        metrics::REGION_VALUE_TIMESTAMP.reset();
+
+        let now = chrono::Utc::now();
+        for region in regions {
+            let Some(numeric) = &region.numeric else {
+                continue;
+            };
+            let labels = [region.id.as_str()];
+
+            let reading = values.get(&region.id);
+            let fresh = reading.is_some_and(|reading| {
+                (now - reading.confirmed_at).num_milliseconds() <= numeric.stale_after_ms as i64
+            });
+
+            if let Some(reading) = reading {
+                metrics::REGION_VALUE_TIMESTAMP
+                    .with_label_values(&labels)
+                    .set(reading.confirmed_at.timestamp_millis() as f64 / 1000.0);
+                if fresh {
+                    metrics::REGION_VALUE
+                        .with_label_values(&labels)
+                        .set(reading.value);
                 }
             }
+            metrics::REGION_VALUE_STALE
+                .with_label_values(&labels)
+                .set(i64::from(!fresh));
+        }
+    }
+
+    /// Pick the region shown by the single-region API and the UI preview
+    pub fn primary_region_of(regions: &[MonitoredRegion]) -> Option<&MonitoredRegion> {
+        regions
+            .iter()
+            .find(|region| region.id == MonitoredRegion::DEFAULT_ID)
+            .or_else(|| regions.first())
+    }
+
+    /// Get the region shown by the single-region API and the UI preview
+    pub fn primary_region(&self) -> Result<Option<MonitoredRegion>, String> {
+        match self.regions.lock() {
+            Ok(regions) => Ok(Self::primary_region_of(&regions).cloned()),
             Err(e) => {
-                error!("Failed to initialize OCR service: {}", e);
+                error!("Failed to lock regions: {}", e);
+                Err(format!("Failed to lock regions: {}", e))
+            }
+        }
+    }
 
-                // Make sure OCR ready flag is set to false
-                if let Ok(mut ocr_ready) = state.ocr_ready.lock() {
-                    *ocr_ready = false;
+    /// Add a region or replace the region with the same id
+    pub fn upsert_region(&self, region: MonitoredRegion) -> Result<(), String> {
+        match self.regions.lock() {
+            Ok(mut regions) => {
+                match regions.iter_mut().find(|existing| existing.id == region.id) {
+                    Some(existing) => *existing = region,
+                    None => regions.push(region),
                 }
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to lock regions: {}", e);
+                Err(format!("Failed to lock regions: {}", e))
+            }
+        }
+    }
 
-                return Err(e);
+    /// Remove a region and its result, returning whether it existed
+    pub fn remove_region(&self, id: &str) -> Result<bool, String> {
+        let removed = match self.regions.lock() {
+            Ok(mut regions) => {
+                let count = regions.len();
+                regions.retain(|region| region.id != id);
+                regions.len() != count
+            }
+            Err(e) => {
+                error!("Failed to lock regions: {}", e);
+                return Err(format!("Failed to lock regions: {}", e));
+            }
+        };
+
+        match self.numeric_values.lock() {
+            Ok(mut values) => {
+                values.remove(id);
+            }
+            Err(e) => error!("Failed to lock numeric values: {}", e),
+        }
+        match self.region_health.lock() {
+            Ok(mut health) => {
+                health.remove(id);
+            }
+            Err(e) => error!("Failed to lock region health: {}", e),
+        }
+        match self.text_history.lock() {
+            Ok(mut history) => {
+                history.remove(id);
+            }
+            Err(e) => error!("Failed to lock text history: {}", e),
+        }
+        let _ = metrics::REGION_VALUE_CHANGES.remove_label_values(&[id]);
+        let _ = metrics::FRAMES_UNCHANGED.remove_label_values(&[id]);
+
+        match self.ocr_results.lock() {
+            Ok(mut results) => {
+                results.remove(id);
+                Ok(removed)
+            }
+            Err(e) => {
+                error!("Failed to lock OCR results: {}", e);
+                Err(format!("Failed to lock OCR results: {}", e))
             }
         }
     }
 
+    /// Store the latest OCR result of a region
+    pub fn set_result(&self, id: &str, result: OcrResult) -> Result<(), String> {
+        match self.ocr_results.lock() {
+            Ok(mut results) => {
+                results.insert(id.to_string(), result);
+                Ok(())
+            }
+            Err(e) => Err(format!("Failed to lock OCR results: {}", e)),
+        }
+    }
+
     pub fn clear_current_result(&self) -> Result<(), String> {
-        match self.ocr_result.lock() {
-            Ok(mut ocr_result) => {
+        match self.ocr_results.lock() {
+            Ok(mut results) => {
                 // Don't clear the entire result - keep the timestamp
                 // But clear the text to indicate monitoring has stopped
-                ocr_result.text = String::new();
-                ocr_result.timestamp = chrono::Utc::now();
+                for ocr_result in results.values_mut() {
+                    ocr_result.text = String::new();
+                    ocr_result.timestamp = chrono::Utc::now();
+                }
                 Ok(())
             }
             Err(e) => {
@@ -284,66 +567,132 @@ impl AppState {
     }
 }
 
-fn calculate_simple_hash(data: &[u8]) -> u64 {
-    let mut hash: u64 = 0;
+impl MonitorSink for AppState {
+    fn display_captured(&self, _display_id: u32, capture_time: Duration) {
+        metrics::CAPTURE_DURATION.observe(capture_time.as_secs_f64());
+    }
+
+    fn region_failed(&self, region: &MonitoredRegion, kind: RegionErrorKind, error: &str) {
+        self.update_region_health(&region.id, |health| {
+            health.record_failure(kind, error.to_string())
+        });
+    }
 
-    // Sample pixels at regular intervals for quick comparison
-    let stride = data.len().max(1000) / 1000; // Sample at most 1000 points
+    fn frame(&self, frame: &RegionFrame<'_>) {
+        let id = frame.region.id.as_str();
+        metrics::PNG_ENCODE_DURATION.observe(frame.encode_time.as_secs_f64());
 
-    for i in (0..data.len()).step_by(stride) {
-        if i < data.len() {
-            hash = hash.wrapping_add((data[i] as u64).wrapping_mul(i as u64 + 1));
+        // Always store the latest screenshot of the primary region regardless of changes
+        // This ensures we always have screenshot data available for the frontend
+        if self.is_primary_region(id) {
+            if let Ok(mut latest_screenshot) = self.latest_screenshot.lock() {
+                *latest_screenshot = Some(frame.png_data.to_vec());
+            } else {
+                error!("Failed to update latest screenshot in state");
+            }
+        }
+
+        match &frame.outcome {
+            FrameOutcome::Unchanged => {
+                metrics::FRAMES_UNCHANGED.with_label_values(&[id]).inc();
+                self.update_region_health(id, |health| {
+                    health.record_success(frame.timestamp, false)
+                });
+            }
+            FrameOutcome::Read { .. } => {
+                self.update_region_health(id, |health| health.record_success(frame.timestamp, true))
+            }
+            FrameOutcome::Failed(error) => self.update_region_health(id, |health| {
+                health.record_failure(RegionErrorKind::Ocr, error.clone())
+            }),
+        }
+
+        self.recorder.record(frame);
+    }
+
+    fn reading(&self, reading: Reading<'_>) {
+        let id = reading.region.id.as_str();
+        if reading.region.numeric.is_some() {
+            match reading.value {
+                Some(value) => {
+                    self.record_numeric_value(reading.region, value, reading.result.timestamp)
+                }
+                // The old number isn't on screen anymore, don't keep confirming it
+                None => self.forget_numeric_value(id),
+            }
+        }
+
+        if let Some(change) = reading.change {
+            self.record_text_change(change);
+            if let Err(e) = self.set_result(id, reading.result) {
+                error!("Failed to update OCR result: {}", e);
+            }
         }
     }
 
-    hash
+    fn reading_confirmed(
+        &self,
+        region: &MonitoredRegion,
+        timestamp: chrono::DateTime<chrono::Utc>,
+    ) {
+        // An unchanged reading still shows the number read before
+        if region.numeric.is_some() {
+            self.confirm_numeric_value(&region.id, timestamp);
+        }
+    }
+
+    fn cycle_finished(&self, rates: &HashMap<String, CaptureRate>, duration: Duration) {
+        match self.capture_rates.lock() {
+            Ok(mut current) => *current = rates.clone(),
+            Err(e) => error!("Failed to lock capture rates: {}", e),
+        }
+
+        metrics::MONITOR_CYCLE_DURATION.observe(duration.as_secs_f64());
+    }
 }
 
-impl Clone for AppState {
-    fn clone(&self) -> Self {
-        Self {
-            region: Mutex::new(match self.region.lock() {
-                Ok(guard) => guard.clone(),
-                Err(_) => {
-                    warn!("Failed to lock region during clone, using empty value");
-                    None
-                }
-            }),
-            ocr_result: Mutex::new(match self.ocr_result.lock() {
-                Ok(guard) => guard.clone(),
-                Err(_) => {
-                    warn!("Failed to lock ocr_result during clone, using empty value");
-                    OcrResult::empty()
-                }
-            }),
-            is_monitoring: Mutex::new(match self.is_monitoring.lock() {
-                Ok(guard) => *guard,
-                Err(_) => {
-                    warn!("Failed to lock is_monitoring during clone, using default value");
-                    false
-                }
-            }),
-            ocr_ready: Mutex::new(match self.ocr_ready.lock() {
-                Ok(guard) => *guard,
-                Err(_) => {
-                    warn!("Failed to lock ocr_ready during clone, using default value");
-                    false
-                }
-            }),
-            latest_screenshot: Mutex::new(match self.latest_screenshot.lock() {
-                Ok(guard) => guard.clone(),
-                Err(_) => {
-                    warn!("Failed to lock latest_screenshot during clone, using empty value");
-                    None
-                }
-            }),
-            last_screenshot_time: Mutex::new(match self.last_screenshot_time.lock() {
-                Ok(guard) => *guard,
-                Err(_) => {
-                    warn!("Failed to lock last_screenshot_time during clone, using current time");
-                    Instant::now()
-                }
-            }),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{NumericExport, Region};
+    use crate::services::recorder::{DEFAULT_MAX_RECORDING_BYTES, DEFAULT_MAX_RECORDING_SESSIONS};
+
+    fn reading<'a>(region: &'a MonitoredRegion, text: &str, value: Option<f64>) -> Reading<'a> {
+        Reading {
+            region,
+            result: OcrResult::new(text.to_string()),
+            change: None,
+            value,
         }
     }
+
+    #[test]
+    fn test_unparseable_text_drops_number() {
+        let state = AppState::new(
+            JobQueue::new(1, 1),
+            SessionRecorder::new(
+                "/nonexistent",
+                DEFAULT_MAX_RECORDING_BYTES,
+                DEFAULT_MAX_RECORDING_SESSIONS,
+            ),
+        );
+        let mut region = MonitoredRegion::new("a", Region::new(0, 0, 10, 10));
+        region.numeric = Some(NumericExport::default());
+        let value = |state: &AppState| {
+            state
+                .numeric_values
+                .lock()
+                .unwrap()
+                .get("a")
+                .map(|reading| reading.value)
+        };
+
+        state.reading(reading(&region, "42", Some(42.0)));
+        state.reading_confirmed(&region, chrono::Utc::now());
+        assert_eq!(value(&state), Some(42.0));
+
+        state.reading(reading(&region, "--", None));
+        state.reading_confirmed(&region, chrono::Utc::now());
+        assert_eq!(value(&state), None);
+    }
 }
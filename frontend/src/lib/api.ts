@@ -1,9 +1,36 @@
-import { StatusResponse, Region } from "../types";
+import { StatusResponse, Region, ErrorResponse } from "../types";
+
+export class ApiError extends Error {
+  constructor(
+    message: string,
+    public status: number,
+    public code?: string,
+  ) {
+    super(message);
+    this.name = "ApiError";
+  }
+}
+
+async function apiError(
+  response: Response,
+  action: string,
+): Promise<ApiError> {
+  try {
+    const body: ErrorResponse = await response.json();
+    return new ApiError(
+      `${action}: ${body.error.message}`,
+      response.status,
+      body.error.code,
+    );
+  } catch {
+    return new ApiError(`${action}: ${response.statusText}`, response.status);
+  }
+}
 
 export async function takeScreenshot(): Promise<string> {
   const response = await fetch("/api/screenshot");
   if (!response.ok) {
-    throw new Error(`Failed to take screenshot: ${response.statusText}`);
+    throw await apiError(response, "Failed to take screenshot");
   }
   const blob = await response.blob();
   return URL.createObjectURL(blob);
@@ -12,7 +39,7 @@ export async function takeScreenshot(): Promise<string> {
 export async function getStatus(): Promise<StatusResponse> {
   const response = await fetch("/api/status");
   if (!response.ok) {
-    throw new Error(`Error fetching status: ${response.statusText}`);
+    throw await apiError(response, "Error fetching status");
   }
   return response.json();
 }
@@ -27,7 +54,7 @@ export async function setRegion(region: Region): Promise<void> {
   });
 
   if (!response.ok) {
-    throw new Error(`Failed to set region: ${response.statusText}`);
+    throw await apiError(response, "Failed to set region");
   }
 }
 
@@ -37,7 +64,7 @@ export async function startMonitoring(): Promise<void> {
   });
 
   if (!response.ok) {
-    throw new Error(`Failed to start monitoring: ${response.statusText}`);
+    throw await apiError(response, "Failed to start monitoring");
   }
 }
 
@@ -47,7 +74,7 @@ export async function stopMonitoring(): Promise<void> {
   });
 
   if (!response.ok) {
-    throw new Error(`Failed to stop monitoring: ${response.statusText}`);
+    throw await apiError(response, "Failed to stop monitoring");
   }
 }
 
@@ -60,9 +87,7 @@ export async function getLatestScreenshot(): Promise<string | null> {
   }
 
   if (!response.ok) {
-    throw new Error(
-      `Error fetching screenshot: ${response.status} ${response.statusText}`,
-    );
+    throw await apiError(response, "Error fetching screenshot");
   }
 
   const blob = await response.blob();
@@ -1,8 +1,17 @@
+export type CoordinateSpace = "physical" | "logical" | "fraction";
+
+export type Anchor = "top_left" | "bottom_right" | "center" | "percentage";
+
 export interface Region {
   x: number;
   y: number;
   width: number;
   height: number;
+  display_id?: number;
+  space?: CoordinateSpace;
+  anchor?: Anchor;
+  reference?: { width: number; height: number };
+  clamp?: boolean;
 }
 
 export interface TextHistoryItem {
@@ -28,6 +37,13 @@ export interface StatusResponse {
   has_screenshot?: boolean;
 }
 
+export interface ErrorResponse {
+  error: {
+    code: string;
+    message: string;
+  };
+}
+
 export type NotificationType = "success" | "error" | "warning" | "info";
 
 export interface Notification {